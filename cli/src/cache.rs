@@ -0,0 +1,669 @@
+use crate::platform::platform;
+use crate::tmp::TempDir;
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+// Resolution order for version lists and downloaded artifacts is memory -> disk cache -> remote.
+// The per-invocation policy (--cache-only / --offline / --no-cache / --refresh) decides which tiers are read
+// and whether remote results are written back. The disk cache lives in ~/.tlk/cache (override
+// with TLK_CACHE_DIR) and is shared by every project: versions/<tool>.json, downloads/<sha256 of
+// content> (+ <sha256>.json holding the last url and tool name), urls/<sha256 of url> naming
+// the content a url served and etags/<sha256 of url> remembering a listing response with its ETag
+// so stale version lists are revalidated with If-None-Match instead of re-downloaded. Downloads are found by their expected checksum when one is known (so
+// reinstalls, other projects and mirrors of the same file hit), otherwise by url. A download's
+// mtime is its last use, which drives LRU eviction when [cache] max_size is set; hit/miss
+// counters live in stats.json.
+//
+// [cache] archive = true guards against upstream deleting a release: artifacts that a lock write
+// records at a new version are kept even under --no-cache, marked "archived" in their .json so
+// eviction skips them, and copied to [cache] archive_to (a shared directory, or an http(s) base
+// URL taking PUT <base>/<sha256>) when set. Installs whose sources all fail fall back to
+// <archive_to>/<sha256> when the artifact's sha256 is pinned.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    /// Fresh disk entries first, then remote (stored back); stale version lists are used only
+    /// when the remote is unreachable.
+    #[default]
+    Default,
+    /// Never touch the network; fail when an entry is missing.
+    CacheOnly,
+    /// Bypass the disk cache entirely (neither read nor written).
+    NoCache,
+    /// Always go to the remote and overwrite the disk cache.
+    Refresh,
+}
+
+static POLICY: OnceCell<CachePolicy> = OnceCell::new();
+static MAX_SIZE: OnceCell<String> = OnceCell::new();
+static MAX_BYTES: OnceCell<Option<u64>> = OnceCell::new();
+
+pub fn set_policy(policy: CachePolicy) {
+    let _ = POLICY.set(policy);
+}
+
+pub fn policy() -> CachePolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+struct Archive {
+    to: Option<String>,
+}
+
+static ARCHIVE: OnceCell<Archive> = OnceCell::new();
+
+// Artifacts fetched this run while archiving: tool -> (url, sha256).
+static FETCHED: Mutex<Option<HashMap<String, (String, String)>>> = Mutex::new(None);
+
+pub fn set_archive(enabled: Option<bool>, to: Option<String>) {
+    if enabled == Some(true) || (enabled.is_none() && to.is_some()) {
+        let to = to.map(|t| t.trim_end_matches('/').to_string());
+        let _ = ARCHIVE.set(Archive { to });
+    }
+}
+
+/// Cap ("5GB") enforced by LRU eviction after each new download. It is parsed on first use, so a
+/// bad value only affects commands that write to the cache.
+pub fn set_max_size(size: String) {
+    let _ = MAX_SIZE.set(size);
+}
+
+fn max_size() -> Option<u64> {
+    *MAX_BYTES.get_or_init(|| {
+        let raw = MAX_SIZE.get()?;
+        match parse_size(raw) {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                eprintln!("Warning: [cache] max_size: {e:#}; the cache is not trimmed");
+                None
+            }
+        }
+    })
+}
+
+// Release lists move; artifacts at a URL are treated as immutable.
+const VERSION_LIST_FRESH: Duration = Duration::from_secs(15 * 60);
+
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("TLK_CACHE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    platform().home_dir().map(|h| h.join(".tlk").join("cache"))
+}
+
+/// Version list for `name`, resolved through the disk cache according to the policy.
+pub fn versions(name: &str, fetch: impl FnOnce() -> Result<Vec<String>>) -> Result<Vec<String>> {
+    let path = cache_dir().map(|d| d.join("versions").join(format!("{name}.json")));
+    let read = |max_age: Option<Duration>| -> Option<Vec<String>> {
+        let path = path.as_ref()?;
+        if let Some(max_age) = max_age {
+            let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age > max_age {
+                return None;
+            }
+        }
+        serde_json::from_slice(&fs::read(path).ok()?).ok()
+    };
+    let store = |list: &Vec<String>| {
+        if let (Some(path), Ok(data)) = (&path, serde_json::to_vec(list)) {
+            write_atomic(path, &data);
+        }
+    };
+    match policy() {
+        CachePolicy::CacheOnly => read(None)
+            .ok_or_else(|| anyhow!("no cached version list for {name} ({})", cache_only_flag())),
+        CachePolicy::NoCache => fetch(),
+        CachePolicy::Refresh => {
+            let list = fetch()?;
+            store(&list);
+            Ok(list)
+        }
+        CachePolicy::Default => {
+            if let Some(list) = read(Some(VERSION_LIST_FRESH)) {
+                return Ok(list);
+            }
+            match fetch() {
+                Ok(list) => {
+                    store(&list);
+                    Ok(list)
+                }
+                Err(e) => match read(None) {
+                    Some(list) => {
+                        eprintln!("Warning: using cached version list for {name} ({e})");
+                        Ok(list)
+                    }
+                    None => Err(e),
+                },
+            }
+        }
+    }
+}
+
+/// A downloaded file on disk together with its size and sha256 (hex). Fresh downloads live in a
+/// scratch directory that is removed when the artifact drops; cached ones are read in place.
+pub struct Artifact {
+    file: File,
+    pub size: u64,
+    pub sha256: String,
+    _scratch: Option<TempDir>,
+}
+
+impl Artifact {
+    /// A download written to `file` (inside `scratch`), hashed while it was written.
+    pub fn downloaded(file: File, size: u64, sha256: String, scratch: TempDir) -> Self {
+        Artifact {
+            file,
+            size,
+            sha256,
+            _scratch: Some(scratch),
+        }
+    }
+
+    pub fn from_cached(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut hasher = Sha256::new();
+        let size = std::io::copy(&mut file, &mut hasher)?;
+        Ok(Artifact {
+            file,
+            size,
+            sha256: hex::encode(hasher.finalize()),
+            _scratch: None,
+        })
+    }
+
+    /// The file's content from the start. Readers share one cursor, so use them one at a time.
+    pub fn reader(&self) -> Result<File> {
+        let mut file = self.file.try_clone()?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(file)
+    }
+}
+
+/// The artifact at `url` (downloaded for `tool`, expected to hash to `sha256` when known),
+/// resolved through the disk cache according to the policy.
+pub fn artifact(
+    tool: &str,
+    url: &str,
+    sha256: Option<&str>,
+    fetch: impl FnOnce() -> Result<Artifact>,
+) -> Result<Artifact> {
+    let by_content = sha256.and_then(|sha| blob_path(&sha.to_ascii_lowercase()));
+    let paths: Vec<PathBuf> = by_content.into_iter().chain(download_path(url)).collect();
+    let cached = || {
+        for path in &paths {
+            let Ok(artifact) = Artifact::from_cached(path) else {
+                continue;
+            };
+            // A blob whose content no longer matches its name is ignored (and later replaced).
+            let named = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            if named.len() == 64 && named != artifact.sha256 && !is_legacy(path, url) {
+                continue;
+            }
+            touch(path);
+            record_access(true);
+            return Some(artifact);
+        }
+        None
+    };
+    let store = |artifact: &Artifact| store(tool, url, artifact);
+    let fetch = || {
+        record_access(false);
+        fetch()
+    };
+    match policy() {
+        CachePolicy::CacheOnly => cached()
+            .ok_or_else(|| anyhow!("{url} is not in the download cache ({})", cache_only_flag())),
+        CachePolicy::NoCache => fetch(),
+        CachePolicy::Refresh => {
+            let artifact = fetch()?;
+            store(&artifact);
+            Ok(artifact)
+        }
+        CachePolicy::Default => {
+            if let Some(artifact) = cached() {
+                return Ok(artifact);
+            }
+            let artifact = fetch()?;
+            store(&artifact);
+            Ok(artifact)
+        }
+    }
+}
+
+// Names the flag behind a cache-only miss, with what fills the cache.
+fn cache_only_flag() -> &'static str {
+    if crate::http::offline() {
+        "--offline; run `tlk install` once with network access to fill it"
+    } else {
+        "--cache-only"
+    }
+}
+
+/// Write (or overwrite) the cached download for `url`, unless the cache is bypassed.
+pub fn store(tool: &str, url: &str, artifact: &Artifact) {
+    if policy() == CachePolicy::NoCache {
+        return;
+    }
+    write_blob(tool, url, artifact);
+}
+
+fn write_blob(tool: &str, url: &str, artifact: &Artifact) {
+    let sha = &artifact.sha256;
+    let (Some(path), Some(index)) = (blob_path(sha), url_index(url)) else {
+        return;
+    };
+    let Ok(mut reader) = artifact.reader() else {
+        return;
+    };
+    replace_atomic(&path, |tmp| {
+        let mut out = File::create(tmp)?;
+        std::io::copy(&mut reader, &mut out).map(|_| ())
+    });
+    let archived = is_archived(&path);
+    let meta = serde_json::json!({ "url": url, "tool": tool, "archived": archived });
+    write_atomic(&path.with_extension("json"), meta.to_string().as_bytes());
+    write_atomic(&index, sha.as_bytes());
+    if let Some(max) = max_size() {
+        let _ = evict(None, Some(max));
+    }
+}
+
+fn is_archived(path: &Path) -> bool {
+    fs::read(path.with_extension("json"))
+        .ok()
+        .and_then(|b| serde_json::from_slice::<serde_json::Value>(&b).ok())
+        .and_then(|v| v.get("archived")?.as_bool())
+        .unwrap_or(false)
+}
+
+/// Remember an artifact fetched for `tool` so a lock write can archive it; with archiving on it
+/// is cached even when the cache is otherwise bypassed.
+pub fn note_fetched(tool: &str, url: &str, artifact: &Artifact) {
+    if ARCHIVE.get().is_none() {
+        return;
+    }
+    if policy() == CachePolicy::NoCache {
+        write_blob(tool, url, artifact);
+    }
+    if let Ok(mut fetched) = FETCHED.lock() {
+        fetched
+            .get_or_insert_with(HashMap::new)
+            .insert(tool.to_string(), (url.to_string(), artifact.sha256.clone()));
+    }
+}
+
+/// Archive the artifacts fetched this run for `tools` (the lock entries a write added or moved
+/// to a new version). Failures are warnings: the lock itself was written.
+pub fn archive<'a>(tools: impl IntoIterator<Item = &'a str>) {
+    let Some(settings) = ARCHIVE.get() else {
+        return;
+    };
+    let fetched = FETCHED
+        .lock()
+        .ok()
+        .and_then(|f| f.clone())
+        .unwrap_or_default();
+    for tool in tools {
+        let Some((url, sha)) = fetched.get(tool) else {
+            continue;
+        };
+        let Some(path) = blob_path(sha).filter(|p| p.is_file()) else {
+            eprintln!("Warning: {tool}: {url} is not in the download cache; not archived");
+            continue;
+        };
+        let meta = serde_json::json!({ "url": url, "tool": tool, "archived": true });
+        write_atomic(&path.with_extension("json"), meta.to_string().as_bytes());
+        match &settings.to {
+            None => println!("Archived {tool} (sha256 {sha})"),
+            Some(to) => match push(&path, to, sha) {
+                Ok(()) => println!(
+                    "Archived {tool} (sha256 {sha}) to {}",
+                    crate::http::redact(to)
+                ),
+                Err(e) => eprintln!(
+                    "Warning: {tool}: archiving to {} failed: {e:#}",
+                    crate::http::redact(to)
+                ),
+            },
+        }
+    }
+}
+
+fn push(path: &Path, to: &str, sha: &str) -> Result<()> {
+    if to.starts_with("http://") || to.starts_with("https://") {
+        let body = File::open(path)?;
+        let client = crate::http::client()?;
+        crate::http::send(client.put(format!("{to}/{sha}")).body(body))?.error_for_status()?;
+        return Ok(());
+    }
+    let dest = Path::new(to).join(sha);
+    if dest.is_file() {
+        return Ok(());
+    }
+    fs::create_dir_all(to)?;
+    let tmp = Path::new(to).join(format!(".{sha}.{}.tmp", std::process::id()));
+    fs::copy(path, &tmp)?;
+    fs::rename(&tmp, &dest)?;
+    Ok(())
+}
+
+/// Where [cache] archive_to keeps the artifact with `sha256`: a URL, or a path when it exists.
+pub fn archived_location(sha256: &str) -> Option<String> {
+    let to = ARCHIVE.get()?.to.as_deref()?;
+    let sha = sha256.to_ascii_lowercase();
+    if to.starts_with("http://") || to.starts_with("https://") {
+        return Some(format!("{to}/{sha}"));
+    }
+    let path = Path::new(to).join(&sha);
+    path.is_file().then(|| path.to_string_lossy().into_owned())
+}
+
+fn url_key(url: &str) -> String {
+    hex::encode(Sha256::digest(url.as_bytes()))
+}
+
+fn blob_path(sha256: &str) -> Option<PathBuf> {
+    cache_dir().map(|d| d.join("downloads").join(sha256))
+}
+
+fn url_index(url: &str) -> Option<PathBuf> {
+    cache_dir().map(|d| d.join("urls").join(url_key(url)))
+}
+
+// Caches written before content addressing stored downloads under the url's hash.
+fn is_legacy(path: &Path, url: &str) -> bool {
+    path.file_name().and_then(|n| n.to_str()) == Some(url_key(url).as_str())
+}
+
+/// Cached download for `url`: the content its last fetch produced, else a pre-content-addressing
+/// entry stored under the url's hash.
+fn download_path(url: &str) -> Option<PathBuf> {
+    let indexed = url_index(url)
+        .and_then(|i| fs::read_to_string(i).ok())
+        .and_then(|sha| blob_path(sha.trim()))
+        .filter(|p| p.is_file());
+    indexed.or_else(|| {
+        cache_dir()
+            .map(|d| d.join("downloads").join(url_key(url)))
+            .filter(|p| p.is_file())
+    })
+}
+
+/// Size of the cached download for `url`, if present (regardless of policy).
+pub fn stored_size(url: &str) -> Option<u64> {
+    fs::metadata(download_path(url)?).ok().map(|m| m.len())
+}
+
+/// Whether `artifact(url)` would be served from disk under the current policy.
+pub fn would_hit(url: &str) -> bool {
+    matches!(policy(), CachePolicy::Default | CachePolicy::CacheOnly) && stored_size(url).is_some()
+}
+
+/// One cached download.
+pub struct Entry {
+    pub path: PathBuf,
+    pub tool: String,
+    /// URL of the last download stored here
+    pub url: String,
+    pub size: u64,
+    pub last_used: SystemTime,
+    /// Kept by [cache] archive; never evicted
+    pub archived: bool,
+}
+
+impl Entry {
+    /// Content sha256 the entry is stored under; None for entries of the older url-keyed layout.
+    pub fn expected_sha256(&self) -> Option<&str> {
+        let name = self.path.file_name()?.to_str()?;
+        (name != url_key(&self.url)).then_some(name)
+    }
+
+    /// Recompute the sha256 of the stored file.
+    pub fn hash(&self) -> Result<String> {
+        let mut file = fs::File::open(&self.path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        fs::remove_file(&self.path)?;
+        let _ = fs::remove_file(self.path.with_extension("json"));
+        Ok(())
+    }
+}
+
+pub fn entries() -> Vec<Entry> {
+    let Some(dir) = cache_dir().map(|d| d.join("downloads")) else {
+        return Vec::new();
+    };
+    let Ok(read) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    for entry in read.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || path.extension().is_some() {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let info: serde_json::Value = fs::read(path.with_extension("json"))
+            .ok()
+            .and_then(|b| serde_json::from_slice(&b).ok())
+            .unwrap_or_default();
+        let field = |key: &str| {
+            info.get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or("?")
+                .to_string()
+        };
+        out.push(Entry {
+            tool: field("tool"),
+            url: field("url"),
+            size: meta.len(),
+            last_used: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            archived: info.get("archived").and_then(|v| v.as_bool()) == Some(true),
+            path,
+        });
+    }
+    out
+}
+
+/// Remove downloads unused for longer than `max_age`, then the least recently used ones until
+/// the total fits `max_size`. Returns (entries removed, bytes freed).
+pub fn evict(max_age: Option<Duration>, max_size: Option<u64>) -> Result<(usize, u64)> {
+    let mut entries: Vec<Entry> = entries().into_iter().filter(|e| !e.archived).collect();
+    entries.sort_by_key(|e| e.last_used);
+    let now = SystemTime::now();
+    let mut total: u64 = entries.iter().map(|e| e.size).sum();
+    let (mut removed, mut freed) = (0, 0);
+    for e in entries {
+        let expired =
+            max_age.is_some_and(|age| now.duration_since(e.last_used).unwrap_or_default() > age);
+        let over = max_size.is_some_and(|max| total > max);
+        if !expired && !over {
+            continue;
+        }
+        e.remove()?;
+        total -= e.size;
+        removed += 1;
+        freed += e.size;
+    }
+    Ok((removed, freed))
+}
+
+/// A remote response remembered together with its ETag.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Validated {
+    pub etag: String,
+    pub body: String,
+}
+
+fn validated_path(url: &str) -> Option<PathBuf> {
+    let key = hex::encode(Sha256::digest(url.as_bytes()));
+    cache_dir().map(|d| d.join("etags").join(key))
+}
+
+/// The last response of `url` with its ETag, for a conditional request. None under --no-cache.
+pub fn validated(url: &str) -> Option<Validated> {
+    if policy() == CachePolicy::NoCache {
+        return None;
+    }
+    serde_json::from_slice(&fs::read(validated_path(url)?).ok()?).ok()
+}
+
+pub fn store_validated(url: &str, etag: &str, body: &str) {
+    if policy() == CachePolicy::NoCache {
+        return;
+    }
+    let entry = Validated {
+        etag: etag.to_string(),
+        body: body.to_string(),
+    };
+    if let (Some(path), Ok(data)) = (validated_path(url), serde_json::to_vec(&entry)) {
+        write_atomic(&path, &data);
+    }
+}
+
+/// Remove every cached download and version list, or only those of `tool`. Returns (downloads
+/// removed, bytes freed).
+pub fn clear(tool: Option<&str>) -> Result<(usize, u64)> {
+    let (mut removed, mut freed) = (0, 0);
+    for e in entries() {
+        if tool.is_some_and(|t| t != e.tool) {
+            continue;
+        }
+        e.remove()?;
+        removed += 1;
+        freed += e.size;
+    }
+    let Some(dir) = cache_dir() else {
+        return Ok((removed, freed));
+    };
+    match tool {
+        Some(tool) => {
+            let _ = fs::remove_file(dir.join("versions").join(format!("{tool}.json")));
+        }
+        None => {
+            for sub in ["urls", "versions", "etags"] {
+                let path = dir.join(sub);
+                if path.exists() {
+                    fs::remove_dir_all(&path)?;
+                }
+            }
+            let _ = fs::remove_file(dir.join("stats.json"));
+        }
+    }
+    Ok((removed, freed))
+}
+
+/// Download cache hit / miss counters accumulated across runs.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Counters {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+pub fn counters() -> Counters {
+    cache_dir()
+        .and_then(|d| fs::read(d.join("stats.json")).ok())
+        .and_then(|b| serde_json::from_slice(&b).ok())
+        .unwrap_or_default()
+}
+
+fn record_access(hit: bool) {
+    let Some(path) = cache_dir().map(|d| d.join("stats.json")) else {
+        return;
+    };
+    let mut c = counters();
+    if hit {
+        c.hits += 1;
+    } else {
+        c.misses += 1;
+    }
+    if let Ok(data) = serde_json::to_vec(&c) {
+        write_atomic(&path, &data);
+    }
+}
+
+fn touch(path: &Path) {
+    if let Ok(f) = fs::File::options().write(true).open(path) {
+        let _ = f.set_modified(SystemTime::now());
+    }
+}
+
+/// Parse sizes like "5GB", "500M", "1024" (binary multiples).
+pub fn parse_size(s: &str) -> Result<u64> {
+    let t = s.trim().to_ascii_uppercase();
+    let digits = t.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == ' ');
+    let unit = t[digits.len()..]
+        .trim()
+        .trim_end_matches("IB")
+        .trim_end_matches('B');
+    let n: f64 = digits.parse().map_err(|_| anyhow!("invalid size '{s}'"))?;
+    let mult: u64 = match unit {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(anyhow!("invalid size unit in '{s}'")),
+    };
+    Ok((n * mult as f64) as u64)
+}
+
+/// Parse ages like "90d", "12h", "30m", "45s".
+pub fn parse_age(s: &str) -> Result<Duration> {
+    let t = s.trim();
+    let (num, unit) = t.split_at(t.len().saturating_sub(1));
+    let n: u64 = num.parse().map_err(|_| anyhow!("invalid age '{s}'"))?;
+    let secs = match unit {
+        "d" => n * 86_400,
+        "h" => n * 3_600,
+        "m" => n * 60,
+        "s" => n,
+        _ => return Err(anyhow!("invalid age unit in '{s}' (use d, h, m or s)")),
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+fn write_atomic(path: &Path, data: &[u8]) {
+    replace_atomic(path, |tmp| fs::write(tmp, data));
+}
+
+// Cache writes are best effort: a read-only or full cache never fails the command.
+fn replace_atomic(path: &Path, write: impl FnOnce(&Path) -> std::io::Result<()>) {
+    let Some(parent) = path.parent() else {
+        return;
+    };
+    if fs::create_dir_all(parent).is_err() {
+        return;
+    }
+    static SEQ: AtomicUsize = AtomicUsize::new(0);
+    let tmp = parent.join(format!(
+        ".{}.{}-{}.tmp",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        std::process::id(),
+        SEQ.fetch_add(1, Ordering::Relaxed)
+    ));
+    if write(&tmp).is_err() || fs::rename(&tmp, path).is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+}
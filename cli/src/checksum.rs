@@ -0,0 +1,88 @@
+use crate::cache;
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha512};
+
+// Checksums in tlk.toml and tlk.lock are "<algo>:<hex>" or bare hex, which is sha256 (the only
+// form older locks hold). The download cache and TOFU pins stay keyed by sha256, computed while
+// downloading; other algorithms cost one more pass over the artifact when it is verified.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algo {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl Algo {
+    fn name(self) -> &'static str {
+        match self {
+            Algo::Sha256 => "sha256",
+            Algo::Sha512 => "sha512",
+            Algo::Blake3 => "blake3",
+        }
+    }
+
+    fn hex_len(self) -> usize {
+        match self {
+            Algo::Sha512 => 128,
+            Algo::Sha256 | Algo::Blake3 => 64,
+        }
+    }
+}
+
+/// Split a checksum into its algorithm and hex digest, checking the digest's length.
+pub fn parse(sum: &str) -> Result<(Algo, &str)> {
+    let (algo, hex) = match sum.split_once(':') {
+        Some((algo, hex)) => {
+            let algo = match algo.to_ascii_lowercase().as_str() {
+                "sha256" => Algo::Sha256,
+                "sha512" => Algo::Sha512,
+                "blake3" => Algo::Blake3,
+                other => {
+                    bail!("unknown checksum algorithm '{other}' (use sha256, sha512 or blake3)")
+                }
+            };
+            (algo, hex)
+        }
+        None => (Algo::Sha256, sum),
+    };
+    if hex.len() != algo.hex_len() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!(
+            "invalid {} checksum '{sum}' (expected {} hex digits)",
+            algo.name(),
+            algo.hex_len()
+        );
+    }
+    Ok((algo, hex))
+}
+
+/// The sha256 hex digest of `sum`, when it is one (cache lookups, package manifests).
+pub fn sha256_hex(sum: &str) -> Option<&str> {
+    match parse(sum) {
+        Ok((Algo::Sha256, hex)) => Some(hex),
+        _ => None,
+    }
+}
+
+/// Check a downloaded or cached artifact against `expected`.
+pub fn verify(artifact: &cache::Artifact, expected: &str) -> Result<()> {
+    let (algo, hex) = parse(expected)?;
+    let actual = match algo {
+        Algo::Sha256 => artifact.sha256.clone(),
+        Algo::Sha512 => {
+            let mut hasher = Sha512::new();
+            std::io::copy(&mut artifact.reader()?, &mut hasher)?;
+            format!("sha512:{}", hex::encode(hasher.finalize()))
+        }
+        Algo::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            std::io::copy(&mut artifact.reader()?, &mut hasher)?;
+            format!("blake3:{}", hasher.finalize().to_hex())
+        }
+    };
+    let digest = actual.rsplit(':').next().unwrap_or(&actual);
+    if !digest.eq_ignore_ascii_case(hex) {
+        bail!("checksum mismatch expected {expected} got {actual}");
+    }
+    Ok(())
+}
@@ -13,10 +13,53 @@ pub struct Cli {
     /// Path to config (defaults to ./tlk.toml)
     #[arg(short, long)]
     pub config: Option<String>,
+
+    /// Resolve version lists and downloads from the local cache only (no network)
+    #[arg(long, global = true, conflicts_with_all = ["no_cache", "refresh"])]
+    pub cache_only: bool,
+    /// Never touch the network: the download cache, version cache and tlk.lock only (also
+    /// TLK_OFFLINE=1)
+    #[arg(long, global = true, conflicts_with_all = ["no_cache", "refresh"])]
+    pub offline: bool,
+    /// Bypass the local cache (always fetch, never store)
+    #[arg(long, global = true, conflicts_with = "refresh")]
+    pub no_cache: bool,
+    /// Fetch from the network and overwrite cached entries
+    #[arg(long, global = true)]
+    pub refresh: bool,
+    /// Progress output: indicatif bars, or newline-delimited JSON events on stderr
+    #[arg(long, global = true, value_name = "FORMAT", default_value = "bars", value_parser = ["bars", "json"])]
+    pub progress: String,
+    /// Skip TLS certificate verification (lab networks with intercepting proxies only)
+    #[arg(long, global = true)]
+    pub insecure: bool,
+    /// Cap the combined download rate, e.g. 2M or 500K bytes per second (overrides [settings]
+    /// limit_rate)
+    #[arg(long, global = true, value_name = "RATE")]
+    pub limit_rate: Option<String>,
+    /// Install at most N tools concurrently (overrides [settings] jobs; default 8)
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+    /// Apply the [profiles.<NAME>] overrides of tlk.toml (also TLK_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// Serve version lists, downloads and the clock from a fixture directory (also
+    /// TLK_FIXTURE_DIR)
+    #[arg(long, global = true, hide = true, value_name = "DIR")]
+    pub fixture_dir: Option<std::path::PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
+    /// Create a starter tlk.toml in the current directory (never overwrites one)
+    Init {
+        /// Seed known tools from an asdf-style .tool-versions file
+        #[arg(long, value_name = "FILE")]
+        from: Option<String>,
+        /// Seed known tools found on PATH, with the versions they report
+        #[arg(long)]
+        detect: bool,
+    },
     /// Install / update declared tools OR one or more known tool specs.
     /// Examples:
     ///   tlk install                     # install all from tlk.toml
@@ -31,28 +74,215 @@ pub enum Commands {
         /// Install exactly the versions recorded in tlk.lock (no lock writes; ignores provided specs and config versions)
         #[arg(long)]
         locked: bool,
-        /// Skip lock verification (use with caution, only applies when installing all)
+        /// Skip lock verification before installing without a lock write (use with caution)
         #[arg(long)]
         no_verify: bool,
         /// Known tool specs (name or name@version). If omitted installs all tools from config.
         #[arg(value_name = "SPEC")]
         specs: Vec<String>,
+        /// Also read specs from FILE ("-" for stdin), one per line; blank lines and # comments are skipped
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["locked", "from_plan"])]
+        specs_from: Option<String>,
         /// Treat provided version as exact (currently informational)
         #[arg(long)]
         exact: bool,
+        /// Install into the shared system location (/opt/tlk/bin or %ProgramData%\tlk\bin) instead of .tlk/bin
+        #[arg(long)]
+        system: bool,
+        /// Reinstall even when the installed version already matches (e.g. corrupted binaries)
+        #[arg(long)]
+        force: bool,
+        /// Force reinstall of just the named config tool(s) (repeatable)
+        #[arg(long, value_name = "NAME")]
+        reinstall: Vec<String>,
+        /// Execute exactly the actions of a plan produced by `tlk plan --json` (no config/lock writes)
+        #[arg(long, value_name = "FILE")]
+        from_plan: Option<String>,
+        /// Also drop lock entries for tools no longer in config
+        #[arg(long, conflicts_with = "locked")]
+        prune_lock: bool,
+        /// Rewrite tlk.toml / tlk.lock for specs even when git has other uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
+        /// Commit the tlk.toml / tlk.lock changes on their own (message from [settings] commit_message)
+        #[arg(long, conflicts_with_all = ["locked", "from_plan"])]
+        commit: bool,
+        /// Compare kubectl against the API server of the current kubeconfig (version skew)
+        #[arg(long)]
+        cluster_check: bool,
+        /// Write a JSON run report (per-tool action, duration, bytes, cache hits, errors, lock hash)
+        #[arg(long, value_name = "FILE")]
+        report: Option<String>,
         // --latest removed; use per-spec @latest instead
     },
+    /// Declare or re-pin tools in tlk.toml without installing them.
+    /// Examples:
+    ///   tlk add terraform@^1.7          # resolve, then write terraform = "^1.7"
+    ///   tlk add helm@3.14.2 --exact     # pin exactly
+    ///   tlk add jq --lock               # latest, also recorded in tlk.lock
+    Add {
+        /// Tool specs (name or name@version|range|latest)
+        #[arg(value_name = "SPEC", required = true)]
+        specs: Vec<String>,
+        /// Store an exact version as given instead of a caret range
+        #[arg(long)]
+        exact: bool,
+        /// Also record the resolved versions in tlk.lock
+        #[arg(long)]
+        lock: bool,
+        /// Also install the tools (implies --lock)
+        #[arg(long)]
+        install: bool,
+    },
+    /// Print the JSON Schema of tlk.toml (--config, default) or tlk.lock (--lock)
+    Schema {
+        #[arg(long, conflicts_with = "lock")]
+        config: bool,
+        #[arg(long)]
+        lock: bool,
+    },
     /// Show what would be installed (no changes)
-    Plan,
+    Plan {
+        /// Emit a fully resolved, machine-readable plan (consumable by `install --from-plan`)
+        #[arg(long)]
+        json: bool,
+    },
     /// List currently installed versions for declared tools
-    List,
+    List {
+        /// Also show each known tool's description, homepage and license
+        #[arg(long)]
+        long: bool,
+    },
+    /// License of every pinned tool; fails on [settings.licenses] allow / deny violations
+    Licenses {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// How declared tools relate: corepack toolchains, shipped commands, `when` conditions
+    Graph {
+        /// Output format
+        #[arg(long, value_name = "FORMAT", default_value = "dot", value_parser = ["dot", "json"])]
+        format: String,
+    },
+    /// Describe a known tool: what it is, license, where it is downloaded from
+    Info {
+        #[arg(value_name = "TOOL")]
+        name: String,
+    },
+    /// Find known tools whose name or description contains QUERY
+    Search {
+        #[arg(value_name = "QUERY")]
+        query: String,
+    },
+    /// Compare installed, locked and latest upstream versions (exits non-zero when behind)
+    Outdated {
+        /// Print the comparison as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the path of the binary a tool resolves to (project .tlk/bin first, then global bin)
+    Which {
+        #[arg(value_name = "TOOL")]
+        name: String,
+        /// List every candidate on the search path, marking shadowed ones
+        #[arg(long)]
+        all: bool,
+    },
     /// Verify tlk.lock against config & installed binaries (no install)
-    Verify,
+    Verify {
+        /// Reinstall tools whose binaries drifted, from the locked sources (never rewrites the lock)
+        #[arg(long)]
+        fix: bool,
+        /// Compare kubectl against the API server of the current kubeconfig (version skew)
+        #[arg(long)]
+        cluster_check: bool,
+    },
     /// Uninstall one or more tools: removes binary, tlk.toml entries & tlk.lock entries
     Uninstall {
         /// Tool names to uninstall
         #[arg(value_name = "NAME")]
         names: Vec<String>,
+        /// Only remove the tlk.toml and tlk.lock entries; leave the installed binary in place
+        #[arg(long)]
+        keep_binary: bool,
+    },
+    /// Remove tools from tlk.toml and tlk.lock without deleting their binaries
+    Remove {
+        /// Tool names to remove
+        #[arg(value_name = "NAME", required = true)]
+        names: Vec<String>,
+    },
+    /// Bump tools to the newest versions matching their specs (rewrites tlk.toml and tlk.lock)
+    Update {
+        /// Tools to update (all declared tools when omitted)
+        #[arg(value_name = "NAME")]
+        names: Vec<String>,
+        /// Commit the tlk.toml / tlk.lock changes on their own (message from [settings] commit_message)
+        #[arg(long)]
+        commit: bool,
+        /// Write a JSON run report (per-tool action, duration, bytes, cache hits, errors, lock hash)
+        #[arg(long, value_name = "FILE")]
+        report: Option<String>,
+    },
+    /// Print exports for the project's toolchain (PATH, TLK_ACTIVE_BIN, per-tool env)
+    /// Example:
+    ///   eval "$(tlk env)"
+    Env {
+        /// Output syntax (bash|zsh|fish|pwsh); POSIX exports when omitted
+        #[arg(long)]
+        shell: Option<String>,
+        /// Print the environment as JSON
+        #[arg(long, conflicts_with = "shell")]
+        json: bool,
+    },
+    /// Run a command with the project's .tlk/bin first on PATH (no shell hook needed)
+    /// Example:
+    ///   tlk exec -- terraform plan
+    #[command(visible_alias = "run")]
+    Exec {
+        /// Command and its arguments (after --)
+        #[arg(
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            value_name = "CMD"
+        )]
+        command: Vec<String>,
+    },
+    /// Print the sha256 of a file or URL, optionally writing it into a tool's tlk.toml entry
+    Hash {
+        /// File path or http(s) URL (omit with --tool to hash that tool's artifact for this machine)
+        #[arg(value_name = "FILE|URL", required_unless_present = "tool")]
+        target: Option<String>,
+        /// Write the checksum into this tool's entry in tlk.toml
+        #[arg(long, value_name = "NAME")]
+        tool: Option<String>,
+        /// Store it under this platform key (e.g. linux-amd64) of a per-platform sha256 table
+        #[arg(long, value_name = "KEY", requires = "tool")]
+        platform: Option<String>,
+    },
+    /// List the entries of a tool's archive and which ones the binary candidate rules match
+    InspectArchive {
+        /// Declared tool name
+        #[arg(value_name = "NAME")]
+        tool: String,
+        /// Inspect the archive of another platform (e.g. darwin-arm64)
+        #[arg(long, value_name = "OS-ARCH")]
+        platform: Option<String>,
+    },
+    /// Rename a declared tool in tlk.toml, tlk.lock and its installed binary
+    Rename {
+        #[arg(value_name = "OLD")]
+        old: String,
+        #[arg(value_name = "NEW")]
+        new: String,
+    },
+    /// Exit non-zero unless the running tlk satisfies a version range (for bootstrap scripts)
+    Require {
+        /// Semver range, e.g. '>=0.5, <1'
+        #[arg(long, value_name = "RANGE")]
+        version: String,
     },
     /// One-time setup: create ~/.tlk/bin and optionally add it to PATH
     Setup {
@@ -62,19 +292,219 @@ pub enum Commands {
     },
     /// Emit shell hook script for dynamic project .tlk/bin activation (bash|zsh|fish|powershell)
     Hook {
-        /// Shell type (bash|zsh). If omitted, prints a universal script.
+        /// Shell type (bash|zsh|fish|pwsh|cmd). If omitted, prints a universal script.
         #[arg(long)]
         shell: Option<String>,
     },
+    /// `set` lines syncing PATH with the current project (used by the cmd.exe hook)
+    #[command(hide = true)]
+    ShimPath,
+    /// Print a shell completion script (bash|zsh|fish|powershell|elvish)
+    /// Example:
+    ///   tlk completions bash > ~/.local/share/bash-completion/completions/tlk
+    Completions { shell: clap_complete::Shell },
+    /// Known tool names, one per line (used by the completion scripts)
+    #[command(hide = true)]
+    CompleteTools,
     /// Regenerate tlk.lock at latest schema (adds cross-platform sources)
     MigrateLock,
     /// Migrate tlk.toml legacy [[tools]] syntax to [tools.<name>] tables
     MigrateConfig,
+    /// Watch tlk.toml / tlk.lock and re-run install (and verify) whenever they change
+    Watch {
+        /// Milliseconds without further changes before reconciling (editors save in steps)
+        #[arg(long, default_value_t = 300)]
+        interval_ms: u64,
+    },
+    /// Serve tool status / drift / install over a local unix socket (line-delimited JSON)
+    Serve {
+        /// Socket path (defaults to .tlk/tlk.sock)
+        #[arg(long)]
+        socket: Option<String>,
+    },
+    /// Generate editor integration files
+    Generate {
+        #[command(subcommand)]
+        target: GenerateTarget,
+    },
+    /// Merge an org baseline tlk.toml (URL or path): add missing tools, raise versions below the baseline
+    ApplyBaseline {
+        /// Baseline config location (https:// URL or file path)
+        #[arg(value_name = "URL|PATH")]
+        baseline: String,
+        /// Apply without the interactive confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Run a tlk command (install|verify|plan|list) in every project found under a directory tree
+    /// or listed in a repos file, then print an aggregated report
+    Foreach {
+        /// Command to run in each project
+        #[arg(value_name = "COMMAND")]
+        command: String,
+        /// Directory searched recursively for tlk.toml files
+        #[arg(long, default_value = ".")]
+        root: String,
+        /// File listing project directories (one per line) instead of searching
+        #[arg(long, value_name = "FILE")]
+        repos: Option<String>,
+        /// Extra arguments passed to the command (after --)
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Maintain tlk.lock
+    Lock {
+        #[command(subcommand)]
+        action: LockAction,
+    },
+    /// Snapshot known-tool version indexes for offline use (TLK_REGISTRY)
+    Registry {
+        #[command(subcommand)]
+        action: RegistryAction,
+    },
+    /// Remove tlk scratch state
+    Clean {
+        /// Purge .tlk/tmp download / extraction dirs (except those of running tlk processes)
+        #[arg(long)]
+        tmp: bool,
+    },
+    /// Inspect and prune the shared download cache (~/.tlk/cache)
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Derive tlk pins from another tool's manifest
+    Import {
+        #[command(subcommand)]
+        source: ImportSource,
+    },
+    /// Bundle redacted diagnostics (environment, tlk.toml, tlk.lock, last run report) for an issue
+    Bugreport {
+        /// Archive to write (default tlk-bugreport-<timestamp>.tar.gz)
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<String>,
+    },
     /// Diagnose lock issues (e.g., missing platform entries)
     Diagnose {
         #[arg(long, default_value = "tlk.lock")]
         lock: String,
+        /// missing-platforms, path-shadowing or receipts (installed files deleted by hand)
         #[arg(long, default_value = "missing-platforms")]
         kind: String,
     },
 }
+
+#[derive(Subcommand, Debug)]
+pub enum GenerateTarget {
+    /// Write .vscode tasks (install/verify/update), terminal PATH settings and extension recommendations
+    Vscode,
+    /// Print a Homebrew formula for a locked tool (URLs and checksums from tlk.lock)
+    BrewFormula {
+        #[arg(value_name = "TOOL")]
+        tool: String,
+    },
+    /// Print a Scoop manifest for a locked tool
+    Scoop {
+        #[arg(value_name = "TOOL")]
+        tool: String,
+    },
+    /// Print a winget singleton manifest for a locked tool
+    Winget {
+        #[arg(value_name = "TOOL")]
+        tool: String,
+        /// Publisher, also the first part of the PackageIdentifier
+        #[arg(long, default_value = "Internal")]
+        publisher: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LockAction {
+    /// Remove lock entries for tools no longer declared in config
+    Prune {
+        /// Print what was dropped as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-pin trust-on-first-use checksums after an intentional upstream change
+    Bless {
+        /// Tools to re-pin
+        #[arg(required = true, value_name = "TOOL")]
+        tools: Vec<String>,
+    },
+    /// Add a platform's download URLs to every lock entry without re-resolving versions
+    AddPlatform {
+        /// Platforms as os-arch, e.g. darwin-arm64
+        #[arg(required = true, value_name = "OS-ARCH")]
+        platforms: Vec<String>,
+        /// Also download each new artifact and record its checksum (TOFU-pinned tools)
+        #[arg(long)]
+        checksums: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Evict downloads unused for --max-age, then least recently used ones beyond --max-size
+    Gc {
+        /// Remove entries unused for longer than this (e.g. 90d, 12h)
+        #[arg(long)]
+        max_age: Option<String>,
+        /// Size cap (e.g. 5GB); defaults to [cache] max_size from tlk.toml
+        #[arg(long)]
+        max_size: Option<String>,
+    },
+    /// Show cache size by tool and hit rate
+    Stats,
+    /// List cached downloads, most recently used first
+    Ls {
+        /// Only entries of this tool
+        #[arg(long)]
+        tool: Option<String>,
+    },
+    /// Remove cached downloads and version lists
+    Clear {
+        /// Only entries of this tool
+        #[arg(long)]
+        tool: Option<String>,
+    },
+    /// Re-hash cached downloads and report entries whose content no longer matches
+    Verify {
+        /// Remove corrupt entries
+        #[arg(long)]
+        fix: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RegistryAction {
+    /// Write version indexes of known tools to a directory that TLK_REGISTRY can point at
+    Mirror {
+        #[arg(value_name = "DIR")]
+        dir: String,
+        /// Only these tools (comma separated); all known tools when omitted
+        #[arg(long, value_delimiter = ',', value_name = "TOOL")]
+        tools: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportSource {
+    /// Pin pnpm / yarn from packageManager and node from engines.node
+    PackageJson {
+        #[arg(long, default_value = "package.json")]
+        path: String,
+        /// Update tlk.toml instead of only printing the pins
+        #[arg(long)]
+        write: bool,
+    },
+    /// Pin terraform from the required_version constraints in *.tf files
+    Terraform {
+        /// Terraform root module directory
+        #[arg(long, default_value = ".")]
+        dir: String,
+        /// Update tlk.toml instead of only printing the pin
+        #[arg(long)]
+        write: bool,
+    },
+}
@@ -0,0 +1,123 @@
+use crate::command_handlers::install::{parse_spec, resolve_version};
+use crate::command_handlers::specs::canonicalize_spec;
+use crate::config::TlkConfig;
+use crate::known_tools::known_tools_map;
+use crate::{installer, ops, versioning};
+use anyhow::{Context, Result};
+use std::fs;
+use toml::Value;
+
+// add: declare or re-pin tools in tlk.toml without downloading anything. Each spec is resolved
+// against the tool's version list (so typos and unsatisfiable ranges fail here, not at install
+// time) and stored canonically: known tools as shorthand (`terraform = "^1.7.5"`), tools already
+// declared under [tools.<name>] keep their table and get a new `version`. --lock also records the
+// resolved versions in tlk.lock; --install installs them (and writes the lock, like install does).
+pub fn add(
+    config_path: &str,
+    specs: &[String],
+    exact: bool,
+    lock: bool,
+    install: bool,
+) -> Result<()> {
+    let raw = match fs::read_to_string(config_path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("reading {config_path}")),
+    };
+    let mut root: Value = raw
+        .parse()
+        .with_context(|| format!("parsing {config_path}"))?;
+    let mut resolved = Vec::new();
+    for spec in specs {
+        let (name, requested) = parse_spec(spec)?;
+        let requested = requested.filter(|v| v != "latest");
+        let version = match &requested {
+            Some(v) => resolve_version(&name, v),
+            None => versioning::fetch_latest(&name),
+        }
+        .with_context(|| format!("resolving {spec}"))?;
+        // Custom tools have no version list to re-resolve a caret range against later.
+        let exact = exact || !known_tools_map().contains_key(name.as_str());
+        let stored = canonicalize_spec(requested.as_deref(), &version, exact, false);
+        set_version(&mut root, &name, &stored)?;
+        println!("Added {name} = \"{stored}\" (resolves to {version})");
+        resolved.push((name, version));
+    }
+    fs::write(config_path, toml::to_string_pretty(&root)?)
+        .with_context(|| format!("writing {config_path}"))?;
+    if !lock && !install {
+        return Ok(());
+    }
+
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("{config_path} could not be loaded after editing");
+    };
+    let mut tools = Vec::new();
+    for (name, version) in &resolved {
+        let Some(tool) = cfg.tools.iter().find(|t| &t.name == name) else {
+            println!("Skipping {name}: its `when` condition does not match this machine");
+            continue;
+        };
+        let mut tool = tool.clone();
+        tool.version = version.clone();
+        tools.push(tool);
+    }
+    let _project = install
+        .then(|| crate::lock::lock_project("tlk.lock", "add --install"))
+        .transpose()?;
+    if install {
+        let opts = installer::InstallOptions { force: false };
+        let results = installer::install_tools_parallel(&tools, &opts);
+        let mut failed = Vec::new();
+        tools.retain(|t| match results.iter().find(|(n, _)| n == &t.name) {
+            Some((_, Ok(()))) => {
+                println!("Installed {} {}", t.name, t.version);
+                true
+            }
+            Some((_, Err(e))) => {
+                eprintln!("Failed to install {}: {e:#}", t.name);
+                failed.push(t.name.clone());
+                false
+            }
+            None => false,
+        });
+        ops::write_locks(Some(&cfg), &tools)?;
+        if !failed.is_empty() {
+            anyhow::bail!("failed to install: {}", failed.join(", "));
+        }
+        return Ok(());
+    }
+    ops::write_locks(Some(&cfg), &tools)
+}
+
+// Point `name` at `version`: update an existing shorthand, inline table or [tools.<name>] entry,
+// else add a shorthand for a known tool.
+pub fn set_version(root: &mut Value, name: &str, version: &str) -> Result<()> {
+    let Value::Table(tbl) = root else {
+        anyhow::bail!("config root is not a table");
+    };
+    let entry = if tbl.contains_key(name) {
+        tbl.get_mut(name)
+    } else {
+        match tbl.get_mut("tools") {
+            Some(Value::Table(t)) => t.get_mut(name),
+            Some(Value::Array(items)) => items
+                .iter_mut()
+                .find(|it| it.get("name").and_then(|v| v.as_str()) == Some(name)),
+            _ => None,
+        }
+    };
+    match entry {
+        Some(Value::Table(t)) => {
+            t.insert("version".into(), Value::String(version.to_string()));
+        }
+        Some(entry) => *entry = Value::String(version.to_string()),
+        None if known_tools_map().contains_key(name) => {
+            tbl.insert(name.to_string(), Value::String(version.to_string()));
+        }
+        None => anyhow::bail!(
+            "'{name}' has no built-in recipe; declare it under [tools.{name}] with a source first"
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use crate::installer::normalize_version;
+use anyhow::{Context, Result};
+use std::io::{BufRead, IsTerminal, Write};
+use toml::Value;
+
+enum Change {
+    Add {
+        name: String,
+        entry: Value,
+        top_level: bool,
+    },
+    Upgrade {
+        name: String,
+        from: String,
+        to: String,
+    },
+}
+
+// apply-baseline: merge an org-maintained baseline tlk.toml (URL or path) into the local config.
+// Baseline tools missing locally are added verbatim; local versions below the baseline version
+// (the floor) are raised to it. Local-only tools, newer local versions and every other field are
+// left alone. The diff is shown and confirmed before tlk.toml is rewritten.
+pub fn apply_baseline(config_path: &str, baseline: &str, yes: bool) -> Result<()> {
+    let raw = fetch_baseline(baseline)?;
+    let base: Value = raw
+        .parse()
+        .with_context(|| format!("parsing baseline {baseline}"))?;
+    let local_raw = std::fs::read_to_string(config_path).unwrap_or_default();
+    let mut local: Value = local_raw
+        .parse()
+        .with_context(|| format!("parsing {config_path}"))?;
+
+    let mut changes = Vec::new();
+    for (name, entry) in baseline_tools(&base) {
+        let Some(floor) = entry_version(&entry) else {
+            continue;
+        };
+        match local_version(&local, &name) {
+            None => changes.push(Change::Add {
+                top_level: base.get(&name).is_some_and(is_shorthand),
+                name,
+                entry,
+            }),
+            Some(current) => {
+                if below_floor(&current, &floor) {
+                    changes.push(Change::Upgrade {
+                        name,
+                        from: current,
+                        to: floor,
+                    });
+                }
+            }
+        }
+    }
+    if changes.is_empty() {
+        println!("{config_path} already satisfies baseline {baseline}");
+        return Ok(());
+    }
+    println!("Changes from baseline {baseline}:");
+    for c in &changes {
+        match c {
+            Change::Add { name, entry, .. } => {
+                println!("  + {name} {}", entry_version(entry).unwrap_or_default())
+            }
+            Change::Upgrade { name, from, to } => println!("  ~ {name} {from} -> {to}"),
+        }
+    }
+    if !yes
+        && !confirm(&format!(
+            "Apply {} change(s) to {config_path}?",
+            changes.len()
+        ))?
+    {
+        println!("Aborted; {config_path} unchanged");
+        return Ok(());
+    }
+    for c in changes {
+        match c {
+            Change::Add {
+                name,
+                entry,
+                top_level,
+            } => add_tool(&mut local, &name, entry, top_level),
+            Change::Upgrade { name, to, .. } => set_version(&mut local, &name, &to),
+        }
+    }
+    std::fs::write(config_path, toml::to_string_pretty(&local)?)
+        .with_context(|| format!("writing {config_path}"))?;
+    println!("Updated {config_path}; run 'tlk install' to apply");
+    Ok(())
+}
+
+fn fetch_baseline(src: &str) -> Result<String> {
+    if src.starts_with("http://") || src.starts_with("https://") {
+        let resp = crate::http::send(crate::http::client()?.get(src))
+            .and_then(|r| Ok(r.error_for_status()?))
+            .with_context(|| format!("fetching baseline {src}"))?;
+        return Ok(resp.text()?);
+    }
+    std::fs::read_to_string(src).with_context(|| format!("reading baseline {src}"))
+}
+
+// Top-level known-tool entries: `name = "ver"` or the inline table `name = { version = "ver", ... }`.
+fn is_shorthand(v: &Value) -> bool {
+    v.is_str() || v.as_table().is_some_and(|t| t.contains_key("version"))
+}
+
+// (name, entry) for every tool in a config: shorthand entries, [tools.<name>] tables and legacy
+// [[tools]] items. Table entries are returned without the legacy `name` key.
+fn baseline_tools(root: &Value) -> Vec<(String, Value)> {
+    let mut out = Vec::new();
+    let Some(tbl) = root.as_table() else {
+        return out;
+    };
+    for (k, v) in tbl {
+        if k != "tools" && is_shorthand(v) {
+            out.push((k.clone(), v.clone()));
+        }
+    }
+    match tbl.get("tools") {
+        Some(Value::Table(tools)) => {
+            for (k, v) in tools {
+                out.push((k.clone(), v.clone()));
+            }
+        }
+        Some(Value::Array(items)) => {
+            for item in items {
+                if let Some(Value::String(name)) = item.get("name") {
+                    let mut entry = item.clone();
+                    if let Some(t) = entry.as_table_mut() {
+                        t.remove("name");
+                    }
+                    out.push((name.clone(), entry));
+                }
+            }
+        }
+        _ => {}
+    }
+    out
+}
+
+fn entry_version(entry: &Value) -> Option<String> {
+    match entry {
+        Value::String(s) => Some(s.clone()),
+        other => other.get("version")?.as_str().map(str::to_string),
+    }
+}
+
+fn local_version(root: &Value, name: &str) -> Option<String> {
+    baseline_tools(root)
+        .into_iter()
+        .find(|(n, _)| n == name)
+        .and_then(|(_, e)| entry_version(&e))
+}
+
+// Versions that cannot be compared (e.g. "latest") are never touched.
+fn below_floor(current: &str, floor: &str) -> bool {
+    let parse = |s: &str| semver::Version::parse(&normalize_version(s).0).ok();
+    match (parse(current), parse(floor)) {
+        (Some(c), Some(f)) => c < f,
+        _ => false,
+    }
+}
+
+fn add_tool(root: &mut Value, name: &str, entry: Value, top_level: bool) {
+    let Some(tbl) = root.as_table_mut() else {
+        return;
+    };
+    if top_level {
+        tbl.insert(name.to_string(), entry);
+        return;
+    }
+    match tbl
+        .entry("tools")
+        .or_insert_with(|| Value::Table(toml::Table::new()))
+    {
+        Value::Table(tools) => {
+            tools.insert(name.to_string(), entry);
+        }
+        Value::Array(items) => {
+            let mut entry = entry;
+            if let Some(t) = entry.as_table_mut() {
+                t.insert("name".into(), Value::String(name.to_string()));
+            }
+            items.push(entry);
+        }
+        _ => {}
+    }
+}
+
+fn set_version(root: &mut Value, name: &str, version: &str) {
+    let Some(tbl) = root.as_table_mut() else {
+        return;
+    };
+    if name != "tools" {
+        match tbl.get_mut(name) {
+            Some(v @ Value::String(_)) => {
+                *v = Value::String(version.to_string());
+                return;
+            }
+            Some(Value::Table(t)) if t.contains_key("version") => {
+                t.insert("version".into(), Value::String(version.to_string()));
+                return;
+            }
+            _ => {}
+        }
+    }
+    match tbl.get_mut("tools") {
+        Some(Value::Table(tools)) => {
+            if let Some(Value::Table(t)) = tools.get_mut(name) {
+                t.insert("version".into(), Value::String(version.to_string()));
+            }
+        }
+        Some(Value::Array(items)) => {
+            for item in items.iter_mut() {
+                if item.get("name").and_then(|v| v.as_str()) == Some(name) {
+                    if let Some(t) = item.as_table_mut() {
+                        t.insert("version".into(), Value::String(version.to_string()));
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("not a terminal; re-run with --yes to apply without confirmation");
+    }
+    print!("{prompt} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(matches!(line.trim(), "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_table_shorthand_is_raised_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let baseline = dir.path().join("baseline.toml");
+        let config = dir.path().join("tlk.toml");
+        std::fs::write(
+            &baseline,
+            "terraform = { version = \"1.7.5\" }\njq = { version = \"1.7.1\", when = \"!env:CI\" }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &config,
+            "terraform = { version = \"1.5.0\", when = \"!env:CI\" }\n",
+        )
+        .unwrap();
+        let config_path = config.to_string_lossy();
+        apply_baseline(&config_path, &baseline.to_string_lossy(), true).unwrap();
+
+        let local: Value = std::fs::read_to_string(&config).unwrap().parse().unwrap();
+        assert!(local.get("tools").is_none(), "{local}");
+        assert_eq!(local["terraform"]["version"].as_str(), Some("1.7.5"));
+        assert_eq!(local["terraform"]["when"].as_str(), Some("!env:CI"));
+        assert_eq!(local["jq"]["version"].as_str(), Some("1.7.1"));
+
+        // Already at the floor: nothing to change.
+        apply_baseline(&config_path, &baseline.to_string_lossy(), true).unwrap();
+        let again: Value = std::fs::read_to_string(&config).unwrap().parse().unwrap();
+        assert_eq!(again, local);
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use flate2::{write::GzEncoder, Compression};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+// bugreport: one tar.gz to attach to an issue, so the first reply isn't a list of questions. It
+// holds the environment (OS / arch / libc, tlk version, which proxy and token variables are set
+// but never their values) and redacted copies of tlk.toml, tlk.lock and the last install / update
+// report. Redaction replaces every URL with a short hash (the same URL hashes alike in every file,
+// so they can still be correlated) and blanks token / secret / password / header values.
+pub fn bugreport(config_path: &str, lock_path: &str, output: Option<&str>) -> Result<()> {
+    let output = match output {
+        Some(path) => path.to_string(),
+        None => format!(
+            "tlk-bugreport-{}.tar.gz",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ),
+    };
+    let mut files: Vec<(&str, String)> = vec![(
+        "environment.json",
+        serde_json::to_string_pretty(&environment())? + "\n",
+    )];
+    for (name, path) in [("tlk.toml", config_path), ("tlk.lock", lock_path)] {
+        match redacted_toml(Path::new(path)) {
+            Ok(Some(text)) => files.push((name, text)),
+            Ok(None) => {}
+            Err(e) => files.push((name, format!("# could not be included: {e:#}\n"))),
+        }
+    }
+    if let Some(path) = crate::report::last_report_path().filter(|p| p.is_file()) {
+        let text = std::fs::read_to_string(&path)?;
+        let mut report: Value =
+            serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        redact(&mut report);
+        files.push((
+            "last-report.json",
+            serde_json::to_string_pretty(&report)? + "\n",
+        ));
+    }
+
+    let file = std::fs::File::create(&output).with_context(|| format!("creating {output}"))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (name, text) in &files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(text.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        tar.append_data(
+            &mut header,
+            format!("tlk-bugreport/{name}"),
+            text.as_bytes(),
+        )?;
+    }
+    tar.into_inner()?.finish()?;
+    println!("Wrote {output}:");
+    for (name, _) in &files {
+        println!("  {name}");
+    }
+    println!("URLs and secrets are redacted; review the files before attaching them to an issue.");
+    Ok(())
+}
+
+fn environment() -> Value {
+    let set = |names: &[&str]| -> Value {
+        names
+            .iter()
+            .map(|n| {
+                let present = std::env::var_os(n).is_some_and(|v| !v.is_empty());
+                (n.to_string(), Value::Bool(present))
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    };
+    let mut tlk_vars: Vec<String> = std::env::vars_os()
+        .filter_map(|(k, _)| k.into_string().ok())
+        .filter(|k| k.starts_with("TLK_"))
+        .collect();
+    tlk_vars.sort();
+    let build_env = if cfg!(target_env = "musl") {
+        "musl"
+    } else if cfg!(target_env = "msvc") {
+        "msvc"
+    } else {
+        "gnu"
+    };
+    let shell = std::env::var("SHELL")
+        .ok()
+        .and_then(|s| s.rsplit('/').next().map(str::to_string));
+    json!({
+        "tlk_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "libc": libc(),
+        "build_env": build_env,
+        "shell": shell,
+        "ci": std::env::var_os("CI").is_some(),
+        "proxy_env": set(&[
+            "HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy",
+            "NO_PROXY", "no_proxy",
+        ]),
+        "token_env": set(&["GITHUB_TOKEN", "GH_TOKEN"]),
+        // Names only: values can hold paths or credentials.
+        "tlk_env": tlk_vars,
+    })
+}
+
+// First line of `ldd --version` (glibc prints to stdout, musl to stderr).
+fn libc() -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let out = std::process::Command::new("ldd")
+        .arg("--version")
+        .output()
+        .ok()?;
+    let text = [out.stdout, out.stderr].concat();
+    String::from_utf8_lossy(&text)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+fn redacted_toml(path: &Path) -> Result<Option<String>> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    let value: toml::Value = text
+        .parse()
+        .with_context(|| format!("parsing {}", path.display()))?;
+    let mut json = serde_json::to_value(&value)?;
+    redact(&mut json);
+    let value: toml::Value = serde_json::from_value(json)?;
+    Ok(Some(toml::to_string_pretty(&value)?))
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::String(s) if s.contains("://") => *s = hash_url(s),
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        Value::Object(map) => {
+            let entries = std::mem::take(map);
+            for (key, mut v) in entries {
+                if sensitive(&key) {
+                    v = Value::String("<redacted>".into());
+                } else {
+                    redact(&mut v);
+                }
+                // [mirrors] and per-URL tables use URLs as keys.
+                let key = if key.contains("://") {
+                    hash_url(&key)
+                } else {
+                    key
+                };
+                map.insert(key, v);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Keys naming a variable (github_token_env) are kept; the variable's value is never read.
+fn sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    !key.ends_with("_env")
+        && (key == "headers"
+            || key == "authorization"
+            || ["token", "secret", "password"]
+                .iter()
+                .any(|w| key.contains(w)))
+}
+
+fn hash_url(url: &str) -> String {
+    let digest = hex::encode(Sha256::digest(url.as_bytes()));
+    format!("<url sha256:{}>", &digest[..12])
+}
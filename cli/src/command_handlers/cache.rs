@@ -0,0 +1,136 @@
+use crate::cache;
+use crate::config::TlkConfig;
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+// cache gc: drop downloads unused for --max-age, then LRU-evict down to --max-size (defaulting to
+// [cache] max_size from tlk.toml).
+pub fn gc(cfg: Option<&TlkConfig>, max_age: Option<&str>, max_size: Option<&str>) -> Result<()> {
+    let max_age = max_age.map(cache::parse_age).transpose()?;
+    let max_size = match max_size.or(cfg.and_then(|c| c.cache.max_size.as_deref())) {
+        Some(s) => Some(cache::parse_size(s)?),
+        None => None,
+    };
+    if max_age.is_none() && max_size.is_none() {
+        anyhow::bail!("nothing to do: pass --max-age and/or --max-size (or set [cache] max_size)");
+    }
+    let (removed, freed) = cache::evict(max_age, max_size)?;
+    println!(
+        "Removed {removed} cached download(s), freed {}",
+        human_size(freed)
+    );
+    Ok(())
+}
+
+pub fn stats() -> Result<()> {
+    let Some(dir) = cache::cache_dir() else {
+        anyhow::bail!("no cache directory (HOME not set)");
+    };
+    let entries = cache::entries();
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    let mut by_tool: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+    for e in &entries {
+        let slot = by_tool.entry(e.tool.as_str()).or_default();
+        slot.0 += 1;
+        slot.1 += e.size;
+    }
+    let c = cache::counters();
+    println!("Cache: {}", dir.display());
+    println!("Downloads: {} ({})", entries.len(), human_size(total));
+    let lookups = c.hits + c.misses;
+    if lookups > 0 {
+        println!(
+            "Hit rate: {:.1}% ({} hits / {} misses)",
+            c.hits as f64 * 100.0 / lookups as f64,
+            c.hits,
+            c.misses
+        );
+    }
+    for (tool, (count, size)) in by_tool {
+        println!("  {tool:<20} {count:>4}  {}", human_size(size));
+    }
+    Ok(())
+}
+
+// cache ls: one line per cached download (last used, size, tool, url), most recent first.
+pub fn ls(tool: Option<&str>) -> Result<()> {
+    let mut entries: Vec<_> = cache::entries()
+        .into_iter()
+        .filter(|e| tool.is_none_or(|t| t == e.tool))
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.last_used));
+    for e in &entries {
+        let used: chrono::DateTime<chrono::Local> = e.last_used.into();
+        println!(
+            "{}  {:>9}  {:<16} {}{}",
+            used.format("%Y-%m-%d %H:%M"),
+            human_size(e.size),
+            e.tool,
+            e.url,
+            if e.archived { " (archived)" } else { "" }
+        );
+    }
+    if entries.is_empty() {
+        println!("Cache is empty");
+    }
+    Ok(())
+}
+
+pub fn clear(tool: Option<&str>) -> Result<()> {
+    let (removed, freed) = cache::clear(tool)?;
+    println!(
+        "Removed {removed} cached download(s), freed {}",
+        human_size(freed)
+    );
+    Ok(())
+}
+
+// cache verify: entries are stored under the sha256 of their content, so re-hashing catches disk
+// corruption or tampering. Entries from the older url-keyed layout carry no checksum to compare
+// against and are only reported.
+pub fn verify(fix: bool) -> Result<()> {
+    let (mut ok, mut legacy, mut corrupt) = (0, 0, 0);
+    for e in cache::entries() {
+        let Some(expected) = e.expected_sha256() else {
+            legacy += 1;
+            continue;
+        };
+        let actual = e.hash()?;
+        if actual == expected {
+            ok += 1;
+            continue;
+        }
+        corrupt += 1;
+        println!(
+            "corrupt: {} ({}) has sha256 {actual}",
+            e.path.display(),
+            e.url
+        );
+        if fix {
+            e.remove()?;
+        }
+    }
+    println!("{ok} ok, {corrupt} corrupt, {legacy} unverifiable (url-keyed)");
+    if corrupt > 0 && !fix {
+        anyhow::bail!("{corrupt} corrupt cache entr(y/ies); rerun with --fix to remove them");
+    }
+    if corrupt > 0 {
+        println!("Removed {corrupt} corrupt entr(y/ies)");
+    }
+    Ok(())
+}
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,93 @@
+use crate::cli::Cli;
+use crate::known_tools::known_tools_map;
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+// completions: clap_complete's static script for the CLI definitions in cli.rs, plus a shim that
+// offers the known tool names (from the hidden `tlk complete-tools`) for the SPEC arguments of
+// `install` and `add`. Elvish gets the static script only.
+pub fn completions(shell: Shell) -> Result<()> {
+    let mut buf = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), "tlk", &mut buf);
+    let mut script = String::from_utf8(buf)?;
+    match shell {
+        Shell::Bash => {
+            script = script.replace("complete -F _tlk ", "complete -F _tlk_with_tools ");
+            script.push_str(BASH_TOOLS);
+        }
+        Shell::Fish => script.push_str(FISH_TOOLS),
+        Shell::Zsh => script = zsh_with_tools(&script),
+        Shell::PowerShell => script = powershell_with_tools(&script),
+        _ => {}
+    }
+    print!("{script}");
+    Ok(())
+}
+
+pub fn complete_tools() -> Result<()> {
+    let mut names: Vec<_> = known_tools_map().into_keys().collect();
+    names.sort();
+    for name in names {
+        println!("{name}");
+    }
+    Ok(())
+}
+
+const BASH_TOOLS: &str = r#"
+_tlk_with_tools() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    if [[ ${COMP_CWORD} -ge 2 && ${cur} != -* && ( ${COMP_WORDS[1]} == install || ${COMP_WORDS[1]} == add ) ]]; then
+        COMPREPLY=( $(compgen -W "$(tlk complete-tools 2>/dev/null)" -- "${cur}") )
+        return 0
+    fi
+    _tlk "$@"
+}
+"#;
+
+const FISH_TOOLS: &str = r#"
+complete -c tlk -n "__fish_seen_subcommand_from install add" -f -a "(tlk complete-tools 2>/dev/null)"
+"#;
+
+const POWERSHELL_TOOLS: &str = r#"
+    $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })
+    if ($words.Count -ge 2 -and $words[1] -in 'install', 'add' -and -not $wordToComplete.StartsWith('-')) {
+        return tlk complete-tools 2>$null | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+            [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+        }
+    }
+"#;
+
+// zsh: swap the default file completion of the SPEC arguments for the tool names.
+fn zsh_with_tools(script: &str) -> String {
+    let mut out: String = script
+        .lines()
+        .map(|line| match line.strip_suffix(":_default' \\") {
+            Some(head) if line.starts_with("'*::specs -- ") => {
+                format!("{head}:_tlk_known_tools' \\\n")
+            }
+            _ => format!("{line}\n"),
+        })
+        .collect();
+    // Defined before the trailer that runs _tlk when the file is autoloaded.
+    let helper = r#"(( $+functions[_tlk_known_tools] )) ||
+_tlk_known_tools() {
+    local -a tools
+    tools=(${(f)"$(tlk complete-tools 2>/dev/null)"})
+    _describe -t tools 'known tool' tools
+}
+
+"#;
+    const TRAILER: &str = "if [ \"$funcstack[1]\" = \"_tlk\" ]; then";
+    match out.find(TRAILER) {
+        Some(pos) => out.insert_str(pos, helper),
+        None => out.push_str(helper),
+    }
+    out
+}
+
+// PowerShell: answer SPEC positions before the generated completer runs.
+fn powershell_with_tools(script: &str) -> String {
+    const PARAMS: &str = "param($wordToComplete, $commandAst, $cursorPosition)\n";
+    script.replacen(PARAMS, &format!("{PARAMS}{POWERSHELL_TOOLS}"), 1)
+}
@@ -1,51 +1,227 @@
-use crate::cli::Commands;
-use crate::command_handlers::{diagnose, hook, install, migrate, migrate_config, setup, uninstall};
+use crate::cli::{CacheAction, Commands, GenerateTarget, ImportSource, LockAction, RegistryAction};
+use crate::command_handlers::{
+    add, baseline, bugreport, cache, completions, diagnose, env, exec, foreach, generate, graph,
+    hash, hook, import, info, init, inspect, install, licenses, lock, manifests, migrate,
+    migrate_config, outdated, plan, registry, rename, require, schema, serve, setup, skew,
+    uninstall, update, verify, watch, which,
+};
 use crate::config::TlkConfig;
 use anyhow::Result;
 
 pub fn dispatch(cmd: Commands, cfg: Option<&TlkConfig>, config_path: &str) -> Result<()> {
     match cmd {
+        Commands::Add {
+            specs,
+            exact,
+            lock,
+            install,
+        } => add::add(config_path, &specs, exact, lock, install),
+        Commands::Init { from, detect } => init::init(config_path, from.as_deref(), detect),
         Commands::Install {
             no_lock,
             locked,
             no_verify,
-            specs,
+            mut specs,
+            specs_from,
             exact,
-        } => {
+            system,
+            force,
+            reinstall,
+            from_plan,
+            prune_lock,
+            allow_dirty,
+            commit,
+            cluster_check,
+            report,
+        } => with_report(report.as_deref(), "install", || {
+            if let Some(source) = &specs_from {
+                specs.extend(install::read_specs(source)?);
+            }
+            let before = commit.then(|| crate::git::locked_versions("tlk.lock"));
             let args = install::InstallArgs {
                 write_lock: !no_lock,
                 locked,
                 no_verify,
                 specs: &specs,
                 exact,
+                system,
+                force,
+                reinstall: &reinstall,
+                from_plan: from_plan.as_deref(),
+                prune_lock,
+                allow_dirty,
                 config_path,
                 cfg,
             };
-            install::run_install(args)
+            install::run_install(args)?;
+            skew::check_kubectl(cfg, cluster_check);
+            match before {
+                Some(before) => commit_changes(cfg, config_path, &before),
+                None => Ok(()),
+            }
+        }),
+        Commands::Plan { json } => plan::print_plan(cfg, config_path, json),
+        Commands::List { long } => crate::installer::list(cfg, long),
+        Commands::Licenses { json } => licenses::licenses(cfg, json),
+        Commands::Graph { format } => graph::graph(cfg, &format),
+        Commands::Info { name } => info::info(cfg, &name),
+        Commands::Search { query } => info::search(&query),
+        Commands::Outdated { json } => outdated::outdated(cfg, "tlk.lock", json),
+        Commands::Completions { shell } => completions::completions(shell),
+        Commands::CompleteTools => completions::complete_tools(),
+        Commands::InspectArchive { tool, platform } => {
+            inspect::inspect_archive(config_path, "tlk.lock", &tool, platform.as_deref())
         }
-        Commands::Plan => crate::installer::plan(cfg),
-        Commands::List => crate::installer::list(cfg),
-        Commands::Verify => crate::installer::verify_lockfile(cfg, "tlk.lock"),
-        Commands::Uninstall { names } => {
+        Commands::Schema { config: _, lock } => schema::schema(lock),
+        Commands::Which { name, all } => which::which(cfg, &name, all),
+        Commands::Verify { fix, cluster_check } => {
+            verify::run_verify(cfg, "tlk.lock", fix)?;
+            skew::check_kubectl(cfg, cluster_check);
+            Ok(())
+        }
+        Commands::Uninstall { names, keep_binary } => {
             if names.is_empty() {
                 anyhow::bail!("at least one tool name required");
             }
-            for name in names {
-                if let Err(e) = uninstall::uninstall_tool(config_path, &name) {
-                    eprintln!("Uninstall {} failed: {}", name, e);
-                } else {
-                    println!("Uninstalled {}", name);
-                }
-            }
-            Ok(())
+            uninstall_all(config_path, &names, keep_binary, "Uninstall")
         }
+        Commands::Remove { names } => uninstall_all(config_path, &names, true, "Remove"),
+        Commands::Update {
+            names,
+            commit,
+            report,
+        } => with_report(report.as_deref(), "update", || {
+            let before = commit.then(|| crate::git::locked_versions("tlk.lock"));
+            update::update(config_path, "tlk.lock", &names)?;
+            match before {
+                Some(before) => commit_changes(cfg, config_path, &before),
+                None => Ok(()),
+            }
+        }),
+        Commands::Env { shell, json } => env::print_env(config_path, shell.as_deref(), json),
+        Commands::Exec { command } => exec::exec(config_path, &command),
+        Commands::Hash {
+            target,
+            tool,
+            platform,
+        } => hash::hash(
+            config_path,
+            "tlk.lock",
+            target.as_deref(),
+            tool.as_deref(),
+            platform.as_deref(),
+        ),
+        Commands::Rename { old, new } => rename::rename_tool(config_path, "tlk.lock", &old, &new),
+        Commands::Require { version } => require::require(&version),
         Commands::Setup { apply } => setup::setup_flow(apply),
         Commands::Hook { shell } => hook::print_hook(shell.as_deref()),
+        Commands::ShimPath => hook::shim_path(),
         Commands::MigrateLock => migrate::migrate_lock(cfg, "tlk.lock"),
         Commands::MigrateConfig => migrate_config::migrate_config(config_path),
+        Commands::Watch { interval_ms } => watch::watch(config_path, interval_ms),
+        Commands::Serve { socket } => serve::serve(config_path, socket.as_deref()),
+        Commands::Generate { target } => match target {
+            GenerateTarget::Vscode => generate::generate_vscode(cfg),
+            GenerateTarget::BrewFormula { tool } => manifests::brew_formula(cfg, "tlk.lock", &tool),
+            GenerateTarget::Scoop { tool } => manifests::scoop_manifest(cfg, "tlk.lock", &tool),
+            GenerateTarget::Winget { tool, publisher } => {
+                manifests::winget_manifest(cfg, "tlk.lock", &tool, &publisher)
+            }
+        },
+        Commands::ApplyBaseline { baseline, yes } => {
+            baseline::apply_baseline(config_path, &baseline, yes)
+        }
+        Commands::Foreach {
+            command,
+            root,
+            repos,
+            args,
+        } => foreach::foreach(&command, &args, &root, repos.as_deref()),
+        Commands::Lock { action } => match action {
+            LockAction::Prune { json } => lock::prune(config_path, "tlk.lock", json),
+            LockAction::Bless { tools } => lock::bless(config_path, "tlk.lock", &tools),
+            LockAction::AddPlatform {
+                platforms,
+                checksums,
+            } => lock::add_platform(config_path, "tlk.lock", &platforms, checksums),
+        },
+        Commands::Registry { action } => match action {
+            RegistryAction::Mirror { dir, tools } => registry::mirror(&dir, &tools),
+        },
+        Commands::Clean { tmp } => {
+            if !tmp {
+                anyhow::bail!("nothing to clean: pass --tmp");
+            }
+            let removed = crate::tmp::sweep(true)?;
+            println!("Removed {removed} temp dir(s) from .tlk/tmp");
+            Ok(())
+        }
+        Commands::Import { source } => match source {
+            ImportSource::PackageJson { path, write } => {
+                import::package_json(config_path, &path, write)
+            }
+            ImportSource::Terraform { dir, write } => import::terraform(config_path, &dir, write),
+        },
+        Commands::Cache { action } => match action {
+            CacheAction::Gc { max_age, max_size } => {
+                cache::gc(cfg, max_age.as_deref(), max_size.as_deref())
+            }
+            CacheAction::Stats => cache::stats(),
+            CacheAction::Ls { tool } => cache::ls(tool.as_deref()),
+            CacheAction::Clear { tool } => cache::clear(tool.as_deref()),
+            CacheAction::Verify { fix } => cache::verify(fix),
+        },
+        Commands::Bugreport { output } => {
+            bugreport::bugreport(config_path, "tlk.lock", output.as_deref())
+        }
         Commands::Diagnose { lock, kind } => match kind.as_str() {
             "missing-platforms" => diagnose::list_missing(&lock),
+            "path-shadowing" => which::diagnose_shadowed(cfg),
+            "receipts" => crate::receipt::diagnose(),
             other => anyhow::bail!("unknown diagnose kind '{other}'"),
         },
     }
 }
+
+fn uninstall_all(config_path: &str, names: &[String], keep_binary: bool, verb: &str) -> Result<()> {
+    let _project = crate::lock::lock_project("tlk.lock", &verb.to_lowercase())?;
+    let mut failed = 0;
+    for name in names {
+        if let Err(e) = uninstall::uninstall_tool(config_path, name, keep_binary) {
+            eprintln!("{verb} {name} failed: {e}");
+            failed += 1;
+        } else if keep_binary {
+            println!("Removed {name} from config and lock (binary kept)");
+        } else {
+            println!("Uninstalled {name}");
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} of {} tool(s) could not be removed", names.len());
+    }
+    Ok(())
+}
+
+// --report: record the run and write the report even when the command fails; a report that
+// cannot be written is only a warning so it never masks the command's own result.
+// Runs are always recorded so `tlk bugreport` has the last one; --report also writes it out.
+fn with_report(path: Option<&str>, command: &str, run: impl FnOnce() -> Result<()>) -> Result<()> {
+    crate::report::enable();
+    let outcome = run();
+    if let Some(path) = path {
+        if let Err(e) = crate::report::write(path, command, "tlk.lock", &outcome) {
+            eprintln!("Warning: {e:#}");
+        }
+    }
+    crate::report::keep_last(command, "tlk.lock", &outcome);
+    outcome
+}
+
+fn commit_changes(
+    cfg: Option<&TlkConfig>,
+    config_path: &str,
+    before: &std::collections::BTreeMap<String, String>,
+) -> Result<()> {
+    let template = cfg.and_then(|c| c.settings.commit_message.as_deref());
+    crate::git::commit_tool_changes(config_path, "tlk.lock", before, template)
+}
@@ -0,0 +1,146 @@
+use crate::config::TlkConfig;
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+// env: the project's toolchain environment as shell exports, for scripts and Makefiles that
+// cannot rely on the prompt hook (`eval "$(tlk env)"`): PATH with the project's tool dirs first,
+// TLK_ACTIVE_BIN (so a hook evaluated later treats the project as already active) and the `env`
+// tables of declared tools. `--json` gives editors the same data. `tlk exec` applies it directly.
+
+/// Toolchain environment of the project owning a config.
+pub struct ProjectEnv {
+    pub config: PathBuf,
+    pub root: PathBuf,
+    /// The project bin dir (.tlk/bin or [settings] bin_dir) first, then custom install_dirs of
+    /// declared tools
+    pub bin_dirs: Vec<PathBuf>,
+    /// Per-tool `env` entries, `{root}` expanded
+    pub vars: BTreeMap<String, String>,
+}
+
+impl ProjectEnv {
+    pub fn load(config_path: &str) -> Result<Self> {
+        let config = project_config(config_path)?;
+        let root = config.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let mut bin_dirs = vec![crate::config::project_bin_dir(&root)];
+        let mut vars = BTreeMap::new();
+        if let Some(cfg) = TlkConfig::load(&config.to_string_lossy()) {
+            for tool in &cfg.tools {
+                if let Some(dir) = &tool.install_dir {
+                    let dir = root.join(dir);
+                    if !bin_dirs.contains(&dir) {
+                        bin_dirs.push(dir);
+                    }
+                }
+                for (name, value) in tool.env.iter().flatten() {
+                    let value = value.replace("{root}", &root.to_string_lossy());
+                    if let Some(prev) = vars.insert(name.clone(), value.clone()) {
+                        if prev != value {
+                            eprintln!(
+                                "Warning: {name} is set by several tools; using {}'s value",
+                                tool.name
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Ok(ProjectEnv {
+            config,
+            root,
+            bin_dirs,
+            vars,
+        })
+    }
+
+    /// The current PATH with the project's bin dirs moved to the front.
+    pub fn path(&self) -> Result<OsString> {
+        let current = std::env::var_os("PATH").unwrap_or_default();
+        let rest = std::env::split_paths(&current).filter(|p| !self.bin_dirs.contains(p));
+        std::env::join_paths(self.bin_dirs.iter().cloned().chain(rest))
+            .context("building PATH for the project")
+    }
+
+    /// Variables to export, PATH and TLK_ACTIVE_BIN first.
+    pub fn exports(&self) -> Result<Vec<(String, String)>> {
+        let mut out = vec![
+            (
+                "PATH".to_string(),
+                self.path()?.to_string_lossy().to_string(),
+            ),
+            (
+                "TLK_ACTIVE_BIN".to_string(),
+                self.bin_dirs[0].to_string_lossy().to_string(),
+            ),
+        ];
+        out.extend(self.vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(out)
+    }
+}
+
+/// The config named with -c when it exists, else the nearest tlk.toml above the working directory.
+pub fn project_config(config_path: &str) -> Result<PathBuf> {
+    let given = Path::new(config_path);
+    if given.is_file() {
+        return std::path::absolute(given).with_context(|| format!("resolving {config_path}"));
+    }
+    let mut dir = std::env::current_dir()?;
+    loop {
+        let candidate = dir.join("tlk.toml");
+        if candidate.is_file() {
+            return Ok(candidate);
+        }
+        if !dir.pop() {
+            anyhow::bail!("no tlk.toml found in the current directory or any parent");
+        }
+    }
+}
+
+pub fn print_env(config_path: &str, shell: Option<&str>, json_output: bool) -> Result<()> {
+    let env = ProjectEnv::load(config_path)?;
+    let exports = env.exports()?;
+    if json_output {
+        let vars: serde_json::Map<_, _> =
+            exports.iter().map(|(k, v)| (k.clone(), json!(v))).collect();
+        let out = json!({
+            "root": env.root.to_string_lossy(),
+            "path_prepend": env.bin_dirs.iter().map(|d| d.to_string_lossy()).collect::<Vec<_>>(),
+            "env": vars,
+        });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+    match shell.map(|s| s.to_lowercase()).as_deref() {
+        None | Some("bash") | Some("zsh") | Some("sh") => {
+            for (k, v) in &exports {
+                println!("export {k}={}", sh_quote(v));
+            }
+        }
+        Some("fish") => {
+            for (k, v) in &exports {
+                if k == "PATH" {
+                    let parts: Vec<_> = std::env::split_paths(v)
+                        .map(|p| sh_quote(&p.to_string_lossy()))
+                        .collect();
+                    println!("set -gx PATH {};", parts.join(" "));
+                } else {
+                    println!("set -gx {k} {};", sh_quote(v));
+                }
+            }
+        }
+        Some("pwsh") | Some("powershell") => {
+            for (k, v) in &exports {
+                println!("$env:{k} = '{}'", v.replace('\'', "''"));
+            }
+        }
+        Some(other) => anyhow::bail!("unsupported shell '{other}' (use bash, zsh, fish or pwsh)"),
+    }
+    Ok(())
+}
+
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
@@ -0,0 +1,78 @@
+use crate::command_handlers::env::ProjectEnv;
+use crate::config::TlkConfig;
+use crate::{installer, ops};
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+// exec / run: run a command in the project's toolchain environment (see env.rs), without eval'ing
+// the shell hook (CI steps, Makefiles, scripts). The project root is the directory of the config
+// given with -c, else the nearest ancestor of the working directory holding tlk.toml. On unix tlk
+// replaces itself with the command, so signals and the exit status pass straight through.
+// A declared tool that is not installed yet is installed first (the locked version when tlk.lock
+// has it), so a fresh clone needs nothing but `tlk exec terraform -- plan`.
+pub fn exec(config_path: &str, command: &[String]) -> Result<()> {
+    let Some((program, mut args)) = command.split_first() else {
+        anyhow::bail!("no command given (usage: tlk exec -- <cmd> [args...])");
+    };
+    // `tlk exec terraform -- plan`
+    if args.first().is_some_and(|a| a == "--") {
+        args = &args[1..];
+    }
+    let env = ProjectEnv::load(config_path)?;
+    ensure_installed(&env.config, &env.root, program)?;
+
+    let mut cmd = Command::new(program);
+    cmd.args(args).envs(env.exports()?);
+    run(cmd, program)
+}
+
+// Install `program` when it is a declared tool whose binary is missing. Lock and install paths are
+// relative to the project root, so the work happens there.
+fn ensure_installed(config: &Path, root: &Path, program: &str) -> Result<()> {
+    let Some(cfg) = TlkConfig::load(&config.to_string_lossy()) else {
+        return Ok(());
+    };
+    let Some(tool) = cfg.tools.iter().find(|t| {
+        t.name == program
+            || installer::target_path(t).is_ok_and(|p| p.file_stem().is_some_and(|s| s == program))
+    }) else {
+        return Ok(());
+    };
+    crate::lock::recover_interrupted(&root.join("tlk.lock").to_string_lossy());
+    if installer::target_path(tool)?.exists() {
+        return Ok(());
+    }
+    let cwd = std::env::current_dir()?;
+    std::env::set_current_dir(root).with_context(|| format!("entering {}", root.display()))?;
+    eprintln!("[tlk] {} is not installed; installing it first", tool.name);
+    let opts = installer::InstallOptions { force: false };
+    let locked =
+        crate::lock::LockFile::load("tlk.lock")?.is_some_and(|l| l.tools.contains_key(&tool.name));
+    let res = if locked {
+        ops::install_locked(
+            "tlk.lock",
+            Some(&cfg),
+            &opts,
+            Some(std::slice::from_ref(&tool.name)),
+        )
+    } else {
+        installer::install_single(tool, &opts).and_then(|_| ops::write_single_lock(Some(&cfg), tool))
+    };
+    std::env::set_current_dir(&cwd)?;
+    res.with_context(|| format!("installing {}", tool.name))
+}
+
+#[cfg(unix)]
+fn run(mut cmd: Command, program: &str) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+    // exec only returns on failure
+    let err = cmd.exec();
+    Err(err).with_context(|| format!("running {program}"))
+}
+
+#[cfg(not(unix))]
+fn run(mut cmd: Command, program: &str) -> Result<()> {
+    let status = cmd.status().with_context(|| format!("running {program}"))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SUPPORTED: &[&str] = &["install", "verify", "plan", "list", "outdated"];
+// Directories never searched for projects.
+const SKIP_DIRS: &[&str] = &[".git", ".tlk", "node_modules", "target", "vendor"];
+
+// foreach: run one tlk command in every project (a directory holding tlk.toml) found under
+// `root`, or in each directory listed in `repos_file` (one path per line, # comments allowed).
+// Each project runs as a child `tlk` process with the project as working directory so its own
+// tlk.toml / tlk.lock are used; results are aggregated into one report at the end.
+pub fn foreach(command: &str, args: &[String], root: &str, repos_file: Option<&str>) -> Result<()> {
+    if !SUPPORTED.contains(&command) {
+        anyhow::bail!(
+            "unsupported foreach command '{command}' (expected one of: {})",
+            SUPPORTED.join(", ")
+        );
+    }
+    let projects = match repos_file {
+        Some(file) => read_repos(file)?,
+        None => discover(Path::new(root))?,
+    };
+    if projects.is_empty() {
+        println!("No tlk.toml found under {root}");
+        return Ok(());
+    }
+    let exe = std::env::current_exe().context("locating tlk executable")?;
+    let mut results: Vec<(PathBuf, Result<(), String>)> = Vec::new();
+    for dir in projects {
+        println!("==> {} (tlk {command})", dir.display());
+        let outcome = if !dir.join("tlk.toml").exists() {
+            Err("no tlk.toml".to_string())
+        } else {
+            match Command::new(&exe)
+                .arg(command)
+                .args(args)
+                .current_dir(&dir)
+                .status()
+            {
+                Ok(s) if s.success() => Ok(()),
+                Ok(s) => Err(match s.code() {
+                    Some(code) => format!("exit code {code}"),
+                    None => "terminated by signal".to_string(),
+                }),
+                Err(e) => Err(e.to_string()),
+            }
+        };
+        results.push((dir, outcome));
+    }
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+    println!("\nSummary (tlk {command}):");
+    for (dir, outcome) in &results {
+        match outcome {
+            Ok(()) => println!("  ok      {}", dir.display()),
+            Err(e) => println!("  FAILED  {} ({e})", dir.display()),
+        }
+    }
+    println!(
+        "{} project(s), {} ok, {failed} failed",
+        results.len(),
+        results.len() - failed
+    );
+    if failed > 0 {
+        anyhow::bail!("{failed} project(s) failed");
+    }
+    Ok(())
+}
+
+fn read_repos(file: &str) -> Result<Vec<PathBuf>> {
+    let raw = std::fs::read_to_string(file).with_context(|| format!("reading {file}"))?;
+    Ok(raw
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(PathBuf::from)
+        .collect())
+}
+
+fn discover(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        if dir.join("tlk.toml").is_file() {
+            found.push(dir.clone());
+        }
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(e) => e,
+            Err(e) if dir == root => {
+                return Err(e).with_context(|| format!("reading {}", root.display()))
+            }
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let Ok(ft) = entry.file_type() else { continue };
+            // Symlinked directories are not followed to avoid cycles.
+            if !ft.is_dir() {
+                continue;
+            }
+            let name = entry.file_name();
+            if SKIP_DIRS.iter().any(|s| name == *s) {
+                continue;
+            }
+            stack.push(entry.path());
+        }
+    }
+    found.sort();
+    Ok(found)
+}
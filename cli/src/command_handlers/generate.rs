@@ -0,0 +1,136 @@
+use crate::config::TlkConfig;
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+// Editor integration: write .vscode/{tasks,settings,extensions}.json so IDE terminals and tasks
+// see the pinned tools without the shell hook. Existing files are merged (tlk-owned tasks are
+// replaced by label, settings keys overwritten, recommendations unioned); files VS Code accepts
+// but serde_json cannot parse (comments / trailing commas) are left alone with a warning.
+pub fn generate_vscode(cfg: Option<&TlkConfig>) -> Result<()> {
+    let dir = Path::new(".vscode");
+    std::fs::create_dir_all(dir).with_context(|| "creating .vscode")?;
+    merge_file(&dir.join("tasks.json"), merge_tasks)?;
+    let bin_dir = cfg
+        .and_then(|c| c.settings.bin_dir.clone())
+        .unwrap_or_else(|| ".tlk/bin".into());
+    merge_file(&dir.join("settings.json"), |v| merge_settings(v, &bin_dir))?;
+    let recommended = recommended_extensions(cfg);
+    if !recommended.is_empty() {
+        merge_file(&dir.join("extensions.json"), |v| {
+            merge_recommendations(v, &recommended)
+        })?;
+    }
+    Ok(())
+}
+
+fn merge_file(path: &Path, merge: impl Fn(&mut Value)) -> Result<()> {
+    let mut value = if path.exists() {
+        let raw = std::fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?;
+        match serde_json::from_str::<Value>(&raw) {
+            Ok(v) if v.is_object() => v,
+            _ => {
+                eprintln!("Warning: {} is not plain JSON; skipping", path.display());
+                return Ok(());
+            }
+        }
+    } else {
+        Value::Object(Map::new())
+    };
+    merge(&mut value);
+    let out = serde_json::to_string_pretty(&value)?;
+    std::fs::write(path, format!("{out}\n")).with_context(|| format!("writing {path:?}"))?;
+    println!("Updated {}", path.display());
+    Ok(())
+}
+
+fn merge_tasks(root: &mut Value) {
+    let obj = root.as_object_mut().expect("checked object");
+    obj.entry("version").or_insert(json!("2.0.0"));
+    let tasks = obj.entry("tasks").or_insert(json!([]));
+    if !tasks.is_array() {
+        *tasks = json!([]);
+    }
+    let items = tasks.as_array_mut().expect("checked array");
+    let ours = [
+        ("tlk: install", "tlk install"),
+        ("tlk: verify", "tlk verify"),
+        ("tlk: update", "tlk update"),
+    ];
+    items.retain(|t| {
+        let label = t.get("label").and_then(|l| l.as_str()).unwrap_or("");
+        !ours.iter().any(|(l, _)| *l == label)
+    });
+    for (label, command) in ours {
+        items.push(json!({
+            "label": label,
+            "type": "shell",
+            "command": command,
+            "problemMatcher": [],
+        }));
+    }
+}
+
+fn merge_settings(root: &mut Value, bin_dir: &str) {
+    let obj = root.as_object_mut().expect("checked object");
+    let (unix_dir, windows_dir) = if Path::new(bin_dir).is_absolute() {
+        (bin_dir.to_string(), bin_dir.to_string())
+    } else {
+        (
+            format!("${{workspaceFolder}}/{bin_dir}"),
+            format!("${{workspaceFolder}}\\{}", bin_dir.replace('/', "\\")),
+        )
+    };
+    let unix_path = format!("{unix_dir}:${{env:PATH}}");
+    let windows_path = format!("{windows_dir};${{env:PATH}}");
+    for (key, path) in [
+        ("terminal.integrated.env.linux", &unix_path),
+        ("terminal.integrated.env.osx", &unix_path),
+        ("terminal.integrated.env.windows", &windows_path),
+    ] {
+        let env = obj.entry(key).or_insert(json!({}));
+        if !env.is_object() {
+            *env = json!({});
+        }
+        env.as_object_mut()
+            .expect("checked object")
+            .insert("PATH".to_string(), json!(path));
+    }
+}
+
+fn merge_recommendations(root: &mut Value, recommended: &[&str]) {
+    let obj = root.as_object_mut().expect("checked object");
+    let recs = obj.entry("recommendations").or_insert(json!([]));
+    if !recs.is_array() {
+        *recs = json!([]);
+    }
+    let items = recs.as_array_mut().expect("checked array");
+    for ext in recommended {
+        if !items.iter().any(|v| v.as_str() == Some(ext)) {
+            items.push(json!(ext));
+        }
+    }
+}
+
+fn recommended_extensions(cfg: Option<&TlkConfig>) -> Vec<&'static str> {
+    let Some(cfg) = cfg else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    for t in &cfg.tools {
+        let ext = match t.name.as_str() {
+            "terraform" => Some("hashicorp.terraform"),
+            "kubectl" | "helm" => Some("ms-kubernetes-tools.vscode-kubernetes-tools"),
+            "buf" => Some("bufbuild.vscode-buf"),
+            "just" => Some("nefrob.vscode-just-syntax"),
+            "moon" => Some("moonrepo.moon-console"),
+            _ => None,
+        };
+        if let Some(ext) = ext {
+            if !out.contains(&ext) {
+                out.push(ext);
+            }
+        }
+    }
+    out
+}
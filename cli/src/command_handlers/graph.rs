@@ -0,0 +1,127 @@
+use crate::config::{TlkConfig, Via};
+use anyhow::Result;
+use serde_json::json;
+
+// graph: the relationships between declared tools, as Graphviz dot or JSON. Edges are the ones
+// tlk acts on: package managers run through node's corepack (`via = "corepack"`), commands a
+// tool ships and shims (npm/npx with node), and `when` conditions gating a tool. Tools skipped
+// by their condition in this environment are still drawn, marked as skipped.
+pub fn graph(cfg: Option<&TlkConfig>, format: &str) -> Result<()> {
+    let Some(cfg) = cfg else {
+        anyhow::bail!("No config provided for graph");
+    };
+    let (nodes, edges) = build(cfg);
+    if format == "json" {
+        let nodes: Vec<_> = nodes
+            .iter()
+            .map(|n| {
+                json!({
+                    "id": n.id,
+                    "kind": n.kind,
+                    "version": n.version,
+                    "skipped": n.skipped,
+                    "declared": n.declared,
+                })
+            })
+            .collect();
+        let edges: Vec<_> = edges
+            .iter()
+            .map(|(from, to, relation)| json!({ "from": from, "to": to, "relation": relation }))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "nodes": nodes, "edges": edges }))?
+        );
+        return Ok(());
+    }
+    println!("digraph tlk {{");
+    println!("  rankdir=LR;");
+    println!("  node [shape=box];");
+    for n in &nodes {
+        let label = match &n.version {
+            Some(v) => format!("{}\\n{}", n.id, v),
+            None => n.id.clone(),
+        };
+        let mut attrs = vec![format!("label=\"{}\"", escape(&label))];
+        match n.kind {
+            "command" => attrs.push("shape=ellipse".into()),
+            "condition" => attrs.push("shape=diamond".into()),
+            _ => {}
+        }
+        if n.skipped || !n.declared {
+            attrs.push("style=dashed".into());
+        }
+        println!("  \"{}\" [{}];", escape(&n.id), attrs.join(", "));
+    }
+    for (from, to, relation) in &edges {
+        println!(
+            "  \"{}\" -> \"{}\" [label=\"{relation}\"];",
+            escape(from),
+            escape(to)
+        );
+    }
+    println!("}}");
+    Ok(())
+}
+
+struct Node {
+    id: String,
+    kind: &'static str,
+    version: Option<String>,
+    skipped: bool,
+    // false for tools referenced by an edge but missing from tlk.toml
+    declared: bool,
+}
+
+fn build(cfg: &TlkConfig) -> (Vec<Node>, Vec<(String, String, &'static str)>) {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut edges = Vec::new();
+    let declared = cfg.tools.iter().map(|t| (t, false));
+    let skipped = cfg.skipped.iter().map(|t| (t, true));
+    for (tool, skipped) in declared.chain(skipped) {
+        nodes.push(Node {
+            id: tool.name.clone(),
+            kind: "tool",
+            version: Some(tool.version.clone()),
+            skipped,
+            declared: true,
+        });
+        if tool.via == Some(Via::Corepack) {
+            edges.push((tool.name.clone(), "node".to_string(), "via corepack"));
+        }
+        for (command, _) in crate::installer::toolchain_commands(tool) {
+            // A command named like the tool is the tool itself (a shim into its toolchain).
+            if command != tool.name {
+                edges.push((tool.name.clone(), command, "provides"));
+            }
+        }
+        if let Some(when) = tool.when.as_deref().map(str::trim) {
+            edges.push((format!("when {when}"), tool.name.clone(), "gates"));
+        }
+    }
+    // Endpoints that are not declared tools: shipped commands, conditions, and a corepack
+    // toolchain missing from tlk.toml.
+    for (from, to, relation) in &edges {
+        for (id, kind) in [(from, "condition"), (to, "command")] {
+            if nodes.iter().any(|n| &n.id == id) {
+                continue;
+            }
+            let kind = match *relation {
+                "via corepack" => "tool",
+                _ => kind,
+            };
+            nodes.push(Node {
+                id: id.clone(),
+                kind,
+                version: None,
+                skipped: false,
+                declared: kind != "tool",
+            });
+        }
+    }
+    (nodes, edges)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('"', "\\\"")
+}
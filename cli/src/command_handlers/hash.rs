@@ -0,0 +1,193 @@
+use crate::command_handlers::manifests::windows_direct_url;
+use crate::config::{host_platform_key, TlkConfig, Tool, ToolKind};
+use crate::naming::{canonical_arch, canonical_os};
+use crate::{http, installer};
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use toml::Value;
+
+// hash: sha256 of a local file or downloaded artifact, printed as the lowercase hex tlk.toml and
+// tlk.lock expect. With --tool the checksum is also written into that tool's entry: as the single
+// `sha256`, or under a platform key when --platform is given or the entry already holds a
+// per-platform table. Without a file/URL, --tool hashes the tool's own artifact for --platform
+// (this machine by default), so the checksum always describes the artifact it is stored for.
+pub fn hash(
+    config_path: &str,
+    lock_path: &str,
+    target: Option<&str>,
+    tool: Option<&str>,
+    platform: Option<&str>,
+) -> Result<()> {
+    let platform = platform.map(canonical_platform).transpose()?;
+    let platform = platform.as_deref();
+    let sha = match (target, tool) {
+        (Some(target), _) if target.starts_with("http://") || target.starts_with("https://") => {
+            hash_url(target)?
+        }
+        (Some(path), _) => hash_file(path)?,
+        (None, Some(name)) => hash_tool_artifact(config_path, lock_path, name, platform)?,
+        (None, None) => anyhow::bail!("give a file or URL to hash, or --tool <name>"),
+    };
+    println!("{sha}");
+    let Some(name) = tool else {
+        return Ok(());
+    };
+    let raw = fs::read_to_string(config_path).with_context(|| format!("reading {config_path}"))?;
+    let mut root: Value = raw.parse()?;
+    let key = set_sha256(&mut root, name, &sha, platform)?;
+    fs::write(config_path, toml::to_string_pretty(&root)?)
+        .with_context(|| format!("writing {config_path}"))?;
+    match key {
+        Some(key) => eprintln!("Wrote {name}.sha256.\"{key}\" to {config_path}"),
+        None => eprintln!("Wrote {name}.sha256 to {config_path}"),
+    }
+    Ok(())
+}
+
+fn hash_file(path: &str) -> Result<String> {
+    let mut file = fs::File::open(path).with_context(|| format!("opening {path}"))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).with_context(|| format!("reading {path}"))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+pub fn hash_url(url: &str) -> Result<String> {
+    let mut hasher = Sha256::new();
+    if let Some(file) = crate::fixture::download(url) {
+        std::io::copy(&mut file?, &mut hasher).with_context(|| format!("reading {url}"))?;
+        return Ok(hex::encode(hasher.finalize()));
+    }
+    let client = http::client()?;
+    let mut resp = http::send(client.get(crate::installer::mirror_url(url)))
+        .with_context(|| format!("GET {url}"))?
+        .error_for_status()
+        .with_context(|| format!("GET {url}"))?;
+    std::io::copy(&mut resp, &mut hasher).with_context(|| format!("downloading {url}"))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// `os-arch` in the naming of source templates and lock keys (macos-aarch64 -> darwin-arm64).
+fn canonical_platform(platform: &str) -> Result<String> {
+    let Some((os, arch)) = platform
+        .split_once('-')
+        .filter(|(o, a)| !o.is_empty() && !a.is_empty())
+    else {
+        anyhow::bail!("invalid platform '{platform}' (expected os-arch, e.g. darwin-arm64)");
+    };
+    Ok(format!("{}-{}", canonical_os(os), canonical_arch(arch)))
+}
+
+// The artifact install would fetch on `platform` (this machine when None); ranges use the locked
+// version.
+fn hash_tool_artifact(
+    config_path: &str,
+    lock_path: &str,
+    name: &str,
+    platform: Option<&str>,
+) -> Result<String> {
+    let tool = locked_tool(config_path, lock_path, name)?;
+    let url = match platform.and_then(|p| p.split_once('-')) {
+        Some((os, arch)) if format!("{os}-{arch}") != host_platform_key() => {
+            let (exact, _) = installer::normalize_version(&tool.version);
+            let url = tool.source_for(&exact, os, arch);
+            if os == "windows" && matches!(tool.kind, ToolKind::Direct) {
+                windows_direct_url(&url)
+            } else {
+                url
+            }
+        }
+        _ => installer::render_source(&tool),
+    };
+    eprintln!("Hashing {name} {} ({url})", tool.version);
+    hash_url(&url)
+}
+
+/// Declared tool `name` with a range version replaced by its locked version.
+pub fn locked_tool(config_path: &str, lock_path: &str, name: &str) -> Result<Tool> {
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("no readable config at {config_path}");
+    };
+    let Some(tool) = cfg
+        .tools
+        .iter()
+        .chain(&cfg.skipped)
+        .find(|t| t.name == name)
+    else {
+        anyhow::bail!("tool '{name}' is not declared in {config_path}");
+    };
+    let mut tool = tool.clone();
+    if installer::is_range(&tool.version) {
+        let locked = crate::lock::LockFile::load(lock_path)?
+            .and_then(|l| l.tools.get(name).map(|e| e.version.clone()));
+        let Some(version) = locked else {
+            anyhow::bail!(
+                "{name} {} is a range with no locked version; run 'tlk install' first or pass a URL",
+                tool.version
+            );
+        };
+        tool.version = version;
+    }
+    Ok(tool)
+}
+
+// Returns the platform key written under, if any.
+fn set_sha256(
+    root: &mut Value,
+    name: &str,
+    sha: &str,
+    platform: Option<&str>,
+) -> Result<Option<String>> {
+    let entry = if root.get(name).is_some() {
+        root.get_mut(name)
+    } else {
+        match root.get_mut("tools") {
+            Some(Value::Table(t)) => t.get_mut(name),
+            Some(Value::Array(items)) => items
+                .iter_mut()
+                .find(|it| it.get("name").and_then(|v| v.as_str()) == Some(name)),
+            _ => None,
+        }
+    };
+    let Some(entry) = entry else {
+        anyhow::bail!("tool '{name}' not found in config");
+    };
+    // Shorthand `helm = "3.14.0"` becomes `helm = { version = "3.14.0", sha256 = "…" }`.
+    if let Value::String(version) = entry {
+        let mut t = toml::Table::new();
+        t.insert("version".into(), Value::String(version.clone()));
+        *entry = Value::Table(t);
+    }
+    let Value::Table(t) = entry else {
+        anyhow::bail!("tool '{name}' has an unsupported config entry");
+    };
+    let per_platform = matches!(t.get("sha256"), Some(Value::Table(_)));
+    let key = match platform {
+        Some(p) => Some(p.to_string()),
+        None if per_platform => Some(host_platform_key()),
+        None => None,
+    };
+    match &key {
+        Some(key) => {
+            if !per_platform {
+                // A single sum covers this machine too: keep it under the host key, not dropped.
+                let mut table = toml::Table::new();
+                if let Some(Value::String(previous)) = t.get("sha256") {
+                    let host = host_platform_key();
+                    if *key != host {
+                        eprintln!("Kept the previous {name}.sha256 as {name}.sha256.\"{host}\"");
+                        table.insert(host, Value::String(previous.clone()));
+                    }
+                }
+                t.insert("sha256".into(), Value::Table(table));
+            }
+            if let Some(Value::Table(table)) = t.get_mut("sha256") {
+                table.insert(key.clone(), Value::String(sha.to_string()));
+            }
+        }
+        None => {
+            t.insert("sha256".into(), Value::String(sha.to_string()));
+        }
+    }
+    Ok(key)
+}
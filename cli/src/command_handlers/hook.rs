@@ -1,4 +1,6 @@
+use crate::installer;
 use anyhow::Result;
+use std::path::PathBuf;
 
 // Public entry: print the appropriate hook script. For now we just ignore shell arg difference
 // and output a POSIX-friendly function that should work in bash and zsh.
@@ -6,6 +8,7 @@ pub fn print_hook(shell: Option<&str>) -> Result<()> {
     match shell.map(|s| s.to_lowercase()) {
         Some(ref s) if s == "fish" => println!("{}", FISH_HOOK),
         Some(ref s) if s == "powershell" || s == "pwsh" => println!("{}", POWERSHELL_HOOK),
+        Some(ref s) if s == "cmd" => println!("{}", CMD_HOOK),
         _ => println!("{}", POSIX_HOOK),
     }
     Ok(())
@@ -14,11 +17,12 @@ pub fn print_hook(shell: Option<&str>) -> Result<()> {
 // The hook strategy:
 // - Define a function _tlk_sync_path invoked on every prompt (PROMPT_COMMAND / precmd)
 // - Walk up from $PWD to filesystem root looking for tlk.toml.
-// - If found, ensure $PROJECT/.tlk/bin exists; prepend (once) via an exported PATH containing TLK_ACTIVE_BIN.
+// - If found, ensure the project bin dir ($PROJECT/.tlk/bin or [settings] bin_dir) exists; prepend (once) via an exported PATH containing TLK_ACTIVE_BIN.
 // - If not found and TLK_ACTIVE_BIN was previously set, remove it from PATH.
 // - Use an exported TLK_ACTIVE_BIN var to track currently active bin path.
 // - Avoid repeated expensive scans by caching last $PWD in TLK_LAST_PWD.
 // - Keep modifications idempotent and reversible.
+// - Run `hash -r` when ~/.tlk/install-stamp changes (bumped by install / uninstall).
 // User usage: eval "$(tlk hook)"  OR tlk hook --shell bash | source /dev/stdin
 const POSIX_HOOK: &str = r##"
 # tlk dynamic PATH activation hook
@@ -55,7 +59,39 @@ _tlk_path_remove() {
   export PATH
 }
 
+# Project install dir: [settings] bin_dir from tlk.toml (relative to the root), else .tlk/bin.
+_tlk_bin_dir() {
+  local line section="" dir=".tlk/bin"
+  while IFS= read -r line || [ -n "$line" ]; do
+    case "$line" in
+      \[*) section="$line" ;;
+      bin_dir*=*)
+        if [ "$section" = "[settings]" ]; then
+          dir="${line#*=}"; dir="${dir#*[\"\']}"; dir="${dir%%[\"\']*}"
+        fi
+        ;;
+    esac
+  done < "$1/tlk.toml"
+  case "$dir" in
+    /*) printf '%s\n' "$dir" ;;
+    *) printf '%s\n' "$1/$dir" ;;
+  esac
+}
+
+# tlk install / uninstall bump ~/.tlk/install-stamp; forget cached command lookups when it changes
+# so a new binary wins over the one the shell remembered.
+_tlk_rehash() {
+  local stamp=""
+  [ -r "$HOME/.tlk/install-stamp" ] || return 0
+  IFS= read -r stamp < "$HOME/.tlk/install-stamp"
+  if [ "$stamp" != "$TLK_INSTALL_STAMP" ]; then
+    TLK_INSTALL_STAMP="$stamp"
+    hash -r
+  fi
+}
+
 _tlk_sync_path() {
+  _tlk_rehash
   # Fast path: if directory unchanged, exit
   if [ "$PWD" = "$TLK_LAST_PWD" ]; then
     return 0
@@ -65,7 +101,8 @@ _tlk_sync_path() {
 
   local root
   if root=$(_tlk_find_project_root); then
-    local bindir="$root/.tlk/bin"
+    local bindir
+    bindir=$(_tlk_bin_dir "$root")
     if [ -d "$bindir" ]; then
       if [ "$TLK_ACTIVE_BIN" != "$bindir" ]; then
         # switching context
@@ -152,6 +189,27 @@ function __tlk_path_remove
     set -gx PATH $new
 end
 
+# Project install dir: [settings] bin_dir from tlk.toml (relative to the root), else .tlk/bin.
+function __tlk_bin_dir
+    set -l section ""
+    set -l dir ".tlk/bin"
+    for line in (cat $argv[1]/tlk.toml)
+        if string match -qr '^\[' -- $line
+            set section (string trim -- $line)
+        else if test "$section" = "[settings]"
+            set -l m (string match -r '^\s*bin_dir\s*=\s*["\']([^"\']*)["\']' -- $line)
+            if test (count $m) -ge 2
+                set dir $m[2]
+            end
+        end
+    end
+    if string match -q '/*' -- $dir
+        echo $dir
+    else
+        echo "$argv[1]/$dir"
+    end
+end
+
 function __tlk_sync_path --on-event fish_prompt
     if test "$PWD" = "$TLK_LAST_PWD"
         return
@@ -159,7 +217,7 @@ function __tlk_sync_path --on-event fish_prompt
     set -gx TLK_LAST_PWD $PWD
     set -l root (__tlk_find_root)
     if test -n "$root"
-        set -l bindir "$root/.tlk/bin"
+        set -l bindir (__tlk_bin_dir $root)
         if test -d $bindir
             if test "$TLK_ACTIVE_BIN" != $bindir
                 if test -n "$TLK_ACTIVE_BIN"
@@ -203,12 +261,24 @@ function Remove-TlkPath([string]$target) {
   $Env:PATH = ($parts -join ';')
 }
 
+# Project install dir: [settings] bin_dir from tlk.toml (relative to the root), else .tlk/bin.
+function Get-TlkBinDir([string]$root) {
+  $section = ''
+  $dir = '.tlk/bin'
+  foreach ($line in Get-Content (Join-Path $root 'tlk.toml')) {
+    if ($line -match '^\s*\[') { $section = $line.Trim() }
+    elseif ($section -eq '[settings]' -and $line -match '^\s*bin_dir\s*=\s*["'']([^"'']*)["'']') { $dir = $Matches[1] }
+  }
+  if ([IO.Path]::IsPathRooted($dir)) { return $dir }
+  return Join-Path $root $dir
+}
+
 function global:prompt {
   if ($PWD.Path -ne $Env:TLK_LAST_PWD) {
     $Env:TLK_LAST_PWD = $PWD.Path
     $root = Get-TlkProjectRoot
     if ($root) {
-      $bindir = Join-Path $root '.tlk/bin'
+      $bindir = Get-TlkBinDir $root
       if (Test-Path $bindir) {
         if ($Env:TLK_ACTIVE_BIN -ne $bindir) {
           if ($Env:TLK_ACTIVE_BIN) { Remove-TlkPath $Env:TLK_ACTIVE_BIN }
@@ -229,3 +299,50 @@ function global:prompt {
 # Initial sync
 & global:prompt > $null
 "#;
+
+// shim-path: the PATH sync of the other hooks, done in Rust for cmd.exe, which cannot script it.
+// Prints `set` lines (and an activation notice) for the cmd hook to execute; an empty output
+// means nothing changed.
+pub fn shim_path() -> Result<()> {
+    let active = std::env::var_os("TLK_ACTIVE_BIN").map(PathBuf::from);
+    let bindir = installer::project_root()
+        .map(|root| crate::config::project_bin_dir(&root))
+        .filter(|dir| dir.is_dir());
+    if bindir == active {
+        return Ok(());
+    }
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let mut parts: Vec<PathBuf> = std::env::split_paths(&path)
+        .filter(|p| Some(p) != active.as_ref())
+        .collect();
+    match &bindir {
+        Some(dir) => {
+            if !parts.contains(dir) {
+                parts.insert(0, dir.clone());
+            }
+            println!("set \"TLK_ACTIVE_BIN={}\"", dir.display());
+            println!("echo [tlk] activated {}", dir.display());
+        }
+        None => println!("set \"TLK_ACTIVE_BIN=\""),
+    }
+    let joined = std::env::join_paths(parts)?;
+    println!("set \"PATH={}\"", joined.to_string_lossy());
+    Ok(())
+}
+
+// cmd.exe has no prompt hook: wrap the directory-changing builtins in doskey macros that run the
+// builtin, then apply `tlk shim-path`. Registered through the Command Processor AutoRun value,
+// which also runs for `cmd /c`, so non-interactive shells return before doing any work.
+const CMD_HOOK: &str = r#"@echo off
+rem tlk dynamic PATH activation (cmd.exe)
+rem Save as %USERPROFILE%\tlk-hook.cmd and run it for every cmd.exe through AutoRun:
+rem   tlk hook --shell cmd > "%USERPROFILE%\tlk-hook.cmd"
+rem   reg add "HKCU\Software\Microsoft\Command Processor" /v AutoRun /t REG_EXPAND_SZ /d "\"%%USERPROFILE%%\tlk-hook.cmd\"" /f
+rem Switching drives with a bare "D:" does not re-sync; use cd /d.
+echo %CMDCMDLINE% | find /i " /c " >nul && goto :eof
+doskey cd=cd $* $T for /f "delims=" %%i in ('tlk shim-path') do @%%i
+doskey chdir=chdir $* $T for /f "delims=" %%i in ('tlk shim-path') do @%%i
+doskey pushd=pushd $* $T for /f "delims=" %%i in ('tlk shim-path') do @%%i
+doskey popd=popd $T for /f "delims=" %%i in ('tlk shim-path') do @%%i
+for /f "delims=" %%i in ('tlk shim-path') do @%%i
+"#;
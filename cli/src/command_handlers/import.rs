@@ -0,0 +1,366 @@
+use crate::command_handlers::add::set_version;
+use crate::command_handlers::install::resolve_version;
+use crate::config::{TlkConfig, Via};
+use crate::installer::normalize_version;
+use crate::versioning;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use toml::Value;
+
+// import package-json: derive tlk pins from package.json so JS repos keep one source of truth.
+// `packageManager` ("pnpm@9.1.0+sha512.…") pins pnpm / yarn exactly (yarn 2+ only ships through
+// corepack, so it gets via = "corepack"); `engines.node` (">=18.17", "^20 || ^22") pins node to a
+// caret on the newest release it allows. Without --write the pins are only printed.
+pub fn package_json(config_path: &str, path: &str, write: bool) -> Result<()> {
+    let wanted = read_package_json(Path::new(path))?;
+    if wanted.is_empty() {
+        println!("{path} declares neither packageManager nor engines.node (nothing to import)");
+        return Ok(());
+    }
+    let mut root = read_config(config_path)?;
+    for want in &wanted {
+        let version = match &want.requirement {
+            Requirement::Exact(v) => v.clone(),
+            Requirement::Range(range) => {
+                let newest = resolve_version(&want.tool, &npm_range_to_tlk(range))
+                    .with_context(|| format!("resolving {} from {}", want.tool, want.field))?;
+                format!("^{newest}")
+            }
+        };
+        let via = if want.corepack {
+            ", via = \"corepack\""
+        } else {
+            ""
+        };
+        println!(
+            "{} = \"{version}\"{via}  (from {} \"{}\")",
+            want.tool, want.field, want.declared
+        );
+        if write {
+            set_version(&mut root, &want.tool, &version)?;
+            if want.corepack {
+                set_corepack(&mut root, &want.tool);
+            }
+        }
+    }
+    if write {
+        fs::write(config_path, toml::to_string_pretty(&root)?)
+            .with_context(|| format!("writing {config_path}"))?;
+        println!("Updated {config_path}; run `tlk install` to apply");
+    } else {
+        println!("(dry run: pass --write to update {config_path})");
+    }
+    Ok(())
+}
+
+// import terraform: pin terraform to the newest release every `required_version` in the
+// directory's *.tf files allows. The pin is exact: terraform constraints usually carry an upper
+// bound ("~> 1.5") that a caret range would step over.
+pub fn terraform(config_path: &str, dir: &str, write: bool) -> Result<()> {
+    let found = read_required_versions(Path::new(dir))?;
+    if found.is_empty() {
+        println!("No required_version in {dir}/*.tf (nothing to import)");
+        return Ok(());
+    }
+    let all = versioning::fetch_all_versions("terraform")?;
+    let newest = all
+        .iter()
+        .find(|v| found.iter().all(|(_, c)| terraform_matches(c, v)))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "no terraform release satisfies {}",
+                describe_constraints(&found)
+            )
+        })?;
+    println!(
+        "terraform = \"{newest}\"  (from required_version {})",
+        describe_constraints(&found)
+    );
+    if !write {
+        println!("(dry run: pass --write to update {config_path})");
+        return Ok(());
+    }
+    let mut root = read_config(config_path)?;
+    set_version(&mut root, "terraform", &newest.to_string())?;
+    fs::write(config_path, toml::to_string_pretty(&root)?)
+        .with_context(|| format!("writing {config_path}"))?;
+    println!("Updated {config_path}; run `tlk install` to apply");
+    Ok(())
+}
+
+/// Warn when tlk.toml disagrees with the package.json or terraform files next to it. Never fails
+/// the install and never touches the network.
+pub fn warn_manifests(config_path: &str, cfg: &TlkConfig) {
+    let dir = Path::new(config_path).parent().unwrap_or(Path::new(""));
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    warn_package_json(dir, cfg);
+    warn_terraform(dir, cfg);
+}
+
+// Only a declared terraform is checked: plenty of repos with *.tf files get terraform elsewhere.
+fn warn_terraform(dir: &Path, cfg: &TlkConfig) {
+    let Some(tool) = cfg.tools.iter().find(|t| t.name == "terraform") else {
+        return;
+    };
+    let Ok(found) = read_required_versions(dir) else {
+        return;
+    };
+    let (pinned, _) = normalize_version(&tool.version);
+    let Ok(version) = semver::Version::parse(&pinned) else {
+        return;
+    };
+    let broken: Vec<_> = found
+        .iter()
+        .filter(|(_, c)| !terraform_matches(c, &version))
+        .cloned()
+        .collect();
+    if !broken.is_empty() {
+        eprintln!(
+            "Warning: tlk.toml pins terraform {} but required_version says {} \
+             (see `tlk import terraform`)",
+            tool.version,
+            describe_constraints(&broken)
+        );
+    }
+}
+
+fn warn_package_json(dir: &Path, cfg: &TlkConfig) {
+    let path = dir.join("package.json");
+    if !path.is_file() {
+        return;
+    }
+    let Ok(wanted) = read_package_json(&path) else {
+        return;
+    };
+    for want in wanted {
+        let Some(tool) = cfg.tools.iter().find(|t| t.name == want.tool) else {
+            eprintln!(
+                "Warning: package.json {} wants {} \"{}\" but tlk.toml does not declare {} \
+                 (see `tlk import package-json`)",
+                want.field, want.tool, want.declared, want.tool
+            );
+            continue;
+        };
+        let (pinned, _) = normalize_version(&tool.version);
+        let ok = match &want.requirement {
+            Requirement::Exact(v) => &pinned == v,
+            Requirement::Range(range) => npm_range_matches(range, &pinned),
+        };
+        if !ok {
+            eprintln!(
+                "Warning: tlk.toml pins {} {} but package.json {} says \"{}\" \
+                 (see `tlk import package-json`)",
+                tool.name, tool.version, want.field, want.declared
+            );
+        } else if want.corepack && tool.via != Some(Via::Corepack) {
+            eprintln!(
+                "Warning: {} {} is only distributed through corepack; set via = \"corepack\" for it \
+                 in tlk.toml",
+                tool.name, pinned
+            );
+        }
+    }
+}
+
+fn read_config(config_path: &str) -> Result<Value> {
+    let raw = match fs::read_to_string(config_path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("reading {config_path}")),
+    };
+    raw.parse()
+        .with_context(|| format!("parsing {config_path}"))
+}
+
+enum Requirement {
+    Exact(String),
+    /// npm semver range, as written
+    Range(String),
+}
+
+struct Wanted {
+    tool: String,
+    field: &'static str,
+    declared: String,
+    requirement: Requirement,
+    corepack: bool,
+}
+
+fn read_package_json(path: &Path) -> Result<Vec<Wanted>> {
+    let data = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let pkg: serde_json::Value =
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))?;
+    let mut out = Vec::new();
+    if let Some(pm) = pkg.get("packageManager").and_then(|v| v.as_str()) {
+        // name@version, optionally followed by +<algo>.<hash> for corepack
+        let Some((name, rest)) = pm.split_once('@') else {
+            anyhow::bail!("packageManager \"{pm}\" is not of the form name@version");
+        };
+        let version = rest.split('+').next().unwrap_or(rest).to_string();
+        match name {
+            "pnpm" | "yarn" => out.push(Wanted {
+                tool: name.to_string(),
+                field: "packageManager",
+                declared: pm.to_string(),
+                corepack: name == "yarn"
+                    && semver::Version::parse(&version).is_ok_and(|v| v.major >= 2),
+                requirement: Requirement::Exact(version),
+            }),
+            // npm comes with node; anything else has no recipe.
+            "npm" => {}
+            other => eprintln!("Note: packageManager {other} has no built-in recipe; skipped"),
+        }
+    }
+    if let Some(range) = pkg.pointer("/engines/node").and_then(|v| v.as_str()) {
+        out.push(Wanted {
+            tool: "node".into(),
+            field: "engines.node",
+            declared: range.to_string(),
+            requirement: Requirement::Range(range.to_string()),
+            corepack: false,
+        });
+    }
+    Ok(out)
+}
+
+// npm writes comparator sets space separated (">=18 <21") where tlk / the semver crate want
+// commas; "||" alternatives and hyphen ranges are understood by resolve_version as they are.
+fn npm_range_to_tlk(range: &str) -> String {
+    range
+        .split("||")
+        .map(|set| {
+            let set = set.trim();
+            if set.contains(" - ") {
+                set.to_string()
+            } else {
+                set.split_whitespace().collect::<Vec<_>>().join(", ")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" || ")
+}
+
+fn npm_range_matches(range: &str, version: &str) -> bool {
+    let Ok(version) = semver::Version::parse(version) else {
+        return false;
+    };
+    npm_range_to_tlk(range).split("||").any(|set| {
+        let set = set.trim();
+        let req = match set.split_once(" - ") {
+            Some((lo, hi)) => format!(">={}, <={}", lo.trim(), hi.trim()),
+            None => set.replace(['x', 'X'], "*"),
+        };
+        semver::VersionReq::parse(&req).is_ok_and(|r| r.matches(&version))
+    })
+}
+
+// Turn a shorthand into an inline table (or extend a table) with via = "corepack".
+fn set_corepack(root: &mut Value, name: &str) {
+    let Some(tbl) = root.as_table_mut() else {
+        return;
+    };
+    let entry = match tbl.get_mut(name) {
+        Some(entry) => entry,
+        None => match tbl.get_mut("tools").and_then(|t| t.get_mut(name)) {
+            Some(entry) => entry,
+            None => return,
+        },
+    };
+    if let Value::String(version) = entry {
+        let mut inline = toml::Table::new();
+        inline.insert("version".into(), Value::String(version.clone()));
+        *entry = Value::Table(inline);
+    }
+    if let Value::Table(t) = entry {
+        t.insert("via".into(), Value::String("corepack".into()));
+    }
+}
+
+// (file name, constraint) for every `required_version = "..."` in dir/*.tf.
+fn read_required_versions(dir: &Path) -> Result<Vec<(String, String)>> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "tf"))
+        .collect();
+    files.sort();
+    let mut out = Vec::new();
+    for file in files {
+        let text =
+            fs::read_to_string(&file).with_context(|| format!("reading {}", file.display()))?;
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        for line in text.lines() {
+            let Some(rest) = line.trim().strip_prefix("required_version") else {
+                continue;
+            };
+            let Some(value) = rest.trim_start().strip_prefix('=') else {
+                continue;
+            };
+            let value = value.trim();
+            if let Some(constraint) = value
+                .strip_prefix('"')
+                .and_then(|v| v.split_once('"'))
+                .map(|(c, _)| c)
+            {
+                out.push((name.to_string(), constraint.to_string()));
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn describe_constraints(found: &[(String, String)]) -> String {
+    found
+        .iter()
+        .map(|(file, c)| format!("\"{c}\" ({file})"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Terraform constraint syntax: comma separated `=`, `!=`, `>`, `>=`, `<`, `<=` and `~>`
+// (pessimistic: only the rightmost given component may grow).
+fn terraform_matches(constraint: &str, version: &semver::Version) -> bool {
+    constraint.split(',').all(|part| {
+        let part = part.trim();
+        if part.is_empty() {
+            return true;
+        }
+        let op_len = part
+            .find(|c: char| c.is_ascii_digit() || c == 'v')
+            .unwrap_or(part.len());
+        let (op, raw) = (part[..op_len].trim(), part[op_len..].trim());
+        let raw = raw.trim_start_matches('v');
+        let parts: Vec<u64> = raw
+            .split(['.', '-'])
+            .take(3)
+            .map_while(|p| p.parse().ok())
+            .collect();
+        if parts.is_empty() {
+            return false;
+        }
+        let at = |i: usize| parts.get(i).copied().unwrap_or(0);
+        let given = semver::Version::parse(raw)
+            .unwrap_or_else(|_| semver::Version::new(at(0), at(1), at(2)));
+        match op {
+            "" | "=" => *version == given,
+            "!=" => *version != given,
+            ">" => *version > given,
+            ">=" => *version >= given,
+            "<" => *version < given,
+            "<=" => *version <= given,
+            "~>" => {
+                let upper = match parts.len() {
+                    1 | 2 => semver::Version::new(at(0) + 1, 0, 0),
+                    _ => semver::Version::new(at(0), at(1) + 1, 0),
+                };
+                *version >= given && *version < upper
+            }
+            _ => false,
+        }
+    })
+}
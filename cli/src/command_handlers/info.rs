@@ -0,0 +1,79 @@
+use crate::config::TlkConfig;
+use crate::installer;
+use crate::known_tools::{self, build_known_tool, known_tools_map};
+use anyhow::Result;
+
+// info: what a tool is and where tlk gets it from. Works for known tools and for custom tools
+// declared in tlk.toml; the source shown is the one for this platform.
+pub fn info(cfg: Option<&TlkConfig>, name: &str) -> Result<()> {
+    let declared = cfg.and_then(|c| c.tools.iter().find(|t| t.name == name));
+    let known = build_known_tool(name, "{version}").ok();
+    let Some(tool) = declared.or(known.as_ref()) else {
+        anyhow::bail!("unknown tool '{name}' (see `tlk search`)");
+    };
+    println!("{name}");
+    if let Some(meta) = known_tools::metadata(name) {
+        println!("  {}", meta.description);
+        println!("  homepage: {}", meta.homepage);
+        println!("  license:  {}", meta.license);
+    }
+    let source = match (declared, &known) {
+        (Some(t), _) => installer::render_source(t),
+        (None, Some(k)) => k.source.clone(),
+        (None, None) => String::new(),
+    };
+    println!("  source:   {source}");
+    let fallbacks = known_tools::fallback_sources(name);
+    if !fallbacks.is_empty() {
+        println!("  fallback sources: {}", fallbacks.len());
+    }
+    let companions: Vec<_> = known_tools::companions(name)
+        .iter()
+        .map(|c| c.name)
+        .collect();
+    if !companions.is_empty() {
+        println!("  also installs: {}", companions.join(", "));
+    }
+    match declared {
+        Some(t) => {
+            let installed =
+                installer::find_installed_version(t).unwrap_or_else(|_| "<not installed>".into());
+            println!("  declared: {}  installed: {installed}", tool.version);
+        }
+        None => println!("  not declared in tlk.toml (tlk add {name})"),
+    }
+    Ok(())
+}
+
+// search: known tools whose name or description contains the query (case-insensitive).
+pub fn search(query: &str) -> Result<()> {
+    let query = query.to_lowercase();
+    let mut names: Vec<_> = known_tools_map().into_keys().collect();
+    names.sort();
+    let matches: Vec<(&str, &str)> = names
+        .into_iter()
+        .map(|name| {
+            (
+                name,
+                known_tools::metadata(name).map_or("", |m| m.description),
+            )
+        })
+        .filter(|(name, description)| {
+            name.contains(&query) || description.to_lowercase().contains(&query)
+        })
+        .collect();
+    let width = matches
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, description) in &matches {
+        println!("{name:<width$} {description}");
+    }
+    if matches.is_empty() {
+        println!(
+            "No known tool matches '{query}'; custom tools can still be declared with a source URL"
+        );
+    }
+    Ok(())
+}
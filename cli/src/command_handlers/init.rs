@@ -0,0 +1,131 @@
+use crate::config::ProbeSettings;
+use crate::known_tools::known_tools_map;
+use crate::platform::platform;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+// init: scaffold a starter tlk.toml. Known tools can be seeded from an asdf-style
+// .tool-versions file (--from) or from binaries already on PATH (--detect, offered
+// interactively when neither flag is given on a terminal). Never overwrites an existing config.
+pub fn init(config_path: &str, from: Option<&str>, detect: bool) -> Result<()> {
+    if Path::new(config_path).exists() {
+        anyhow::bail!("{config_path} already exists; edit it or remove it first");
+    }
+    let mut tools: BTreeMap<String, String> = BTreeMap::new();
+    let mut unsupported: Vec<(String, String)> = Vec::new();
+    if let Some(file) = from {
+        for (name, version) in read_tool_versions(file)? {
+            if known_tools_map().contains_key(name.as_str()) {
+                tools.insert(name, version);
+            } else {
+                unsupported.push((name, version));
+            }
+        }
+    }
+    let detect = detect
+        || (from.is_none()
+            && std::io::stdin().is_terminal()
+            && ask("Seed tlk.toml from known tools found on PATH?")?);
+    if detect {
+        for (name, version) in detect_on_path() {
+            tools.entry(name).or_insert(version);
+        }
+    }
+
+    let mut out = String::from(
+        "# Tools pinned for this project.\n\
+         # Known tools: name = \"version\" (exact, a range like \"^1.7\", or \"latest\").\n\
+         # Other tools: [tools.<name>] with version, source (URL template) and kind.\n\n",
+    );
+    if tools.is_empty() {
+        out.push_str("# terraform = \"1.7.5\"\n");
+    }
+    for (name, version) in &tools {
+        out.push_str(&format!("{name} = \"{version}\"\n"));
+    }
+    for (name, version) in &unsupported {
+        out.push_str(&format!(
+            "# {name} {version}: no built-in recipe; declare it under [tools.{name}]\n"
+        ));
+    }
+    std::fs::write(config_path, out).with_context(|| format!("writing {config_path}"))?;
+
+    println!("Created {config_path} with {} tool(s).", tools.len());
+    println!("Next steps:");
+    println!("  tlk install          # download the tools into .tlk/bin and write tlk.lock");
+    println!("  eval \"$(tlk hook)\"   # put .tlk/bin on PATH in this shell ('tlk setup' makes it permanent)");
+    println!("  git add {config_path} tlk.lock");
+    Ok(())
+}
+
+// asdf names that differ from tlk's
+fn tlk_name(asdf: &str) -> &str {
+    match asdf {
+        "nodejs" => "node",
+        other => other,
+    }
+}
+
+fn read_tool_versions(file: &str) -> Result<Vec<(String, String)>> {
+    let raw = std::fs::read_to_string(file).with_context(|| format!("reading {file}"))?;
+    Ok(raw
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default().trim())
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            let name = parts.next()?;
+            let version = parts.next()?.trim_start_matches('v');
+            Some((tlk_name(name).to_string(), version.to_string()))
+        })
+        .collect())
+}
+
+// Known tools found on PATH (outside this project's .tlk/bin) with the version they report, or
+// "latest" when `--version` gives nothing parseable.
+fn detect_on_path() -> Vec<(String, String)> {
+    let local = std::env::current_dir()
+        .map(|d| crate::config::project_bin_dir(&d))
+        .unwrap_or_default();
+    let dirs: Vec<_> = std::env::var_os("PATH")
+        .map(|p| std::env::split_paths(&p).collect())
+        .unwrap_or_default();
+    let found: Vec<_> = known_tools_map()
+        .into_keys()
+        .filter_map(|name| {
+            let bin = platform().final_binary_name(name);
+            dirs.iter()
+                .filter(|d| **d != local)
+                .map(|d| d.join(&bin))
+                .find(|p| p.is_file())
+                .map(|p| (name.to_string(), p))
+        })
+        .collect();
+    let settings = ProbeSettings::default();
+    std::thread::scope(|s| {
+        let handles: Vec<_> = found
+            .iter()
+            .map(|(name, path)| {
+                let settings = &settings;
+                s.spawn(move || {
+                    let version = crate::probe::probe_version(path, settings)
+                        .ok()
+                        .filter(|v| v != "unknown" && v != "unprobed")
+                        .unwrap_or_else(|| "latest".to_string());
+                    println!("Found {name} {version} at {}", path.display());
+                    (name.clone(), version)
+                })
+            })
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    })
+}
+
+fn ask(prompt: &str) -> Result<bool> {
+    print!("{prompt} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    Ok(matches!(line.trim(), "y" | "Y" | "yes"))
+}
@@ -0,0 +1,120 @@
+use crate::command_handlers::hash::locked_tool;
+use crate::config::ToolKind;
+use crate::installer::{self, ArchiveFormat};
+use crate::naming::{canonical_arch, canonical_os};
+use anyhow::{Context, Result};
+use std::io::BufReader;
+use std::path::Path;
+
+// inspect-archive: the entry tree of a tool's archive (from the download cache, or downloaded into
+// it) with the entries the binary candidate rules match, to answer "binary not found in archive"
+// without unpacking by hand. Candidates come from `binary` / `archive_entries` and this machine's
+// platform rules; the last matching entry is the one install would use.
+pub fn inspect_archive(
+    config_path: &str,
+    lock_path: &str,
+    name: &str,
+    platform: Option<&str>,
+) -> Result<()> {
+    let tool = locked_tool(config_path, lock_path, name)?;
+    if matches!(tool.kind, ToolKind::Direct) {
+        anyhow::bail!("{name} is a direct download (kind = \"direct\"), not an archive");
+    }
+    let url = match platform {
+        Some(key) => {
+            let Some((os, arch)) = key.split_once('-') else {
+                anyhow::bail!("platform must look like os-arch (e.g. linux-amd64), got '{key}'");
+            };
+            let (exact, _) = installer::normalize_version(&tool.version);
+            tool.source_for(&exact, canonical_os(os), canonical_arch(arch))
+        }
+        None => installer::render_source(&tool),
+    };
+    let artifact = installer::fetch_cached(&tool, &url)?;
+    let candidates = installer::archive_candidates(&tool);
+
+    // (path, size, is_dir, matches)
+    let entries: Vec<(String, u64, bool, bool)> = match ArchiveFormat::detect(&artifact, &url)? {
+        Some(format @ (ArchiveFormat::TarGz | ArchiveFormat::Tar)) => {
+            let mut archive = tar::Archive::new(format.tar_reader(artifact.reader()?));
+            let mut out = Vec::new();
+            for entry in archive
+                .entries()
+                .with_context(|| format!("reading {}", format.name()))?
+            {
+                let e = entry?;
+                let path = e.path()?.into_owned();
+                let is_dir = e.header().entry_type().is_dir();
+                let matches = installer::tar_entry_matches(&candidates, &path);
+                out.push((
+                    path.to_string_lossy().into_owned(),
+                    e.size(),
+                    is_dir,
+                    matches,
+                ));
+            }
+            out
+        }
+        Some(ArchiveFormat::Zip) => {
+            let mut zip =
+                zip::ZipArchive::new(BufReader::new(artifact.reader()?)).context("reading zip")?;
+            let mut out = Vec::new();
+            for i in 0..zip.len() {
+                let file = zip.by_index(i)?;
+                let matches = installer::zip_entry_matches(&candidates, file.name());
+                out.push((file.name().to_string(), file.size(), file.is_dir(), matches));
+            }
+            out
+        }
+        Some(ArchiveFormat::Gz) => {
+            anyhow::bail!("{url} is a single gzip'd binary, installed as {name} whatever its name")
+        }
+        None => anyhow::bail!("unsupported archive type for {url}"),
+    };
+
+    println!("{url}");
+    println!("  {} bytes, sha256 {}", artifact.size, artifact.sha256);
+    println!("Candidates: {}", candidates.join(", "));
+    if platform.is_some_and(|p| p != crate::config::host_platform_key()) {
+        println!(
+            "  (platform rules of this machine; the target platform may look for other names)"
+        );
+    }
+    println!();
+    let installed = entries.iter().rposition(|(_, _, dir, m)| *m && !dir);
+    for (i, (path, size, is_dir, matches)) in entries.iter().enumerate() {
+        let trimmed = path.trim_end_matches('/');
+        let depth = Path::new(trimmed).components().count().saturating_sub(1);
+        let leaf = trimmed.rsplit('/').next().unwrap_or(trimmed);
+        let label = if *is_dir {
+            format!("{leaf}/")
+        } else {
+            leaf.to_string()
+        };
+        let marker = match (Some(i) == installed, *matches && !is_dir) {
+            (true, _) => "  <- match (installed)",
+            (false, true) => "  <- match (overridden by a later entry)",
+            _ => "",
+        };
+        let size = if *is_dir {
+            String::new()
+        } else {
+            size.to_string()
+        };
+        let line = format!(
+            "{:indent$}{label:<40} {size:>10}{marker}",
+            "",
+            indent = depth * 2
+        );
+        println!("{}", line.trim_end());
+    }
+    match installed {
+        Some(_) => Ok(()),
+        None => {
+            println!();
+            anyhow::bail!(
+                "no entry matches; set `binary` or `archive_entries` for {name} to one of the paths above"
+            )
+        }
+    }
+}
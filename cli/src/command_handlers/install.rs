@@ -1,6 +1,7 @@
+use crate::command_handlers::{import, lock, plan, which};
 use crate::config::TlkConfig;
 use crate::{installer, ops, versioning};
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 pub struct InstallArgs<'a> {
     pub write_lock: bool,
@@ -8,6 +9,12 @@ pub struct InstallArgs<'a> {
     pub no_verify: bool,
     pub specs: &'a [String],
     pub exact: bool,
+    pub system: bool,
+    pub force: bool,
+    pub reinstall: &'a [String],
+    pub from_plan: Option<&'a str>,
+    pub prune_lock: bool,
+    pub allow_dirty: bool,
     pub config_path: &'a str,
     pub cfg: Option<&'a TlkConfig>,
 }
@@ -16,23 +23,68 @@ pub fn run_install(args: InstallArgs) -> Result<()> {
     if args.locked && !args.specs.is_empty() {
         anyhow::bail!("--locked cannot be combined with specs");
     }
+    if !args.reinstall.is_empty() && (args.locked || !args.specs.is_empty()) {
+        anyhow::bail!("--reinstall cannot be combined with --locked or specs");
+    }
+    let opts = installer::InstallOptions {
+        force: args.force || !args.reinstall.is_empty(),
+    };
+    let _project = crate::lock::lock_project("tlk.lock", "install")?;
+    if let Some(plan_path) = args.from_plan {
+        if args.locked || !args.specs.is_empty() || !args.reinstall.is_empty() {
+            anyhow::bail!("--from-plan cannot be combined with --locked, --reinstall or specs");
+        }
+        return plan::install_from_plan(plan_path, args.config_path, args.cfg, &opts);
+    }
+    if let (true, Some(cfg)) = (args.specs.is_empty(), args.cfg) {
+        import::warn_manifests(args.config_path, cfg);
+    }
+    let system_dir = if args.system {
+        let dir = crate::platform::platform()
+            .system_bin_dir()
+            .ok_or_else(|| anyhow::anyhow!("no system install location on this platform"))?;
+        Some(dir.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    let system_cfg = match (&system_dir, args.cfg) {
+        (Some(dir), Some(cfg)) => Some(cfg.with_install_dir(dir)),
+        _ => None,
+    };
+    let full_cfg = system_cfg.as_ref().or(args.cfg);
+    // --reinstall narrows what gets installed; lock writes still cover the full config.
+    let reinstall_cfg = if args.reinstall.is_empty() {
+        None
+    } else {
+        Some(only_tools(full_cfg, args.reinstall)?)
+    };
+    let cfg = reinstall_cfg.as_ref().or(full_cfg);
     if args.locked {
         if args.write_lock {
             eprintln!("Note: --locked implies no lock updates; ignoring implied write");
         }
-        ops::install_locked("tlk.lock", args.cfg)?;
+        ops::install_locked("tlk.lock", cfg, &opts, None)?;
         return Ok(());
     }
+    // Without a lock write the existing lock must already be consistent (both install paths).
+    if !args.write_lock && !args.no_verify {
+        installer::verify_lockfile(full_cfg, "tlk.lock")?;
+    }
     if args.specs.is_empty() {
-        if !args.write_lock && !args.no_verify {
-            installer::verify_lockfile(args.cfg, "tlk.lock")?;
-        }
-        installer::install_all(args.cfg)?;
+        installer::install_all(cfg, &opts)?;
         if args.write_lock {
-            installer::write_lockfile(args.cfg, "tlk.lock")?;
+            installer::write_lockfile(full_cfg, "tlk.lock")?;
+        }
+        if let Some(cfg) = cfg.filter(|c| c.settings.path_check != Some(false)) {
+            which::warn_shadowed(cfg);
+        }
+        if args.prune_lock {
+            lock::prune(args.config_path, "tlk.lock", false)?;
         }
         return Ok(());
     }
+    // Spec installs rewrite tlk.toml (and usually tlk.lock); keep them out of unrelated changes.
+    crate::git::ensure_clean(args.config_path, args.allow_dirty)?;
     // Resolve requested known tools (currently only known-tool path supported for multi-spec)
     let mut resolved = Vec::new();
     for spec in args.specs {
@@ -47,12 +99,16 @@ pub fn run_install(args: InstallArgs) -> Result<()> {
         } else {
             versioning::fetch_latest(&name)?
         };
-        let tool = crate::known_tools::build_known_tool(&name, &version)?;
+        let mut tool = crate::known_tools::build_known_tool(&name, &version)?;
+        if let Some(cfg) = cfg {
+            cfg.settings.apply(&mut tool);
+        }
+        tool.install_dir = system_dir.clone();
         resolved.push((tool, per_spec_latest, ver_opt.clone()));
     }
     // Parallel install
     let tools_only: Vec<_> = resolved.iter().map(|(t, _, _)| t.clone()).collect();
-    let results = installer::install_tools_parallel(&tools_only);
+    let results = installer::install_tools_parallel(&tools_only, &opts);
     // Report using earlier collected metadata
     for (tool, per_spec_latest, _) in &resolved {
         let success = results
@@ -88,16 +144,37 @@ pub fn run_install(args: InstallArgs) -> Result<()> {
     }
     // Lock update only if requested
     if args.write_lock {
-        for (tool, _per_spec_latest, _original_spec) in &resolved {
-            if let Err(e) = ops::write_single_lock(tool) {
-                eprintln!("Warning: failed to update lock for {}: {e}", tool.name);
-            }
+        let installed: Vec<_> = tools_only
+            .iter()
+            .filter(|t| results.iter().any(|(n, r)| n == &t.name && r.is_ok()))
+            .cloned()
+            .collect();
+        if let Err(e) = ops::write_locks(cfg, &installed) {
+            eprintln!("Warning: failed to update lock: {e}");
         }
     }
+    if args.prune_lock {
+        lock::prune(args.config_path, "tlk.lock", false)?;
+    }
     Ok(())
 }
 
-fn parse_spec(spec: &str) -> anyhow::Result<(String, Option<String>)> {
+// Narrow the config to the named tools for --reinstall.
+fn only_tools(cfg: Option<&TlkConfig>, names: &[String]) -> Result<TlkConfig> {
+    let Some(cfg) = cfg else {
+        anyhow::bail!("--reinstall requires a config");
+    };
+    let mut narrowed = cfg.clone();
+    narrowed.tools.retain(|t| names.contains(&t.name));
+    for name in names {
+        if !narrowed.tools.iter().any(|t| &t.name == name) {
+            anyhow::bail!("tool '{name}' is not declared in config");
+        }
+    }
+    Ok(narrowed)
+}
+
+pub fn parse_spec(spec: &str) -> anyhow::Result<(String, Option<String>)> {
     if let Some((n, v)) = spec.split_once('@') {
         Ok((n.to_string(), Some(v.to_string())))
     } else {
@@ -105,12 +182,37 @@ fn parse_spec(spec: &str) -> anyhow::Result<(String, Option<String>)> {
     }
 }
 
+/// Specs listed in `source` ("-" for stdin) for `install --specs-from`: one per line (a range may
+/// contain spaces), with blank lines and `#` comments skipped. A list without specs is an error rather than "install all".
+pub fn read_specs(source: &str) -> Result<Vec<String>> {
+    let text = if source == "-" {
+        std::io::read_to_string(std::io::stdin()).context("reading specs from stdin")?
+    } else {
+        std::fs::read_to_string(source).with_context(|| format!("reading specs from {source}"))?
+    };
+    let mut specs = Vec::new();
+    for line in text.lines() {
+        let spec = line.split('#').next().unwrap_or_default().trim();
+        if spec.is_empty() {
+            continue;
+        }
+        specs.push(spec.to_string());
+    }
+    if specs.is_empty() {
+        anyhow::bail!("no specs in {source}");
+    }
+    Ok(specs)
+}
+
 // build_known_tool moved to known_tools::build_known_tool
 
-fn resolve_version(name: &str, spec: &str) -> anyhow::Result<String> {
+pub fn resolve_version(name: &str, spec: &str) -> anyhow::Result<String> {
     if semver::Version::parse(spec).is_ok() {
         return Ok(spec.to_string());
     }
+    if let Some(version) = versioning::resolve_channel(name, spec)? {
+        return Ok(version);
+    }
     let all = versioning::fetch_all_versions(name)?;
     if spec.contains("||") {
         let mut best: Option<semver::Version> = None;
@@ -144,15 +246,13 @@ fn resolve_version(name: &str, spec: &str) -> anyhow::Result<String> {
     }
     let mut normalized = spec.replace('*', "x");
     if semver::Version::parse(&normalized).is_err() {
-        if normalized.chars().filter(|c| *c == '.').count() == 1
+        let dots = normalized.chars().filter(|c| *c == '.').count();
+        let partial_minor = dots == 1
             && !normalized.contains('x')
             && !normalized.contains('^')
-            && !normalized.contains('~')
-        {
-            normalized.push_str(".x");
-        } else if normalized.chars().filter(|c| *c == '.').count() == 0
-            && normalized.chars().all(|c| c.is_ascii_digit())
-        {
+            && !normalized.contains('~');
+        let bare_major = dots == 0 && normalized.chars().all(|c| c.is_ascii_digit());
+        if partial_minor || bare_major {
             normalized.push_str(".x");
         }
     }
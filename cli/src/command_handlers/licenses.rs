@@ -0,0 +1,118 @@
+use crate::config::{LicensePolicy, TlkConfig, Tool};
+use crate::{installer, known_tools, versioning};
+use anyhow::Result;
+use serde_json::json;
+
+// licenses: the license of every pinned tool, from the tool's own `license`, the built-in recipe,
+// or GitHub's license detection for github.com release sources. With [settings.licenses] the
+// report doubles as a CI gate: denied licenses, and with an allowlist anything not on it
+// (including licenses that could not be determined), fail the command.
+pub fn licenses(cfg: Option<&TlkConfig>, json_output: bool) -> Result<()> {
+    let Some(cfg) = cfg else {
+        anyhow::bail!("No config provided for licenses");
+    };
+    let found: Vec<(Option<String>, &'static str)> = std::thread::scope(|s| {
+        let handles: Vec<_> = cfg
+            .tools
+            .iter()
+            .map(|t| s.spawn(|| license_of(t)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or((None, "unknown")))
+            .collect()
+    });
+    let policy = cfg.settings.licenses.clone().unwrap_or_default();
+
+    let mut rows = Vec::new();
+    for (t, (license, from)) in cfg.tools.iter().zip(found) {
+        let verdict = evaluate(&policy, license.as_deref());
+        rows.push((t.name.clone(), t.version.clone(), license, from, verdict));
+    }
+    let violations = rows.iter().filter(|r| r.4.is_some()).count();
+
+    if json_output {
+        let items: Vec<_> = rows
+            .iter()
+            .map(|(name, version, license, from, verdict)| {
+                json!({
+                    "name": name,
+                    "version": version,
+                    "license": license,
+                    "from": from,
+                    "violation": verdict,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "tools": items }))?
+        );
+    } else {
+        let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(4).max(4);
+        println!(
+            "{:<width$}  {:<12}  {:<16}  FROM",
+            "TOOL", "VERSION", "LICENSE"
+        );
+        for (name, version, license, from, verdict) in &rows {
+            println!(
+                "{:<width$}  {:<12}  {:<16}  {}{}",
+                name,
+                version,
+                license.as_deref().unwrap_or("?"),
+                from,
+                verdict
+                    .as_deref()
+                    .map(|v| format!("  ({v})"))
+                    .unwrap_or_default()
+            );
+        }
+    }
+    if violations > 0 {
+        anyhow::bail!("{violations} tool(s) violate [settings.licenses]");
+    }
+    Ok(())
+}
+
+fn license_of(tool: &Tool) -> (Option<String>, &'static str) {
+    if let Some(license) = &tool.license {
+        return (Some(license.clone()), "tlk.toml");
+    }
+    let (version, _) = installer::normalize_version(&tool.version);
+    if let Some(license) = known_tools::license(&tool.name, &version) {
+        return (Some(license.to_string()), "recipe");
+    }
+    let Some((owner, repo)) = github_repo(&tool.source) else {
+        return (None, "unknown");
+    };
+    match versioning::github_license(owner, repo) {
+        Ok(license) => (license, "github"),
+        Err(e) => {
+            eprintln!("Warning: {}: {e:#}", tool.name);
+            (None, "unknown")
+        }
+    }
+}
+
+// owner/repo of https://github.com/<owner>/<repo>/releases/download/... sources.
+fn github_repo(source: &str) -> Option<(&str, &str)> {
+    let rest = source
+        .strip_prefix("https://github.com/")
+        .or_else(|| source.strip_prefix("http://github.com/"))?;
+    let mut parts = rest.split('/');
+    let owner = parts.next().filter(|p| !p.is_empty())?;
+    let repo = parts.next().filter(|p| !p.is_empty())?;
+    Some((owner, repo))
+}
+
+fn evaluate(policy: &LicensePolicy, license: Option<&str>) -> Option<String> {
+    let listed = |list: &[String], id: &str| list.iter().any(|l| l.eq_ignore_ascii_case(id));
+    match license {
+        Some(id) if listed(&policy.deny, id) => Some("denied".into()),
+        Some(id) if !policy.allow.is_empty() && !listed(&policy.allow, id) => {
+            Some("not in allow list".into())
+        }
+        None if !policy.allow.is_empty() => Some("unknown license".into()),
+        _ => None,
+    }
+}
@@ -0,0 +1,203 @@
+use crate::command_handlers::{hash::hash_url, manifests::windows_direct_url};
+use crate::config::host_platform_key;
+use crate::config::{TlkConfig, ToolKind};
+use crate::installer;
+use crate::naming::{canonical_arch, canonical_os};
+use anyhow::Result;
+use serde_json::json;
+use std::collections::HashMap;
+
+// lock prune: drop lock entries for tools no longer declared in config. The config is re-read
+// from disk so tools added by the current command (spec installs) are kept.
+pub fn prune(config_path: &str, lock_path: &str, json_output: bool) -> Result<()> {
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("cannot prune {lock_path}: no readable config at {config_path}");
+    };
+    let _guard = crate::lock::lock_for_write(lock_path)?;
+    let Some(mut lock) = crate::lock::LockFile::load(lock_path)? else {
+        if json_output {
+            println!("{}", json!({ "pruned": [] }));
+        } else {
+            println!("No {lock_path} present; nothing to prune.");
+        }
+        return Ok(());
+    };
+    let mut pruned: Vec<(String, String)> = lock
+        .tools
+        .iter()
+        .filter(|(name, _)| {
+            !cfg.tools
+                .iter()
+                .chain(&cfg.skipped)
+                .any(|t| &t.name == *name)
+        })
+        .map(|(name, entry)| (name.clone(), entry.version.clone()))
+        .collect();
+    pruned.sort();
+    if !pruned.is_empty() {
+        for (name, _) in &pruned {
+            lock.tools.remove(name);
+        }
+        lock.save(lock_path)?;
+    }
+    if json_output {
+        let items: Vec<_> = pruned
+            .iter()
+            .map(|(name, version)| json!({ "name": name, "version": version }))
+            .collect();
+        println!("{}", json!({ "pruned": items }));
+    } else if pruned.is_empty() {
+        println!("{lock_path} has no entries for removed tools");
+    } else {
+        for (name, version) in &pruned {
+            println!("- {name} {version}");
+        }
+        println!("Pruned {} tool(s) from {lock_path}", pruned.len());
+    }
+    Ok(())
+}
+
+// lock bless: accept a changed upstream artifact for TOFU-pinned tools. The locked version is
+// downloaded afresh (replacing any cached copy) and its sha256 replaces this platform's pin; the
+// other platforms' pins are kept, so each platform blesses its own artifact.
+pub fn bless(config_path: &str, lock_path: &str, names: &[String]) -> Result<()> {
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("no readable config at {config_path}");
+    };
+    let _guard = crate::lock::lock_for_write(lock_path)?;
+    let Some(mut lock) = crate::lock::LockFile::load(lock_path)? else {
+        anyhow::bail!("no lock file found at {lock_path}");
+    };
+    let platform = host_platform_key();
+    for name in names {
+        let Some(tool) = cfg.tools.iter().find(|t| &t.name == name) else {
+            anyhow::bail!("tool '{name}' is not declared in {config_path}");
+        };
+        if !crate::tofu::enabled(tool) {
+            anyhow::bail!("{name} is not TOFU-pinned (it has a sha256 or tofu is off)");
+        }
+        let Some(entry) = lock.tools.get_mut(name) else {
+            anyhow::bail!("{name} has no entry in {lock_path}; run 'tlk install' first");
+        };
+        let mut locked = tool.clone();
+        locked.version = entry.version.clone();
+        let sha = installer::fetch_fresh_sha256(&locked)?;
+        let old = entry
+            .tofu_sha256
+            .as_ref()
+            .and_then(|p| p.get(&platform))
+            .cloned();
+        match old {
+            Some(old) if old == sha => {
+                println!("{name} {}: pin unchanged ({sha})", entry.version)
+            }
+            Some(old) => println!("{name} {}: re-pinned {old} -> {sha}", entry.version),
+            None => println!("{name} {}: pinned {sha}", entry.version),
+        }
+        entry
+            .tofu_sha256
+            .get_or_insert_with(HashMap::new)
+            .insert(platform.clone(), sha);
+    }
+    lock.save(lock_path)?;
+    println!(
+        "Pins of other platforms are unchanged; run 'tlk lock bless' there if their artifacts \
+         changed too."
+    );
+    Ok(())
+}
+
+// lock add-platform: extend every lock entry with the download URL of another platform, for
+// when a new platform joins the team. Versions, digests and the other platforms' URLs are left
+// alone. With --checksums each new artifact is downloaded once and its sha256 becomes that
+// platform's TOFU pin; tools with configured checksums already carry theirs in tlk.toml.
+pub fn add_platform(
+    config_path: &str,
+    lock_path: &str,
+    platforms: &[String],
+    checksums: bool,
+) -> Result<()> {
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("no readable config at {config_path}");
+    };
+    let mut targets = Vec::new();
+    for p in platforms {
+        let Some((os, arch)) = p
+            .split_once('-')
+            .filter(|(o, a)| !o.is_empty() && !a.is_empty())
+        else {
+            anyhow::bail!("invalid platform '{p}' (expected os-arch, e.g. darwin-arm64)");
+        };
+        targets.push((
+            canonical_os(os).to_string(),
+            canonical_arch(arch).to_string(),
+        ));
+    }
+    let _guard = crate::lock::lock_for_write(lock_path)?;
+    let Some(mut lock) = crate::lock::LockFile::load(lock_path)? else {
+        anyhow::bail!("no lock file found at {lock_path}; run 'tlk install' first");
+    };
+    let mut names: Vec<String> = lock.tools.keys().cloned().collect();
+    names.sort();
+    let mut added = 0;
+    for name in &names {
+        let Some(tool) = cfg
+            .tools
+            .iter()
+            .chain(&cfg.skipped)
+            .find(|t| &t.name == name)
+        else {
+            eprintln!("Skipping {name}: not declared in {config_path}");
+            continue;
+        };
+        let entry = lock.tools.get_mut(name).expect("listed above");
+        for (os, arch) in &targets {
+            let key = format!("{os}-{arch}");
+            let url = tool.source_for(&entry.version, os, arch);
+            let listed = match &entry.sources {
+                Some(sources) => sources.get(&key) == Some(&url),
+                // Platform independent so far; a differing URL turns it into a matrix.
+                None => url == entry.source,
+            };
+            if !listed {
+                let sources = entry.sources.get_or_insert_with(|| {
+                    let own = entry.platform.clone().unwrap_or_else(host_platform_key);
+                    let own = match own.split_once('-') {
+                        Some((o, a)) => format!("{}-{}", canonical_os(o), canonical_arch(a)),
+                        None => own,
+                    };
+                    HashMap::from([(own, entry.source.clone())])
+                });
+                sources.insert(key.clone(), url.clone());
+                added += 1;
+                println!("{name} {}: {key} -> {url}", entry.version);
+            }
+            let pin = key.clone();
+            let pinned = entry
+                .tofu_sha256
+                .as_ref()
+                .is_some_and(|p| p.contains_key(&pin));
+            if checksums && crate::tofu::enabled(tool) && !pinned {
+                let url = if os == "windows" && matches!(tool.kind, ToolKind::Direct) {
+                    windows_direct_url(&url)
+                } else {
+                    url
+                };
+                let sha = hash_url(&url)?;
+                println!("{name} {}: {key} sha256 {sha}", entry.version);
+                entry
+                    .tofu_sha256
+                    .get_or_insert_with(HashMap::new)
+                    .insert(pin, sha);
+                added += 1;
+            }
+        }
+    }
+    if added == 0 {
+        println!("{lock_path} already covers {}", platforms.join(", "));
+        return Ok(());
+    }
+    lock.save(lock_path)?;
+    println!("Extended {lock_path} ({added} addition(s))");
+    Ok(())
+}
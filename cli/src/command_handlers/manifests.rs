@@ -0,0 +1,411 @@
+use crate::command_handlers::hash::hash_url;
+use crate::config::{TlkConfig, Tool, ToolKind};
+use crate::installer::{self, is_entry_pattern, ArchiveFormat};
+use crate::lock::{LockFile, LockedEntry};
+use crate::naming::{canonical_arch, canonical_os};
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::BufReader;
+
+// Package-manager manifests for a locked tool, so an internal tool pinned in tlk can also be
+// published to teammates through Homebrew, Scoop or winget. Everything comes from the lock entry:
+// the per-platform URLs of `sources` and their checksums (sha256 / sha256_by_platform /
+// tofu_sha256). Platforms without a recorded checksum are downloaded once to compute it.
+// Manifests are printed to stdout for review before they are committed to a tap or bucket.
+// A `binary` glob or `regex:` pattern means nothing to a package manager, so it is resolved to the
+// entry path it matches in each platform's archive (downloaded through the cache).
+
+struct Artifact {
+    url: String,
+    sha256: String,
+    /// Binary path inside this archive, when `binary` is a pattern
+    entry: Option<String>,
+}
+
+struct Locked {
+    name: String,
+    version: String,
+    kind: ToolKind,
+    /// Binary path inside the archive (archives only)
+    binary: String,
+    /// "os-arch" (canonical names, e.g. darwin-arm64) -> artifact
+    artifacts: BTreeMap<String, Artifact>,
+}
+
+// Lock entry of `name` with the artifacts of the given OSes.
+fn locked(cfg: Option<&TlkConfig>, lock_path: &str, name: &str, oses: &[&str]) -> Result<Locked> {
+    let Some(cfg) = cfg else {
+        anyhow::bail!("No config provided for manifest generation");
+    };
+    let Some(tool) = cfg
+        .tools
+        .iter()
+        .chain(&cfg.skipped)
+        .find(|t| t.name == name)
+    else {
+        anyhow::bail!("tool '{name}' is not declared in config");
+    };
+    let entry = LockFile::load(lock_path)?
+        .and_then(|l| l.tools.get(name).cloned())
+        .ok_or_else(|| {
+            anyhow::anyhow!("{name} has no entry in {lock_path}; run 'tlk install' first")
+        })?;
+    let binary = tool.binary.clone().unwrap_or_else(|| name.to_string());
+    let mut artifacts = BTreeMap::new();
+    for (key, url) in platform_sources(&entry) {
+        if !oses.iter().any(|os| key.starts_with(&format!("{os}-"))) {
+            continue;
+        }
+        // Windows installs of direct downloads fetch the .exe (see platform/windows.rs).
+        let url = if key.starts_with("windows-") && matches!(tool.kind, ToolKind::Direct) {
+            windows_direct_url(&url)
+        } else {
+            url
+        };
+        let recorded = recorded_sha256(&entry, &key);
+        let artifact = if matches!(tool.kind, ToolKind::Archive) && is_entry_pattern(&binary) {
+            resolve_entry(tool, &binary, &key, url, recorded)?
+        } else {
+            let sha256 = match recorded {
+                Some(sha) => sha,
+                None => {
+                    eprintln!("Computing sha256 of {url}");
+                    hash_url(&url)?
+                }
+            };
+            Artifact {
+                url,
+                sha256,
+                entry: None,
+            }
+        };
+        artifacts.insert(key, artifact);
+    }
+    Ok(Locked {
+        name: name.to_string(),
+        version: entry.version.clone(),
+        kind: tool.kind.clone(),
+        binary,
+        artifacts,
+    })
+}
+
+// Download the `key` archive and find the entry the `binary` pattern matches (the last one, as
+// install picks it), checking the download against the locked checksum.
+fn resolve_entry(
+    tool: &Tool,
+    binary: &str,
+    key: &str,
+    url: String,
+    recorded: Option<String>,
+) -> Result<Artifact> {
+    let mut unverified = tool.clone();
+    // The configured checksum describes this machine's artifact, not necessarily this one.
+    unverified.sha256 = None;
+    let artifact = installer::fetch_cached(&unverified, &url)?;
+    if let Some(sha) = recorded.as_ref() {
+        if !sha.eq_ignore_ascii_case(&artifact.sha256) {
+            anyhow::bail!(
+                "{url}: sha256 {} does not match the locked {sha}",
+                artifact.sha256
+            );
+        }
+    }
+    let mut candidates = tool.archive_entries.clone().unwrap_or_default();
+    candidates.push(binary.to_string());
+    if key.starts_with("windows-") && !binary.starts_with("regex:") {
+        candidates.push(format!("{binary}.exe"));
+    }
+    let found = match ArchiveFormat::detect(&artifact, &url)? {
+        Some(format @ (ArchiveFormat::TarGz | ArchiveFormat::Tar)) => {
+            let mut archive = tar::Archive::new(format.tar_reader(artifact.reader()?));
+            let mut found = None;
+            for entry in archive
+                .entries()
+                .with_context(|| format!("reading {url}"))?
+            {
+                let entry = entry?;
+                let path = entry.path()?.into_owned();
+                if entry.header().entry_type().is_file()
+                    && installer::tar_entry_matches(&candidates, &path)
+                {
+                    found = Some(path.to_string_lossy().into_owned());
+                }
+            }
+            found
+        }
+        Some(ArchiveFormat::Zip) => {
+            let mut zip =
+                zip::ZipArchive::new(BufReader::new(artifact.reader()?)).context("reading zip")?;
+            let mut found = None;
+            for i in 0..zip.len() {
+                let file = zip.by_index(i)?;
+                if !file.is_dir() && installer::zip_entry_matches(&candidates, file.name()) {
+                    found = Some(file.name().to_string());
+                }
+            }
+            found
+        }
+        _ => None,
+    };
+    let Some(entry) = found else {
+        anyhow::bail!(
+            "{}: binary = \"{binary}\" is a pattern and no entry of {url} matches it; \
+             set `binary` to the path inside the archive",
+            tool.name
+        );
+    };
+    Ok(Artifact {
+        url,
+        sha256: artifact.sha256,
+        entry: Some(entry.trim_start_matches("./").to_string()),
+    })
+}
+
+// File name of the binary for Homebrew's `Dir["**/<name>"]` lookup.
+fn binary_file_name(t: &Locked) -> Result<String> {
+    let mut names: Vec<&str> = t
+        .artifacts
+        .values()
+        .filter_map(|a| a.entry.as_deref())
+        .map(url_file_name)
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    match names.as_slice() {
+        [] => Ok(url_file_name(&t.binary).to_string()),
+        [name] => Ok(name.to_string()),
+        _ => anyhow::bail!(
+            "{}: binary = \"{}\" matches differently named files per platform ({})",
+            t.name,
+            t.binary,
+            names.join(", ")
+        ),
+    }
+}
+
+fn platform_key(key: &str) -> String {
+    match key.split_once('-') {
+        Some((os, arch)) => format!("{}-{}", canonical_os(os), canonical_arch(arch)),
+        None => key.to_string(),
+    }
+}
+
+fn platform_sources(entry: &LockedEntry) -> Vec<(String, String)> {
+    match &entry.sources {
+        Some(sources) => sources
+            .iter()
+            .map(|(k, url)| (platform_key(k), url.clone()))
+            .collect(),
+        None => {
+            let key = entry
+                .platform
+                .clone()
+                .unwrap_or_else(crate::config::host_platform_key);
+            vec![(platform_key(&key), entry.source.clone())]
+        }
+    }
+}
+
+// Package managers want sha256; sha512 / blake3 values are recomputed as sha256 instead.
+fn recorded_sha256(entry: &LockedEntry, key: &str) -> Option<String> {
+    recorded_checksum(entry, key)
+        .and_then(|sum| crate::checksum::sha256_hex(&sum).map(str::to_string))
+}
+
+fn recorded_checksum(entry: &LockedEntry, key: &str) -> Option<String> {
+    let find = |map: &std::collections::HashMap<String, String>| {
+        map.iter()
+            .find(|(k, _)| platform_key(k) == key)
+            .map(|(_, v)| v.clone())
+    };
+    if let Some(sha) = entry.sha256_by_platform.as_ref().and_then(find) {
+        return Some(sha);
+    }
+    if let Some(sha) = entry.tofu_sha256.as_ref().and_then(find) {
+        return Some(sha);
+    }
+    // A single sha256 only describes the artifact of the platform that wrote the lock.
+    let own = entry.platform.as_deref().map(platform_key);
+    let single = entry.sources.is_none() || own.as_deref() == Some(key);
+    entry.sha256.clone().filter(|_| single)
+}
+
+pub fn windows_direct_url(url: &str) -> String {
+    if [".exe", ".zip", ".tar.gz"]
+        .iter()
+        .any(|ext| url.ends_with(ext))
+    {
+        url.to_string()
+    } else {
+        format!("{url}.exe")
+    }
+}
+
+fn url_file_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+pub fn brew_formula(cfg: Option<&TlkConfig>, lock_path: &str, name: &str) -> Result<()> {
+    let t = locked(cfg, lock_path, name, &["darwin", "linux"])?;
+    let class: String = t
+        .name
+        .split(['-', '_', '.'])
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    let mut out = String::new();
+    out.push_str(&format!("class {class} < Formula\n"));
+    out.push_str(&format!("  desc \"{} (pinned by tlk)\"\n", t.name));
+    if let Some(a) = t.artifacts.values().next() {
+        let homepage = a.url.splitn(4, '/').take(3).collect::<Vec<_>>().join("/");
+        out.push_str(&format!("  homepage \"{homepage}\"\n"));
+    }
+    out.push_str(&format!("  version \"{}\"\n", t.version));
+    let mut any = false;
+    for (os, block) in [("darwin", "on_macos"), ("linux", "on_linux")] {
+        let arches: Vec<_> = [("arm64", "on_arm"), ("amd64", "on_intel")]
+            .into_iter()
+            .filter_map(|(arch, cond)| Some((cond, t.artifacts.get(&format!("{os}-{arch}"))?)))
+            .collect();
+        if arches.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n  {block} do\n"));
+        for (cond, a) in arches {
+            out.push_str(&format!("    {cond} do\n"));
+            out.push_str(&format!("      url \"{}\"\n", a.url));
+            out.push_str(&format!("      sha256 \"{}\"\n", a.sha256.to_lowercase()));
+            out.push_str("    end\n");
+            any = true;
+        }
+        out.push_str("  end\n");
+    }
+    if !any {
+        anyhow::bail!("{} has no macOS or Linux artifacts in the lock", t.name);
+    }
+    out.push_str("\n  def install\n");
+    match t.kind {
+        // Homebrew strips a single top-level directory when unpacking, so search for the binary.
+        ToolKind::Archive => {
+            let base = binary_file_name(&t)?;
+            out.push_str(&format!(
+                "    bin.install Dir[\"**/{base}\"].first => \"{}\"\n",
+                t.name
+            ));
+        }
+        ToolKind::Direct => out.push_str(&format!(
+            "    bin.install Dir[\"*\"].first => \"{}\"\n",
+            t.name
+        )),
+    }
+    out.push_str("  end\n\n  test do\n");
+    out.push_str(&format!(
+        "    system \"#{{bin}}/{}\", \"--version\"\n",
+        t.name
+    ));
+    out.push_str("  end\nend\n");
+    print!("{out}");
+    Ok(())
+}
+
+fn windows_artifacts(t: &Locked) -> Result<Vec<(&'static str, &'static str, &Artifact)>> {
+    // (scoop architecture, winget architecture, artifact)
+    let found: Vec<_> = [
+        ("64bit", "x64", "windows-amd64"),
+        ("arm64", "arm64", "windows-arm64"),
+    ]
+    .into_iter()
+    .filter_map(|(scoop, winget, key)| Some((scoop, winget, t.artifacts.get(key)?)))
+    .collect();
+    if found.is_empty() {
+        anyhow::bail!("{} has no Windows artifacts in the lock", t.name);
+    }
+    Ok(found)
+}
+
+fn exe_name(binary: &str) -> String {
+    if binary.ends_with(".exe") {
+        binary.to_string()
+    } else {
+        format!("{binary}.exe")
+    }
+}
+
+pub fn scoop_manifest(cfg: Option<&TlkConfig>, lock_path: &str, name: &str) -> Result<()> {
+    let t = locked(cfg, lock_path, name, &["windows"])?;
+    let mut architecture = serde_json::Map::new();
+    for (arch, _, a) in windows_artifacts(&t)? {
+        let mut entry = json!({ "url": a.url, "hash": a.sha256.to_lowercase() });
+        // Scoop keeps a direct download under its URL file name, which differs per architecture,
+        // as do archive paths resolved from a `binary` pattern.
+        if matches!(t.kind, ToolKind::Direct) {
+            entry["bin"] = json!([[url_file_name(&a.url), t.name]]);
+        } else if let Some(path) = &a.entry {
+            entry["bin"] = json!([[path, t.name]]);
+        }
+        architecture.insert(arch.to_string(), entry);
+    }
+    let mut manifest = json!({
+        "version": t.version,
+        "description": format!("{} (pinned by tlk)", t.name),
+        "architecture": architecture,
+    });
+    if matches!(t.kind, ToolKind::Archive) && !is_entry_pattern(&t.binary) {
+        manifest["bin"] = json!([[exe_name(&t.binary), t.name]]);
+    }
+    println!("{}", serde_json::to_string_pretty(&manifest)?);
+    Ok(())
+}
+
+pub fn winget_manifest(
+    cfg: Option<&TlkConfig>,
+    lock_path: &str,
+    name: &str,
+    publisher: &str,
+) -> Result<()> {
+    let t = locked(cfg, lock_path, name, &["windows"])?;
+    let mut out = String::new();
+    out.push_str(&format!("PackageIdentifier: {publisher}.{}\n", t.name));
+    out.push_str(&format!("PackageVersion: {}\n", t.version));
+    out.push_str("PackageLocale: en-US\n");
+    out.push_str(&format!("Publisher: {publisher}\n"));
+    out.push_str(&format!("PackageName: {}\n", t.name));
+    out.push_str("License: Proprietary\n");
+    out.push_str(&format!("ShortDescription: {} (pinned by tlk)\n", t.name));
+    out.push_str("Installers:\n");
+    for (_, arch, a) in windows_artifacts(&t)? {
+        out.push_str(&format!("  - Architecture: {arch}\n"));
+        match t.kind {
+            ToolKind::Archive => {
+                out.push_str("    InstallerType: zip\n");
+                out.push_str("    NestedInstallerType: portable\n");
+                out.push_str("    NestedInstallerFiles:\n");
+                let path = a.entry.clone().unwrap_or_else(|| exe_name(&t.binary));
+                out.push_str(&format!(
+                    "      - RelativeFilePath: {}\n",
+                    path.replace('/', "\\")
+                ));
+                out.push_str(&format!("        PortableCommandAlias: {}\n", t.name));
+            }
+            ToolKind::Direct => {
+                out.push_str("    InstallerType: portable\n");
+                out.push_str(&format!("    Commands:\n      - {}\n", t.name));
+            }
+        }
+        out.push_str(&format!("    InstallerUrl: {}\n", a.url));
+        out.push_str(&format!(
+            "    InstallerSha256: {}\n",
+            a.sha256.to_uppercase()
+        ));
+    }
+    out.push_str("ManifestType: singleton\nManifestVersion: 1.6.0\n");
+    print!("{out}");
+    Ok(())
+}
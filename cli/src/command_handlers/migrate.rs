@@ -1,5 +1,5 @@
 use crate::config::TlkConfig;
-use crate::installer::compute_installed_digest;
+use crate::installer::installed_digests;
 use crate::installer::render_source;
 use crate::lock::{to_locked_entry, LockFile};
 use anyhow::{Context, Result};
@@ -9,6 +9,7 @@ pub fn migrate_lock(cfg: Option<&TlkConfig>, path: &str) -> Result<()> {
     let Some(cfg) = cfg else {
         return Err(anyhow::anyhow!("No config provided for lock migration"));
     };
+    let _guard = crate::lock::lock_for_write(path)?;
     let Some(existing) = LockFile::load(path)? else {
         println!("No {path} found; nothing to migrate.");
         return Ok(());
@@ -17,23 +18,23 @@ pub fn migrate_lock(cfg: Option<&TlkConfig>, path: &str) -> Result<()> {
     // Rebuild entries from current config to ensure consistency (v3 map schema)
     use std::collections::HashMap;
     let mut map = HashMap::new();
+    for t in &cfg.skipped {
+        if let Some(entry) = existing.tools.get(&t.name) {
+            map.insert(t.name.clone(), entry.clone());
+        }
+    }
+    let mut digests = installed_digests(&cfg.tools);
     for t in &cfg.tools {
-        let digest = compute_installed_digest(t).ok();
+        let digest = digests.remove(&t.name).flatten();
         let tpl = t.effective_source_template(
             crate::known_tools::placeholder_os(),
             crate::known_tools::placeholder_arch(),
         );
         let (exact, requested) = normalize_version(&t.version);
         let rendered = render_source(t).replace(&t.version, &exact);
-        let (name, entry) = to_locked_entry(
-            &t.name,
-            &exact,
-            requested.as_deref(),
-            &rendered,
-            &tpl,
-            &t.sha256,
-            digest,
-        );
+        let (name, mut entry) =
+            to_locked_entry(t, &exact, requested.as_deref(), &rendered, &tpl, digest);
+        entry.tofu_sha256 = crate::tofu::pins(t, &exact, existing.tools.get(&name));
         map.insert(name, entry);
     }
     let lf = LockFile::new(map);
@@ -1,9 +1,39 @@
+pub mod add;
+pub mod baseline;
+pub mod bugreport;
+pub mod cache;
 pub mod dispatch;
+pub mod completions;
+pub mod env;
+pub mod exec;
+pub mod foreach;
+pub mod generate;
+pub mod graph;
+pub mod hash;
 pub mod hook;
+pub mod import;
+pub mod info;
+pub mod init;
+pub mod inspect;
 pub mod install;
+pub mod licenses;
+pub mod lock;
+pub mod manifests;
 pub mod migrate;
 pub mod diagnose;
 pub mod migrate_config;
+pub mod outdated;
+pub mod plan;
+pub mod registry;
+pub mod rename;
+pub mod require;
+pub mod schema;
+pub mod serve;
 pub mod setup;
+pub mod skew;
 pub mod specs;
 pub mod uninstall;
+pub mod update;
+pub mod verify;
+pub mod watch;
+pub mod which;
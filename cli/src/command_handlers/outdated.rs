@@ -0,0 +1,91 @@
+use crate::config::TlkConfig;
+use crate::{installer, versioning};
+use anyhow::Result;
+use serde_json::json;
+
+// outdated: installed, locked and latest upstream version of every declared tool. Exits non-zero
+// when anything lags the newest release so nightly CI jobs can flag drift. Tools whose upstream
+// cannot be listed (custom sources) show "?" and never count as behind.
+pub fn outdated(cfg: Option<&TlkConfig>, lock_path: &str, json_output: bool) -> Result<()> {
+    let Some(cfg) = cfg else {
+        anyhow::bail!("No config provided for outdated");
+    };
+    let lock = crate::lock::LockFile::load(lock_path)?;
+    let latest: Vec<Option<String>> = std::thread::scope(|s| {
+        let handles: Vec<_> = cfg
+            .tools
+            .iter()
+            .map(|t| s.spawn(|| versioning::fetch_latest(&t.name).ok()))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().ok().flatten())
+            .collect()
+    });
+
+    let mut rows = Vec::new();
+    for (t, latest) in cfg.tools.iter().zip(latest) {
+        let installed = installer::find_installed_version(t).ok();
+        let locked = lock
+            .as_ref()
+            .and_then(|l| l.tools.get(&t.name))
+            .map(|e| e.version.clone());
+        let behind = latest.as_deref().is_some_and(|latest| {
+            [installed.as_deref(), locked.as_deref()]
+                .into_iter()
+                .flatten()
+                .any(|v| older(v, latest))
+        });
+        rows.push((t.name.clone(), installed, locked, latest, behind));
+    }
+    let behind = rows.iter().filter(|r| r.4).count();
+
+    if json_output {
+        let items: Vec<_> = rows
+            .iter()
+            .map(|(name, installed, locked, latest, behind)| {
+                json!({
+                    "name": name,
+                    "installed": installed,
+                    "locked": locked,
+                    "latest": latest,
+                    "behind": behind,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "tools": items }))?
+        );
+    } else {
+        let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(4).max(4);
+        println!(
+            "{:<width$}  {:<12}  {:<12}  LATEST",
+            "TOOL", "INSTALLED", "LOCKED"
+        );
+        for (name, installed, locked, latest, behind) in &rows {
+            println!(
+                "{:<width$}  {:<12}  {:<12}  {}{}",
+                name,
+                installed.as_deref().unwrap_or("-"),
+                locked.as_deref().unwrap_or("-"),
+                latest.as_deref().unwrap_or("?"),
+                if *behind { "  (outdated)" } else { "" }
+            );
+        }
+    }
+    if behind > 0 {
+        anyhow::bail!("{behind} tool(s) behind the latest release");
+    }
+    Ok(())
+}
+
+fn older(version: &str, latest: &str) -> bool {
+    match (
+        semver::Version::parse(version),
+        semver::Version::parse(latest),
+    ) {
+        (Ok(v), Ok(l)) => v < l,
+        _ => false,
+    }
+}
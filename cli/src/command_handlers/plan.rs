@@ -0,0 +1,290 @@
+use crate::cache;
+use crate::command_handlers::cache::human_size;
+use crate::config::{host_platform_key, Checksum, Extract, TlkConfig, Tool, ToolKind};
+use crate::installer::{self, InstallOptions};
+use crate::lock::LockFile;
+use crate::ops;
+use crate::platform::platform;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+const LOCK_PATH: &str = "tlk.lock";
+
+// Machine-readable install plan: `tlk plan --json > plan.json`, review / sign off, then
+// `tlk install --from-plan plan.json` executes exactly those downloads. The plan pins the config
+// it was produced from so an edited tlk.toml cannot silently diverge from what was approved.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Plan {
+    pub schema: u32,
+    pub generated: DateTime<Utc>,
+    /// sha256 of the tlk.toml the plan was produced from
+    pub config_sha256: String,
+    pub actions: Vec<PlanAction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanAction {
+    pub name: String,
+    pub version: String,
+    pub kind: ToolKind,
+    pub url: String,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// sha256 the installed binary must have (the lock's `digest`, when written on this platform)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub binary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_entries: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binaries: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract: Option<Extract>,
+    /// Absolute path the binary is written to
+    pub target: String,
+    /// Expected download size in bytes (cache, lock `size`, or a HEAD request)
+    #[serde(default)]
+    pub download_size: Option<u64>,
+    /// Whether the download is expected to be served from the local cache
+    #[serde(default)]
+    pub cached: bool,
+}
+
+pub fn print_plan(cfg: Option<&TlkConfig>, config_path: &str, json: bool) -> Result<()> {
+    let Some(cfg) = cfg else {
+        if json {
+            anyhow::bail!("No config provided for plan");
+        }
+        return Ok(());
+    };
+    let plan = build_plan(cfg, config_path)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+        return Ok(());
+    }
+    for a in &plan.actions {
+        let size = a.download_size.map(human_size);
+        let note = match (size, a.cached) {
+            (Some(size), true) => format!("{size}, cached"),
+            (None, true) => "cached".to_string(),
+            (Some(size), false) => size,
+            (None, false) => "size unknown".to_string(),
+        };
+        println!("{} {} -> {} [{note}]", a.name, a.version, a.url);
+    }
+    installer::print_skipped(cfg);
+    print_budget(&plan.actions);
+    Ok(())
+}
+
+// One-line summary of what `tlk install` will pull over the network.
+fn print_budget(actions: &[PlanAction]) {
+    let fetch: Vec<_> = actions.iter().filter(|a| !a.cached).collect();
+    let cached = actions.len() - fetch.len();
+    let bytes: u64 = fetch.iter().filter_map(|a| a.download_size).sum();
+    let unknown = fetch.iter().filter(|a| a.download_size.is_none()).count();
+    let mut line = format!(
+        "Download: {} from {} tool(s), {cached} from cache",
+        human_size(bytes),
+        fetch.len()
+    );
+    if unknown > 0 {
+        line.push_str(&format!(" ({unknown} of unknown size)"));
+    }
+    println!("{line}");
+}
+
+// Size / cache-hit prediction per URL: the cache knows sizes of what it holds, the lock records
+// sizes seen when it was written, and anything else is asked of the server with HEAD (skipped
+// under --cache-only). Lookups run concurrently since each HEAD is a round trip.
+fn estimate_downloads(urls: &[String]) -> Vec<(Option<u64>, bool)> {
+    let lock = LockFile::load(LOCK_PATH).ok().flatten();
+    let client = crate::http::builder()
+        .ok()
+        .and_then(|b| b.timeout(Duration::from_secs(10)).build().ok());
+    let offline = cache::policy() == cache::CachePolicy::CacheOnly;
+    std::thread::scope(|s| {
+        let handles: Vec<_> = urls
+            .iter()
+            .map(|url| {
+                let (lock, client) = (&lock, &client);
+                s.spawn(move || {
+                    let cached = cache::would_hit(url);
+                    let size = cache::stored_size(url)
+                        .or_else(|| {
+                            lock.as_ref()?
+                                .tools
+                                .values()
+                                .find(|e| &e.source == url)?
+                                .size
+                        })
+                        .or_else(|| {
+                            if offline {
+                                return None;
+                            }
+                            let resp = crate::http::send(
+                                client.as_ref()?.head(crate::installer::mirror_url(url)),
+                            )
+                            .ok()?;
+                            if !resp.status().is_success() {
+                                return None;
+                            }
+                            resp.headers()
+                                .get(reqwest::header::CONTENT_LENGTH)?
+                                .to_str()
+                                .ok()?
+                                .parse()
+                                .ok()
+                        });
+                    (size, cached)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or((None, false)))
+            .collect()
+    })
+}
+
+// Tools with a lock entry are planned exactly as `tlk install --locked` would fetch them
+// (locked version, source and checksums); tools missing from tlk.lock resolve from tlk.toml.
+fn build_plan(cfg: &TlkConfig, config_path: &str) -> Result<Plan> {
+    let lock = LockFile::load(LOCK_PATH)?;
+    if lock.is_some() {
+        crate::lock::check_lock_hash(LOCK_PATH)?;
+    }
+    let mut actions = Vec::new();
+    for t in &cfg.tools {
+        let locked = lock.as_ref().and_then(|l| l.tools.get(&t.name));
+        let (resolved, url, sha256, digest) = match locked {
+            Some(lt) => {
+                let tool = ops::locked_tool(cfg, &t.name, lt)?;
+                let url = installer::render_source(&tool);
+                // Tools without a published checksum are held to their TOFU pin for this host.
+                let sha256 = tool.host_sha256().map(str::to_string).or_else(|| {
+                    lt.tofu_sha256
+                        .as_ref()?
+                        .get(&host_platform_key())
+                        .map(|pin| format!("sha256:{pin}"))
+                });
+                let digest = tool.expected_digest.clone();
+                (tool, url, sha256, digest)
+            }
+            None => {
+                let (exact, _) = installer::normalize_version(&t.version);
+                let mut tool = t.clone();
+                tool.version = exact;
+                let url = installer::render_source(t).replace(&t.version, &tool.version);
+                let sha256 = t.host_sha256().map(str::to_string);
+                (tool, url, sha256, None)
+            }
+        };
+        actions.push(PlanAction {
+            name: t.name.clone(),
+            version: resolved.version.clone(),
+            kind: t.kind.clone(),
+            url,
+            sha256,
+            digest,
+            binary: t.binary.clone(),
+            archive_entries: t.archive_entries.clone(),
+            binaries: t.binaries.clone(),
+            extract: t.extract.clone(),
+            target: platform()
+                .display_path(&installer::target_path(&resolved)?)
+                .to_string_lossy()
+                .to_string(),
+            download_size: None,
+            cached: false,
+        });
+    }
+    let urls: Vec<String> = actions.iter().map(|a| a.url.clone()).collect();
+    for (a, (size, cached)) in actions.iter_mut().zip(estimate_downloads(&urls)) {
+        a.download_size = size;
+        a.cached = cached;
+    }
+    Ok(Plan {
+        schema: 1,
+        generated: crate::fixture::now(),
+        config_sha256: config_digest(config_path)?,
+        actions,
+    })
+}
+
+pub fn install_from_plan(
+    plan_path: &str,
+    config_path: &str,
+    cfg: Option<&TlkConfig>,
+    opts: &InstallOptions,
+) -> Result<()> {
+    let raw =
+        std::fs::read_to_string(plan_path).with_context(|| format!("reading plan {plan_path}"))?;
+    let plan: Plan =
+        serde_json::from_str(&raw).with_context(|| format!("parsing plan {plan_path}"))?;
+    if plan.config_sha256 != config_digest(config_path)? {
+        anyhow::bail!(
+            "{config_path} changed since {plan_path} was generated; re-run 'tlk plan --json'"
+        );
+    }
+    let mut tools: Vec<Tool> = plan.actions.iter().map(action_tool).collect();
+    if let Some(cfg) = cfg {
+        for t in tools.iter_mut() {
+            cfg.settings.apply(t);
+        }
+    }
+    let results = installer::install_tools_parallel(&tools, opts);
+    let failed: Vec<_> = results.iter().filter(|(_, r)| r.is_err()).collect();
+    if !failed.is_empty() {
+        anyhow::bail!("{} planned install(s) failed", failed.len());
+    }
+    installer::refresh_path();
+    Ok(())
+}
+
+// Tool whose source and location are fully pinned by the plan (no templates re-evaluated).
+fn action_tool(action: &PlanAction) -> Tool {
+    let target = std::path::Path::new(&action.target);
+    Tool {
+        name: action.name.clone(),
+        version: action.version.clone(),
+        kind: action.kind.clone(),
+        source: action.url.clone(),
+        maps: None,
+        naming: None,
+        sha256: action.sha256.clone().map(Checksum::Single),
+        binary: action.binary.clone(),
+        archive_entries: action.archive_entries.clone(),
+        binaries: action.binaries.clone(),
+        extract: action.extract.clone(),
+        install_dir: target.parent().map(|p| p.to_string_lossy().to_string()),
+        permissions: None,
+        probe: None,
+        when: None,
+        tofu: None,
+        via: None,
+        limit_rate: None,
+        license: None,
+        env: None,
+        per_os: None,
+        per_os_arch: None,
+        source_fn: None,
+        variants: &[],
+        fallbacks: &[],
+        companions: &[],
+        expected_digest: action.digest.clone(),
+    }
+}
+
+// The recorded lock checksum is left out: installs rewrite it without changing what is planned.
+fn config_digest(config_path: &str) -> Result<String> {
+    let data =
+        std::fs::read_to_string(config_path).with_context(|| format!("reading {config_path}"))?;
+    Ok(hex::encode(Sha256::digest(
+        crate::lock::without_lock_hash(&data).as_bytes(),
+    )))
+}
@@ -0,0 +1,73 @@
+use crate::known_tools::known_tools_map;
+use crate::versioning;
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::Path;
+
+// registry mirror: snapshot the version indexes of known tools into `dir` for air-gapped networks:
+//   <dir>/index.json            tools, latest version and snapshot time
+//   <dir>/versions/<tool>.json  JSON array of versions, newest first
+// Point TLK_REGISTRY at the directory (or a static HTTP server serving it) and version resolution
+// uses only the snapshot. Known-tool recipes are compiled into tlk, so only the version data needs
+// mirroring; the artifacts themselves come from the download cache or mirror overrides.
+pub fn mirror(dir: &str, only: &[String]) -> Result<()> {
+    let known = known_tools_map();
+    for name in only {
+        if !known.contains_key(name.as_str()) {
+            anyhow::bail!("'{name}' is not a known tool");
+        }
+    }
+    let mut names: Vec<&str> = known
+        .keys()
+        .copied()
+        .filter(|n| only.is_empty() || only.iter().any(|o| o == n))
+        .collect();
+    names.sort();
+    let versions_dir = Path::new(dir).join("versions");
+    std::fs::create_dir_all(&versions_dir)
+        .with_context(|| format!("creating {}", versions_dir.display()))?;
+
+    let mut index = serde_json::Map::new();
+    let mut failed = 0;
+    for name in names {
+        let list = match versioning::fetch_all_versions(name) {
+            Ok(list) if !list.is_empty() => list,
+            Ok(_) => {
+                println!("{name}: no versions found, skipped");
+                continue;
+            }
+            Err(e) => {
+                println!("{name}: skipped ({e})");
+                if !only.is_empty() {
+                    failed += 1;
+                }
+                continue;
+            }
+        };
+        let list: Vec<String> = list.iter().map(|v| v.to_string()).collect();
+        let path = versions_dir.join(format!("{name}.json"));
+        std::fs::write(&path, serde_json::to_vec_pretty(&list)?)
+            .with_context(|| format!("writing {}", path.display()))?;
+        println!("{name}: {} version(s), latest {}", list.len(), list[0]);
+        index.insert(
+            name.to_string(),
+            json!({ "latest": list[0], "versions": list.len() }),
+        );
+    }
+    let index_path = Path::new(dir).join("index.json");
+    let doc = json!({
+        "generated": crate::fixture::now().to_rfc3339(),
+        "tlk_version": env!("CARGO_PKG_VERSION"),
+        "tools": index,
+    });
+    std::fs::write(&index_path, serde_json::to_vec_pretty(&doc)?)
+        .with_context(|| format!("writing {}", index_path.display()))?;
+    println!(
+        "Snapshot of {} tool(s) written to {dir}; use it with TLK_REGISTRY={dir}",
+        doc["tools"].as_object().map_or(0, |t| t.len())
+    );
+    if failed > 0 {
+        anyhow::bail!("{failed} requested tool(s) could not be mirrored");
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use crate::config::TlkConfig;
+use crate::installer;
+use crate::lock::LockFile;
+use crate::receipt;
+use anyhow::{Context, Result};
+use std::fs;
+use toml::Value;
+
+// rename: move a declared tool to a new name across tlk.toml, tlk.lock (keeping its checksum
+// history), the installed binary and its install receipt. Archive tools without an explicit
+// `binary` get `binary = "<old>"` so extraction still finds the original entry. Steps run
+// binary -> receipt -> config -> lock (last, so a recorded lock_hash lands in the new config) and
+// earlier steps are rolled back if a later one fails.
+pub fn rename_tool(config_path: &str, lock_path: &str, old: &str, new: &str) -> Result<()> {
+    if old == new {
+        anyhow::bail!("old and new names are identical");
+    }
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("no readable config at {config_path}");
+    };
+    let Some(tool) = cfg.tools.iter().find(|t| t.name == old) else {
+        anyhow::bail!("tool '{old}' is not declared in {config_path}");
+    };
+    if cfg.tools.iter().any(|t| t.name == new) {
+        anyhow::bail!("tool '{new}' already exists in {config_path}");
+    }
+    let config_raw =
+        fs::read_to_string(config_path).with_context(|| format!("reading {config_path}"))?;
+    let mut root: Value = config_raw.parse()?;
+    if root.get(old).is_some_and(|v| v.is_str()) {
+        anyhow::bail!(
+            "'{old}' is a shorthand entry whose recipe is chosen by name; \
+             declare it as [tools.{new}] with an explicit source instead"
+        );
+    }
+    rename_in_config(&mut root, old, new)?;
+    let new_config = toml::to_string_pretty(&root)?;
+
+    let mut renamed = tool.clone();
+    renamed.name = new.to_string();
+    let old_bin = installer::target_path(tool)?;
+    let new_bin = installer::target_path(&renamed)?;
+    let moved_bin = old_bin.exists();
+    if moved_bin {
+        if new_bin.exists() {
+            anyhow::bail!("{} already exists", new_bin.display());
+        }
+        fs::rename(&old_bin, &new_bin)
+            .with_context(|| format!("renaming {old_bin:?} to {new_bin:?}"))?;
+    }
+    let moved_receipt = match receipt::rename(old, new, &old_bin, &new_bin) {
+        Ok(moved) => moved,
+        Err(e) => {
+            if moved_bin {
+                let _ = fs::rename(&new_bin, &old_bin);
+            }
+            return Err(e);
+        }
+    };
+    let undo = || {
+        if moved_bin {
+            let _ = fs::rename(&new_bin, &old_bin);
+        }
+        if moved_receipt {
+            let _ = receipt::rename(new, old, &new_bin, &old_bin);
+        }
+    };
+
+    if let Err(e) = fs::write(config_path, new_config) {
+        undo();
+        return Err(e).with_context(|| format!("writing {config_path}"));
+    }
+    if let Err(e) = rename_in_lock(lock_path, old, new) {
+        undo();
+        let _ = fs::write(config_path, &config_raw);
+        return Err(e);
+    }
+    println!("Renamed {old} -> {new}");
+    Ok(())
+}
+
+fn rename_in_config(root: &mut Value, old: &str, new: &str) -> Result<()> {
+    let tools = root
+        .get_mut("tools")
+        .ok_or_else(|| anyhow::anyhow!("tool '{old}' not found in config"))?;
+    let keep_binary = |entry: &mut toml::Table| {
+        let is_archive = entry
+            .get("kind")
+            .and_then(|k| k.as_str())
+            .unwrap_or("archive")
+            == "archive";
+        if is_archive && !entry.contains_key("binary") {
+            entry.insert("binary".into(), Value::String(old.to_string()));
+        }
+    };
+    match tools {
+        Value::Table(tbl) => {
+            let Some(Value::Table(mut entry)) = tbl.remove(old) else {
+                anyhow::bail!("tool '{old}' not found in config");
+            };
+            if entry.contains_key("name") {
+                entry.insert("name".into(), Value::String(new.to_string()));
+            }
+            keep_binary(&mut entry);
+            tbl.insert(new.to_string(), Value::Table(entry));
+        }
+        Value::Array(items) => {
+            let entry = items
+                .iter_mut()
+                .filter_map(|it| it.as_table_mut())
+                .find(|t| t.get("name").and_then(|v| v.as_str()) == Some(old))
+                .ok_or_else(|| anyhow::anyhow!("tool '{old}' not found in config"))?;
+            entry.insert("name".into(), Value::String(new.to_string()));
+            keep_binary(entry);
+        }
+        _ => anyhow::bail!("unexpected [tools] shape in config"),
+    }
+    Ok(())
+}
+
+fn rename_in_lock(lock_path: &str, old: &str, new: &str) -> Result<()> {
+    let _guard = crate::lock::lock_for_write(lock_path)?;
+    let Some(mut lock) = LockFile::load(lock_path)? else {
+        return Ok(());
+    };
+    if let Some(entry) = lock.tools.remove(old) {
+        lock.tools.insert(new.to_string(), entry);
+        lock.save(lock_path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use anyhow::{Context, Result};
+
+// require: assert the running tlk satisfies a semver range (`>=0.5, <1`) so bootstrap scripts and
+// Makefiles can fail early, before doing anything destructive with an incompatible tlk.
+pub fn require(range: &str) -> Result<()> {
+    let req = semver::VersionReq::parse(range)
+        .with_context(|| format!("invalid version range '{range}' (e.g. '>=0.5, <1')"))?;
+    let running = semver::Version::parse(env!("CARGO_PKG_VERSION"))?;
+    if !req.matches(&running) {
+        anyhow::bail!("tlk {running} does not satisfy the required version '{range}'");
+    }
+    println!("tlk {running} satisfies '{range}'");
+    Ok(())
+}
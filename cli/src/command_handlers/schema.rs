@@ -0,0 +1,477 @@
+use crate::known_tools::known_tools_map;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+// schema: JSON Schema (draft 2020-12) documents for tlk.toml and tlk.lock, mirroring the serde
+// types in config.rs and lock.rs, for editor validation and completion (taplo's `#:schema`
+// directive, yaml-language-server) and for checking files in CI. The `$id` carries the document
+// version (the lock's is its `schema` number), so bump it together with incompatible changes.
+
+/// Version of the tlk.toml format described by `config_schema`.
+const CONFIG_SCHEMA_VERSION: u32 = 1;
+/// Matches the `schema` number written by `LockFile::new`.
+const LOCK_SCHEMA_VERSION: u32 = 3;
+
+pub fn schema(lock: bool) -> Result<()> {
+    let doc = if lock { lock_schema() } else { config_schema() };
+    println!("{}", serde_json::to_string_pretty(&doc)?);
+    Ok(())
+}
+
+fn string_map(description: &str) -> Value {
+    json!({
+        "type": "object",
+        "description": description,
+        "additionalProperties": { "type": "string" }
+    })
+}
+
+fn checksum() -> Value {
+    json!({
+        "description": "Expected checksum of the download (bare hex = sha256, or sha256: / sha512: / blake3: prefixed): one value, or one per platform keyed like the lock's sources (\"linux-amd64\"); \"*\" = \"unverified\" lets unlisted platforms install unverified",
+        "oneOf": [
+            { "$ref": "#/$defs/checksum" },
+            {
+                "type": "object",
+                "propertyNames": { "pattern": "^([a-z]+-[a-z0-9]+|\\*)$" },
+                "properties": { "*": { "const": "unverified" } },
+                "additionalProperties": { "$ref": "#/$defs/checksum" }
+            }
+        ]
+    })
+}
+
+fn os_sources(value: Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": { "linux": value, "mac": value, "windows": value },
+        "additionalProperties": false
+    })
+}
+
+pub fn config_schema() -> Value {
+    let arch_sources = json!({
+        "type": "object",
+        "properties": {
+            "amd64": { "type": "string" },
+            "arm64": { "type": "string" },
+            "x86_64": { "type": "string" },
+            "aarch64": { "type": "string" }
+        },
+        "additionalProperties": false
+    });
+    let tool = json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "description": "Tool name (defaults to the table key)" },
+            "version": { "type": "string", "description": "Exact version, range (\"^1.7\") or \"latest\"" },
+            "version_from": { "type": "string", "description": "File holding the version (.terraform-version, .nvmrc, ...), relative to tlk.toml" },
+            "kind": { "enum": ["archive", "direct"], "default": "archive" },
+            "source": { "type": "string", "description": "URL template with {version}, {os}, {arch} and optional |filters" },
+            "maps": {
+                "type": "object",
+                "description": "Named mapping tables usable as filters in source templates",
+                "additionalProperties": string_map("value -> replacement")
+            },
+            "naming": { "enum": ["go-style", "gnu-style", "node-style", "macos-style"] },
+            "sha256": checksum(),
+            "binary": { "type": "string", "description": "Path of the binary inside the archive" },
+            "archive_entries": { "$ref": "#/$defs/archive_entries" },
+            "binaries": { "$ref": "#/$defs/binaries" },
+            "extract": { "$ref": "#/$defs/extract" },
+            "via": { "$ref": "#/$defs/via" },
+            "limit_rate": { "type": "string", "description": "Download rate for this tool, e.g. 500K (on top of [settings] limit_rate)" },
+            "license": { "type": "string", "description": "SPDX license id reported by tlk licenses" },
+            "install_dir": { "type": "string", "description": "Install location (relative paths resolve against the project root)" },
+            "permissions": { "$ref": "#/$defs/permissions" },
+            "probe": { "$ref": "#/$defs/probe" },
+            "when": { "$ref": "#/$defs/when" },
+            "tofu": { "type": "boolean", "description": "Trust-on-first-use checksum pinning (defaults to [settings] tofu)" },
+            "env": { "$ref": "#/$defs/env" },
+            "per_os": os_sources(json!({ "type": "string" })),
+            "per_os_arch": os_sources(arch_sources)
+        },
+        "required": ["source"],
+        "anyOf": [{ "required": ["version"] }, { "required": ["version_from"] }],
+        "additionalProperties": false
+    });
+    let shorthand = json!({
+        "oneOf": [
+            { "type": "string", "description": "Exact version, range (\"^1.7\") or \"latest\"" },
+            {
+                "type": "object",
+                "properties": {
+                    "version": { "type": "string" },
+                    "version_from": { "type": "string" },
+                    "kind": { "enum": ["archive", "direct"], "description": "Override the recipe's packaging" },
+                    "binary": { "type": "string", "description": "Override the binary path inside the archive" },
+                    "archive_entries": { "$ref": "#/$defs/archive_entries" },
+                    "binaries": { "$ref": "#/$defs/binaries" },
+                    "extract": { "$ref": "#/$defs/extract" },
+                    "via": { "$ref": "#/$defs/via" },
+                    "limit_rate": { "type": "string", "description": "Download rate for this tool, e.g. 500K" },
+                    "license": { "type": "string", "description": "SPDX license id reported by tlk licenses" },
+                    "when": { "$ref": "#/$defs/when" },
+                    "sha256": checksum(),
+                    "env": { "$ref": "#/$defs/env" }
+                },
+                "anyOf": [{ "required": ["version"] }, { "required": ["version_from"] }],
+                "additionalProperties": false
+            }
+        ]
+    });
+    let mut properties = serde_json::Map::new();
+    let mut known: Vec<_> = known_tools_map().into_keys().collect();
+    known.sort();
+    for name in known {
+        properties.insert(name.to_string(), json!({ "$ref": "#/$defs/shorthand" }));
+    }
+    properties.insert(
+        "tools".into(),
+        json!({
+            "description": "Tools with explicit sources: [tools.<name>] tables (or legacy [[tools]] entries with a name)",
+            "oneOf": [
+                { "type": "object", "additionalProperties": { "$ref": "#/$defs/tool" } },
+                {
+                    "type": "array",
+                    "items": { "allOf": [{ "$ref": "#/$defs/tool" }, { "required": ["name"] }] }
+                }
+            ]
+        }),
+    );
+    properties.insert("settings".into(), json!({ "$ref": "#/$defs/settings" }));
+    properties.insert(
+        "cache".into(),
+        json!({
+            "type": "object",
+            "properties": {
+                "max_size": { "type": "string", "description": "Size cap such as \"5GB\"; least recently used downloads are evicted beyond it" },
+                "archive": { "type": "boolean", "description": "Keep artifacts a lock write records at a new version, exempt from eviction and --no-cache" },
+                "archive_to": { "type": "string", "description": "Directory or http(s) base URL (PUT <base>/<sha256>) archived artifacts are also copied to; installs fall back to it when every source fails" }
+            },
+            "additionalProperties": false
+        }),
+    );
+    properties.insert(
+        "network".into(),
+        json!({
+            "type": "object",
+            "properties": {
+                "proxy": { "type": "string", "description": "Proxy for every request (http://, https://, socks5://, socks5h://); overrides HTTPS_PROXY / HTTP_PROXY / ALL_PROXY" },
+                "no_proxy": { "type": "string", "description": "Comma separated hosts, domains or CIDRs that bypass proxy (defaults to NO_PROXY)" },
+                "ca_bundle": { "type": "string", "description": "PEM file of extra trusted root certificates, relative to tlk.toml" },
+                "insecure_skip_verify": { "type": "boolean", "description": "Disable TLS certificate verification (like --insecure; lab networks only)" }
+            },
+            "additionalProperties": false
+        }),
+    );
+    properties.insert(
+        "mirrors".into(),
+        json!({
+            "type": "object",
+            "description": "Upstream URL prefix -> mirror prefix, applied to every download (e.g. \"https://releases.hashicorp.com\" = \"https://mirror.corp/hashicorp\")",
+            "additionalProperties": { "type": "string" }
+        }),
+    );
+    properties.insert(
+        "vars".into(),
+        json!({
+            "type": "object",
+            "description": "Values substituted for ${name} in any string of tlk.toml",
+            "additionalProperties": { "type": "string" }
+        }),
+    );
+    // A profile may override single fields of a [tools.x] table.
+    let mut overridable = properties.clone();
+    overridable.insert(
+        "tools".into(),
+        json!({ "type": "object", "additionalProperties": { "type": "object" } }),
+    );
+    properties.insert(
+        "profiles".into(),
+        json!({
+            "type": "object",
+            "description": "Overrides merged over the top level when selected with --profile <name> or TLK_PROFILE",
+            "additionalProperties": {
+                "type": "object",
+                "properties": overridable,
+                "additionalProperties": false
+            }
+        }),
+    );
+    let settings = json!({
+        "type": "object",
+        "properties": {
+            "permissions": { "$ref": "#/$defs/permissions" },
+            "probe": { "$ref": "#/$defs/probe" },
+            "tofu": { "type": "boolean" },
+            "commit_message": { "type": "string", "description": "Message template for --commit; {changes} lists the bumps" },
+            "http_attempts": { "type": "integer", "minimum": 1, "description": "Attempts per HTTP request on transient failures (default 3)" },
+            "github_token_env": { "type": "string", "description": "Environment variable holding a GitHub token for API calls (checked before GITHUB_TOKEN and GH_TOKEN)" },
+            "bin_dir": { "type": "string", "description": "Install directory for the project's tools, relative to tlk.toml (default .tlk/bin)" },
+            "licenses": {
+                "type": "object",
+                "description": "License policy enforced by tlk licenses",
+                "properties": {
+                    "allow": { "type": "array", "items": { "type": "string" }, "description": "SPDX ids allowed; when set, anything else (including unknown) fails" },
+                    "deny": { "type": "array", "items": { "type": "string" }, "description": "SPDX ids that always fail" }
+                },
+                "additionalProperties": false
+            },
+            "cluster_version": { "type": "string", "description": "Kubernetes version of the target cluster (\"1.29\"); kubectl pins outside its ±1 minor skew are warned about" },
+            "limit_rate": { "type": "string", "description": "Combined download rate for all tools, e.g. 2M (--limit-rate overrides)" },
+            "path_check": { "type": "boolean", "description": "Warn after installs when another copy of a tool comes first on PATH (default true)" },
+            "jobs": { "type": "integer", "minimum": 1, "description": "Tools installed concurrently (default 8; --jobs overrides)" },
+            "lock_timeout": { "type": "integer", "minimum": 0, "description": "Seconds to wait for another tlk run in the project before failing (default 600; TLK_LOCK_TIMEOUT overrides)" },
+            "lock_hash": { "type": "string", "pattern": "^([0-9a-fA-F]{64}|file|)$", "description": "sha256 of tlk.lock kept up to date by tlk (start with \"\"), or \"file\" to keep it in .tlk/lock.sha256; verify and install --locked refuse a lock that no longer matches" },
+            "tls": {
+                "type": "object",
+                "properties": {
+                    "min_version": { "enum": ["1.2", "1.3"] },
+                    "hosts": {
+                        "type": "object",
+                        "additionalProperties": {
+                            "type": "object",
+                            "properties": {
+                                "pin_sha256": { "type": "array", "items": { "$ref": "#/$defs/sha256" } }
+                            },
+                            "additionalProperties": false
+                        }
+                    }
+                },
+                "additionalProperties": false
+            },
+            "webhooks": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "url": { "type": "string" },
+                        "url_env": { "type": "string" },
+                        "format": { "enum": ["json", "slack"] },
+                        "template": { "type": "string" },
+                        "events": { "type": "array", "items": { "enum": ["lock-updated", "verify-failed"] } },
+                        "ci_only": { "type": "boolean" }
+                    },
+                    "additionalProperties": false
+                }
+            }
+        },
+        "additionalProperties": false
+    });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("urn:tlk:config:v{CONFIG_SCHEMA_VERSION}"),
+        "title": "tlk.toml",
+        "description": format!("tlk project configuration (tlk {})", env!("CARGO_PKG_VERSION")),
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+        "$defs": {
+            "sha256": { "type": "string", "pattern": "^[0-9a-fA-F]{64}$" },
+            "checksum": {
+                "type": "string",
+                "pattern": "^((sha256:|blake3:)?[0-9a-fA-F]{64}|sha512:[0-9a-fA-F]{128})$"
+            },
+            "via": {
+                "enum": ["corepack"],
+                "description": "Run the package manager (pnpm, yarn) through the node toolchain's corepack instead of downloading it"
+            },
+            "binaries": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Archive paths of every command to put on PATH (e.g. bin/node, bin/npm); the archive is kept whole under .tlk/toolchains"
+            },
+            "extract": {
+                "enum": ["all"],
+                "description": "Keep the whole archive under .tlk/toolchains and run the binary from there"
+            },
+            "archive_entries": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Extra archive paths tried for the binary before the built-in candidates"
+            },
+            "when": {
+                "type": "string",
+                "description": "Install only when the condition holds: env:NAME, env:NAME=value, negated with !",
+                "pattern": "^!?env:[^=]+(=.*)?$"
+            },
+            "env": string_map("Environment variables exported with the project toolchain; {root} expands to the project root"),
+            "permissions": {
+                "type": "object",
+                "properties": {
+                    "file_mode": { "type": "string", "pattern": "^0?[0-7]{3,4}$" },
+                    "dir_mode": { "type": "string", "pattern": "^0?[0-7]{3,4}$" },
+                    "group": { "type": "string" }
+                },
+                "additionalProperties": false
+            },
+            "probe": {
+                "type": "object",
+                "properties": {
+                    "enabled": { "type": "boolean" },
+                    "sandbox": { "type": "boolean" },
+                    "timeout_secs": { "type": "integer", "minimum": 0 }
+                },
+                "additionalProperties": false
+            },
+            "shorthand": shorthand,
+            "tool": tool,
+            "settings": settings
+        }
+    })
+}
+
+pub fn lock_schema() -> Value {
+    let platform_map = |description: &str| {
+        json!({
+            "type": "object",
+            "description": description,
+            "additionalProperties": { "type": "string" }
+        })
+    };
+    let tools = json!({
+        "type": "object",
+        "additionalProperties": {
+            "type": "object",
+            "properties": {
+                "version": { "type": "string", "description": "Exact resolved version" },
+                "requested_version": { "type": "string", "description": "Original spec when it was a range" },
+                "source": { "type": "string" },
+                "source_template": { "type": "string" },
+                "platform": { "type": "string", "description": "Platform that wrote source, sha256 and digest" },
+                "sources": platform_map("Download URL per platform (\"linux-amd64\")"),
+                "sha256": { "type": "string" },
+                "sha256_by_platform": platform_map("sha256 per platform from tlk.toml"),
+                "digest": { "type": "string", "description": "sha256 of the installed binary" },
+                "size": { "type": "integer", "minimum": 0 },
+                "tofu_sha256": platform_map("Trust-on-first-use sha256 pins per platform"),
+                "served_from": { "type": "string", "description": "Fallback URL that served the artifact when source failed" }
+            },
+            "required": ["version", "source"],
+            "additionalProperties": false
+        }
+    });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("urn:tlk:lock:v{LOCK_SCHEMA_VERSION}"),
+        "title": "tlk.lock",
+        "description": "Resolved tool versions written by tlk",
+        "type": "object",
+        "properties": {
+            "generated": { "type": "string", "format": "date-time" },
+            "tlk_version": { "type": "string" },
+            "schema": { "const": LOCK_SCHEMA_VERSION },
+            "tools": tools,
+            "profiles": {
+                "type": "object",
+                "description": "Entries of a profile (--profile) that resolve differently from tools",
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": { "tools": tools },
+                    "required": ["tools"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "required": ["generated", "tools"],
+        "additionalProperties": false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        ArchSources, CacheSettings, LicensePolicy, NetworkSettings, PerOsSources, Permissions,
+        ProbeSettings, Settings, TlsHost, TlsSettings, Tool, Webhook,
+    };
+    use crate::lock::{LockFile, LockedEntry, ProfileLock};
+    use serde::de::{self, DeserializeOwned, Visitor};
+
+    // Deserializer that records the field names a derived `Deserialize` impl asks for, so the
+    // schema is checked against the serde types themselves rather than a second list.
+    struct Fields<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> de::Deserializer<'de> for Fields<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("fields recorded"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+            ignored_any
+        }
+    }
+
+    fn fields<T: DeserializeOwned>() -> &'static [&'static str] {
+        let mut fields: &'static [&'static str] = &[];
+        let _ = T::deserialize(Fields(&mut fields));
+        assert!(
+            !fields.is_empty(),
+            "{} has no fields",
+            std::any::type_name::<T>()
+        );
+        fields
+    }
+
+    #[track_caller]
+    fn assert_covers<T: DeserializeOwned>(schema: &Value, pointer: &str) {
+        let object = schema
+            .pointer(pointer)
+            .unwrap_or_else(|| panic!("schema has no {pointer}"));
+        let missing: Vec<_> = fields::<T>()
+            .iter()
+            .filter(|f| object.pointer(&format!("/properties/{f}")).is_none())
+            .collect();
+        assert!(
+            missing.is_empty(),
+            "{pointer} lacks {missing:?} of {}",
+            std::any::type_name::<T>()
+        );
+    }
+
+    #[test]
+    fn config_schema_covers_every_serde_field() {
+        let schema = config_schema();
+        assert_covers::<Tool>(&schema, "/$defs/tool");
+        assert_covers::<Settings>(&schema, "/$defs/settings");
+        assert_covers::<Permissions>(&schema, "/$defs/permissions");
+        assert_covers::<ProbeSettings>(&schema, "/$defs/probe");
+        assert_covers::<LicensePolicy>(&schema, "/$defs/settings/properties/licenses");
+        assert_covers::<TlsSettings>(&schema, "/$defs/settings/properties/tls");
+        assert_covers::<TlsHost>(
+            &schema,
+            "/$defs/settings/properties/tls/properties/hosts/additionalProperties",
+        );
+        assert_covers::<Webhook>(&schema, "/$defs/settings/properties/webhooks/items");
+        assert_covers::<PerOsSources>(&schema, "/$defs/tool/properties/per_os");
+        assert_covers::<ArchSources>(
+            &schema,
+            "/$defs/tool/properties/per_os_arch/properties/linux",
+        );
+        assert_covers::<CacheSettings>(&schema, "/properties/cache");
+        assert_covers::<NetworkSettings>(&schema, "/properties/network");
+    }
+
+    #[test]
+    fn lock_schema_covers_every_serde_field() {
+        let schema = lock_schema();
+        assert_covers::<LockFile>(&schema, "");
+        assert_covers::<LockedEntry>(&schema, "/properties/tools/additionalProperties");
+        assert_covers::<ProfileLock>(&schema, "/properties/profiles/additionalProperties");
+    }
+}
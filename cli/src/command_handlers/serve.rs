@@ -0,0 +1,134 @@
+use crate::command_handlers::install::{run_install, InstallArgs};
+use crate::config::TlkConfig;
+use crate::installer;
+use crate::lock::LockFile;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+// Serve mode: a tiny line-delimited JSON API over a unix socket for editor integrations.
+// Each request is one JSON object per line, answered with one JSON object per line:
+//   {"cmd":"status"}   -> per-tool desired / locked / installed versions
+//   {"cmd":"drift"}    -> verification problems between config, lock and binaries
+//   {"cmd":"install"}  -> install declared tools (updates tlk.lock) then report status
+// Config and lock are re-read for every request so edits are picked up without restarts.
+#[cfg(unix)]
+pub fn serve(config_path: &str, socket: Option<&str>) -> Result<()> {
+    use anyhow::Context;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+
+    let socket = socket.unwrap_or(".tlk/tlk.sock");
+    if let Some(parent) = std::path::Path::new(socket).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).with_context(|| format!("creating {parent:?}"))?;
+        }
+    }
+    // Stale socket from a previous run would make bind fail.
+    let _ = std::fs::remove_file(socket);
+    let listener = UnixListener::bind(socket).with_context(|| format!("binding {socket}"))?;
+    println!("[tlk serve] listening on {socket}");
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("[tlk serve] accept failed: {e}");
+                continue;
+            }
+        };
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = handle(config_path, &line);
+            if writeln!(writer, "{response}").is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve(_config_path: &str, _socket: Option<&str>) -> Result<()> {
+    anyhow::bail!("tlk serve requires unix domain sockets (not available on this platform)")
+}
+
+fn handle(config_path: &str, line: &str) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return json!({ "ok": false, "error": format!("invalid request: {e}") }),
+    };
+    let cmd = request.get("cmd").and_then(|c| c.as_str()).unwrap_or("");
+    let result = match cmd {
+        "status" => status(config_path),
+        "drift" => drift(config_path),
+        "install" => install(config_path),
+        other => Err(anyhow::anyhow!("unknown cmd '{other}'")),
+    };
+    match result {
+        Ok(v) => json!({ "ok": true, "cmd": cmd, "result": v }),
+        Err(e) => json!({ "ok": false, "cmd": cmd, "error": e.to_string() }),
+    }
+}
+
+fn load(config_path: &str) -> Result<TlkConfig> {
+    TlkConfig::load(config_path).ok_or_else(|| anyhow::anyhow!("unable to load {config_path}"))
+}
+
+fn status(config_path: &str) -> Result<Value> {
+    let cfg = load(config_path)?;
+    let lock = LockFile::load("tlk.lock")?;
+    let tools: Vec<Value> = cfg
+        .tools
+        .iter()
+        .map(|t| {
+            let locked = lock
+                .as_ref()
+                .and_then(|l| l.tools.get(&t.name))
+                .map(|lt| lt.version.clone());
+            let installed = installer::find_installed_version(t).ok();
+            json!({
+                "name": t.name,
+                "desired": t.version,
+                "locked": locked,
+                "installed": installed,
+                "missing": installed.is_none(),
+            })
+        })
+        .collect();
+    Ok(json!({ "tools": tools }))
+}
+
+fn drift(config_path: &str) -> Result<Value> {
+    let cfg = load(config_path)?;
+    let Some(lock) = LockFile::load("tlk.lock")? else {
+        return Ok(json!({ "lock": false, "problems": [] }));
+    };
+    let problems: Vec<Value> = installer::lock_drift(&cfg, &lock)
+        .iter()
+        .map(|d| json!({ "tool": d.tool, "problem": d.message, "fixable": d.fixable }))
+        .collect();
+    Ok(json!({ "lock": true, "problems": problems }))
+}
+
+fn install(config_path: &str) -> Result<Value> {
+    let cfg = load(config_path)?;
+    run_install(InstallArgs {
+        write_lock: true,
+        locked: false,
+        no_verify: true,
+        specs: &[],
+        exact: false,
+        system: false,
+        force: false,
+        reinstall: &[],
+        from_plan: None,
+        prune_lock: false,
+        allow_dirty: false,
+        config_path,
+        cfg: Some(&cfg),
+    })?;
+    status(config_path)
+}
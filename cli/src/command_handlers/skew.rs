@@ -0,0 +1,106 @@
+use crate::config::TlkConfig;
+use crate::installer;
+use anyhow::{Context, Result};
+use std::process::Command;
+
+// Kubernetes supports kubectl within one minor version of the API server. After install /
+// verify, compare the pinned kubectl against `[settings] cluster_version`, or with
+// --cluster-check against the server the current kubeconfig points at, and warn on skew.
+// Advice only: nothing here fails the command.
+pub fn check_kubectl(cfg: Option<&TlkConfig>, cluster_check: bool) {
+    let Some(cfg) = cfg else {
+        return;
+    };
+    let Some(tool) = cfg.tools.iter().find(|t| t.name == "kubectl") else {
+        if cluster_check {
+            eprintln!("Note: --cluster-check: kubectl is not declared in tlk.toml");
+        }
+        return;
+    };
+    let client = installer::find_installed_version(tool)
+        .ok()
+        .and_then(|v| minor_of(&v))
+        .or_else(|| minor_of(&installer::normalize_version(&tool.version).0));
+    let Some(client) = client else {
+        return;
+    };
+    let mut cluster = None;
+    if cluster_check {
+        match probe_server(tool) {
+            Ok(found) => cluster = Some((found, "cluster")),
+            Err(e) => eprintln!("Warning: --cluster-check: {e:#}"),
+        }
+    }
+    if cluster.is_none() {
+        cluster = cfg
+            .settings
+            .cluster_version
+            .clone()
+            .map(|v| (v, "[settings] cluster_version"));
+    }
+    let Some((server, from)) = cluster else {
+        return;
+    };
+    let Some(server_minor) = minor_of(&server) else {
+        eprintln!("Warning: cannot read a Kubernetes version from {from} \"{server}\"");
+        return;
+    };
+    if client.0 != server_minor.0 || client.1.abs_diff(server_minor.1) > 1 {
+        eprintln!(
+            "Warning: kubectl {}.{} is outside the supported skew of {from} {} (one minor \
+             version older or newer, i.e. {}); pin kubectl to a matching release",
+            client.0,
+            client.1,
+            server.trim_start_matches('v'),
+            supported_range(server_minor)
+        );
+    } else if cluster_check {
+        println!(
+            "kubectl {}.{} is within the supported skew of {from} {}",
+            client.0,
+            client.1,
+            server.trim_start_matches('v')
+        );
+    }
+}
+
+// `kubectl version -o json` reports the API server of the current kubeconfig context.
+fn probe_server(tool: &crate::config::Tool) -> Result<String> {
+    let path = installer::target_path(tool)?;
+    let out = Command::new(&path)
+        .args(["version", "-o", "json", "--request-timeout=5s"])
+        .output()
+        .with_context(|| format!("running {}", path.display()))?;
+    let json: serde_json::Value = serde_json::from_slice(&out.stdout).with_context(|| {
+        format!(
+            "kubectl version: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        )
+    })?;
+    let server = json.get("serverVersion").ok_or_else(|| {
+        anyhow::anyhow!(
+            "no server version (is a cluster reachable?): {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        )
+    })?;
+    server
+        .get("gitVersion")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("kubectl version: serverVersion.gitVersion missing"))
+}
+
+// (major, minor) of "1.29", "v1.29.3" or "v1.29.3-eks-4a1b2c".
+fn minor_of(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version
+        .trim()
+        .trim_start_matches('v')
+        .split(['.', '-', '+']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+fn supported_range((major, minor): (u64, u64)) -> String {
+    format!("{major}.{}-{major}.{}", minor.saturating_sub(1), minor + 1)
+}
@@ -3,9 +3,10 @@ pub fn canonicalize_spec(
     user_spec: Option<&str>,
     resolved: &str,
     exact_flag: bool,
-    latest_flag: bool,
+    _latest_flag: bool,
 ) -> String {
-    if let Some(spec) = user_spec {
+    // Channels ("lts", "current") are stored as the version they resolved to.
+    if let Some(spec) = user_spec.filter(|s| !crate::versioning::is_channel(s)) {
         let trimmed = spec.trim();
         if trimmed.is_empty() {
             return resolved.to_string();
@@ -18,14 +19,12 @@ pub fn canonicalize_spec(
             return if exact_flag { trimmed.to_string() } else { caret_for_version(trimmed) };
         }
         let dot_count = trimmed.chars().filter(|c| *c == '.').count();
-        if trimmed.chars().all(|c| c.is_ascii_digit() || c == '.') {
-            if dot_count == 0 { return format!("{}.x", trimmed); }
-            else if dot_count == 1 { return format!("{}.x", trimmed); }
+        if trimmed.chars().all(|c| c.is_ascii_digit() || c == '.') && dot_count <= 1 {
+            return format!("{}.x", trimmed);
         }
         return trimmed.to_string();
     }
     if exact_flag { return resolved.to_string(); }
-    if latest_flag { return caret_for_version(resolved); }
     caret_for_version(resolved)
 }
 
@@ -51,7 +50,7 @@ pub fn canonicalize_spec_logging(
     if let toml::Value::Table(tbl) = &mut root {
         if let Some(existing) = tbl.get_mut(name) { if existing.is_str() { *existing = toml::Value::String(to_store.clone()); } } else {
             let mut updated=false;
-            if let Some(arr)=tbl.get_mut("tools") { if let toml::Value::Array(items)=arr { for it in items.iter_mut() { if let toml::Value::Table(t)=it { if t.get("name").and_then(|v| v.as_str())==Some(name) { t.insert("version".into(), toml::Value::String(to_store.clone())); updated=true; break; } } } } }
+            if let Some(toml::Value::Array(items))=tbl.get_mut("tools") { for it in items.iter_mut() { if let toml::Value::Table(t)=it { if t.get("name").and_then(|v| v.as_str())==Some(name) { t.insert("version".into(), toml::Value::String(to_store.clone())); updated=true; break; } } } }
             if !updated { tbl.insert(name.to_string(), toml::Value::String(to_store.clone())); }
         }
     }
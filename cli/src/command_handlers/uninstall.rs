@@ -1,58 +1,120 @@
 use crate::config::TlkConfig;
 use anyhow::Result;
 
-pub fn uninstall_tool(config_path: &str, name: &str) -> Result<()> {
+// uninstall: drop a tool from tlk.toml and tlk.lock and delete what its install receipt lists
+// (for installs without one, the binary, toolchain and shims derived from the config). With
+// keep_binary (`tlk remove` / `uninstall --keep-binary`) only the declarations change; .tlk/bin
+// is left for a separate cleanup.
+pub fn uninstall_tool(config_path: &str, name: &str, keep_binary: bool) -> Result<()> {
+    if !keep_binary {
+        // The receipt lists exactly what the install wrote; older installs have none.
+        if !crate::receipt::revert(name)? {
+            remove_binary(config_path, name)?;
+        }
+        crate::installer::mark_installed();
+    }
+    let declared = remove_from_config(config_path, name)?;
+    remove_from_lock("tlk.lock", name)?;
+    if keep_binary && !declared {
+        anyhow::bail!("tool '{name}' is not declared in {config_path}");
+    }
+    Ok(())
+}
+
+fn remove_binary(config_path: &str, name: &str) -> Result<()> {
     use std::fs;
     let p = crate::platform::platform();
-    if let Some(cfg) = TlkConfig::load(config_path) {
-        if let Some(tool) = cfg.tools.iter().find(|t| t.name == name) {
-            let dir = tool
-                .install_dir
-                .clone()
-                .or_else(|| p.global_bin_dir().map(|g| g.to_string_lossy().to_string()))
-                .unwrap_or_else(|| ".tlk/bin".into());
-            let filename = p.final_binary_name(name);
-            let path = std::path::Path::new(&dir).join(&filename);
-            if path.exists() {
-                fs::remove_file(&path)
-                    .map_err(|e| anyhow::anyhow!("removing binary {:?}: {e}", path))?;
-            }
+    let cfg = TlkConfig::load(config_path);
+    let declared = cfg
+        .as_ref()
+        .and_then(|c| c.tools.iter().find(|t| t.name == name));
+    // Shims of the commands run from the tool's toolchain
+    let commands: Vec<String> = match declared {
+        Some(tool) => crate::installer::toolchain_commands(tool)
+            .into_iter()
+            .map(|(command, _)| command)
+            .collect(),
+        None => crate::known_tools::companions(name)
+            .iter()
+            .map(|c| c.name.to_string())
+            .collect(),
+    };
+    if let Some(tool) = declared {
+        let dir = tool
+            .install_dir
+            .clone()
+            .or_else(|| p.global_bin_dir().map(|g| g.to_string_lossy().to_string()))
+            .unwrap_or_else(|| ".tlk/bin".into());
+        let filename = p.final_binary_name(name);
+        let path = std::path::Path::new(&dir).join(&filename);
+        if path.exists() {
+            fs::remove_file(&path)
+                .map_err(|e| anyhow::anyhow!("removing binary {:?}: {e}", path))?;
         }
+        crate::installer::remove_toolchain(name, &commands, std::path::Path::new(&dir));
     }
-    let local_dir = std::path::Path::new(".tlk/bin");
+    let root = crate::installer::project_root().unwrap_or_default();
+    let local_dir = &crate::config::project_bin_dir(&root);
     for candidate in [p.final_binary_name(name), name.to_string()].into_iter() {
         let path = local_dir.join(&candidate);
         if path.exists() {
             let _ = fs::remove_file(&path);
         }
     }
-    remove_from_config(config_path, name)?;
-    remove_from_lock("tlk.lock", name)?;
+    crate::installer::remove_toolchain(name, &commands, local_dir);
     Ok(())
 }
 
-fn remove_from_config(path: &str, name: &str) -> Result<()> {
+// Remove every declaration of `name` (shorthand, inline table, [tools.<name>] or [[tools]]);
+// false when there was none.
+fn remove_from_config(path: &str, name: &str) -> Result<bool> {
     use std::fs;
     let data = fs::read_to_string(path)?;
     let mut root: toml::Value = data.parse()?;
+    let mut found = false;
     if let toml::Value::Table(tbl) = &mut root {
-        if let Some(v) = tbl.get(name) {
-            if v.is_str() {
-                tbl.remove(name);
+        if tbl.get(name).is_some_and(|v| v.is_str() || v.is_table())
+            && !crate::config::RESERVED_KEYS.contains(&name)
+        {
+            tbl.remove(name);
+            found = true;
+        }
+        match tbl.get_mut("tools") {
+            Some(toml::Value::Table(tools)) => found |= tools.remove(name).is_some(),
+            Some(toml::Value::Array(items)) => {
+                let before = items.len();
+                items.retain(|it| it.get("name").and_then(|v| v.as_str()) != Some(name));
+                found |= items.len() != before;
             }
+            _ => {}
         }
-        if let Some(arr) = tbl.get_mut("tools") {
-            if let toml::Value::Array(items) = arr {
-                items.retain(|it| !(it.get("name").and_then(|v| v.as_str()) == Some(name)));
+        // Profile overrides of a removed tool would otherwise re-declare it.
+        if let Some(toml::Value::Table(profiles)) = tbl.get_mut("profiles") {
+            for (_, overlay) in profiles.iter_mut() {
+                let Some(overlay) = overlay.as_table_mut() else {
+                    continue;
+                };
+                overlay.remove(name);
+                if let Some(toml::Value::Table(tools)) = overlay.get_mut("tools") {
+                    tools.remove(name);
+                }
             }
         }
+        if tbl
+            .get("tools")
+            .is_some_and(|t| t.as_table().is_some_and(|t| t.is_empty()))
+        {
+            tbl.remove("tools");
+        }
     }
-    let serialized = toml::to_string_pretty(&root)?;
-    fs::write(path, serialized)?;
-    Ok(())
+    if found {
+        fs::write(path, toml::to_string_pretty(&root)?)?;
+    }
+    Ok(found)
 }
 
 fn remove_from_lock(path: &str, name: &str) -> Result<()> {
+    let _guard = crate::lock::lock_for_write(path)?;
     if let Some(mut lf) = crate::lock::LockFile::load(path)? {
         lf.tools.remove(name);
         lf.save(path)?;
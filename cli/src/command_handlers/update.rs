@@ -0,0 +1,172 @@
+use crate::command_handlers::install::resolve_version;
+use crate::config::TlkConfig;
+use crate::{installer, ops};
+use anyhow::{Context, Result};
+use std::fs;
+use toml::Value;
+
+// update: re-resolve each tool's version spec against upstream release listings and move to the
+// newest matching version. Simple ranges keep their operator with the floor raised to the new
+// version (`^1.7` -> `^1.9.2`), because installs without a lock resolve a range to its floor;
+// compound ranges (`>=1.2 <2`, `||`, wildcards) are kept verbatim. Exact pins and tools pinned
+// through `version_from` files are left alone.
+pub fn update(config_path: &str, lock_path: &str, names: &[String]) -> Result<()> {
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("no readable config at {config_path}");
+    };
+    for name in names {
+        if !cfg.tools.iter().any(|t| &t.name == name) {
+            anyhow::bail!("tool '{name}' is not declared in {config_path}");
+        }
+    }
+    let raw = fs::read_to_string(config_path).with_context(|| format!("reading {config_path}"))?;
+    let root: Value = raw.parse()?;
+    let lock = crate::lock::LockFile::load(lock_path)?;
+
+    let mut updates = Vec::new();
+    for tool in &cfg.tools {
+        if !names.is_empty() && !names.contains(&tool.name) {
+            continue;
+        }
+        if config_entry(&root, &tool.name).is_some_and(|e| e.get("version_from").is_some()) {
+            println!("{}: pinned by version_from, skipping", tool.name);
+            continue;
+        }
+        if !installer::is_range(&tool.version) {
+            println!("{} {}: exact pin, skipping", tool.name, tool.version);
+            continue;
+        }
+        let newest = match resolve_version(&tool.name, &tool.version) {
+            Ok(v) => v,
+            Err(e) => {
+                println!("{} {}: cannot re-resolve ({e})", tool.name, tool.version);
+                continue;
+            }
+        };
+        let current = lock
+            .as_ref()
+            .and_then(|l| l.tools.get(&tool.name))
+            .map(|e| e.version.clone())
+            .unwrap_or_else(|| match installer::normalize_version(&tool.version) {
+                (floor, Some(_)) => floor,
+                _ => "unlocked".to_string(),
+            });
+        if !is_newer(&newest, &current) {
+            println!("{} {}: up to date ({current})", tool.name, tool.version);
+            continue;
+        }
+        let spec = raise_floor(&tool.version, &newest);
+        println!("{} {}: {current} -> {newest}", tool.name, tool.version);
+        let mut updated = tool.clone();
+        updated.version = if spec == tool.version {
+            newest
+        } else {
+            spec.clone()
+        };
+        updates.push((updated, spec));
+    }
+    if updates.is_empty() {
+        println!("Everything is up to date.");
+        return Ok(());
+    }
+
+    let tools: Vec<_> = updates.iter().map(|(t, _)| t.clone()).collect();
+    let opts = installer::InstallOptions { force: true };
+    let _project = crate::lock::lock_project("tlk.lock", "update")?;
+    let results = installer::install_tools_parallel(&tools, &opts);
+    let mut failed = 0;
+    let mut updated = Vec::new();
+    let mut root = root;
+    for (tool, spec) in &updates {
+        match results.iter().find(|(n, _)| n == &tool.name) {
+            Some((_, Ok(()))) => {}
+            Some((_, Err(e))) => {
+                eprintln!("Failed to update {}: {e:#}", tool.name);
+                failed += 1;
+                continue;
+            }
+            None => {
+                failed += 1;
+                continue;
+            }
+        }
+        set_config_version(&mut root, &tool.name, spec)?;
+        fs::write(config_path, toml::to_string_pretty(&root)?)
+            .with_context(|| format!("writing {config_path}"))?;
+        updated.push(tool.clone());
+    }
+    ops::write_locks(Some(&cfg), &updated)?;
+    installer::refresh_path();
+    if failed > 0 {
+        anyhow::bail!("{failed} update(s) failed");
+    }
+    Ok(())
+}
+
+fn is_newer(candidate: &str, current: &str) -> bool {
+    match (
+        semver::Version::parse(candidate),
+        semver::Version::parse(current),
+    ) {
+        (Ok(c), Ok(cur)) => c > cur,
+        _ => candidate != current,
+    }
+}
+
+// `^1.7` / `~1.7.0` / `>=1.2` with the version part replaced; anything else unchanged.
+fn raise_floor(spec: &str, newest: &str) -> String {
+    let s = spec.trim();
+    for op in [">=", "^", "~"] {
+        if let Some(rest) = s.strip_prefix(op) {
+            let rest = rest.trim();
+            let simple = !rest.is_empty()
+                && rest.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && !rest.ends_with('.');
+            if simple {
+                return format!("{op}{newest}");
+            }
+            break;
+        }
+    }
+    s.to_string()
+}
+
+fn config_entry<'a>(root: &'a Value, name: &str) -> Option<&'a Value> {
+    if let Some(v) = root.get(name) {
+        return Some(v);
+    }
+    match root.get("tools")? {
+        Value::Table(t) => t.get(name),
+        Value::Array(items) => items
+            .iter()
+            .find(|it| it.get("name").and_then(|v| v.as_str()) == Some(name)),
+        _ => None,
+    }
+}
+
+fn set_config_version(root: &mut Value, name: &str, spec: &str) -> Result<()> {
+    let new = Value::String(spec.to_string());
+    if let Some(entry) = root.get_mut(name) {
+        match entry {
+            Value::Table(t) => {
+                t.insert("version".into(), new);
+            }
+            other => *other = new,
+        }
+        return Ok(());
+    }
+    let entry = match root.get_mut("tools") {
+        Some(Value::Table(t)) => t.get_mut(name),
+        Some(Value::Array(items)) => items
+            .iter_mut()
+            .find(|it| it.get("name").and_then(|v| v.as_str()) == Some(name)),
+        _ => None,
+    };
+    match entry {
+        Some(Value::Table(t)) => {
+            t.insert("version".into(), new);
+            Ok(())
+        }
+        _ => anyhow::bail!("tool '{name}' not found in config"),
+    }
+}
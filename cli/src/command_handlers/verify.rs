@@ -0,0 +1,37 @@
+use crate::config::TlkConfig;
+use crate::installer::{self, InstallOptions};
+use crate::lock::LockFile;
+use crate::ops;
+use anyhow::Result;
+
+// verify --fix: reinstall tools whose installed binary drifted from the lock, using the locked
+// sources (the lock itself is never rewritten), then verify again. Problems that need config or
+// lock changes are reported with a hint instead.
+pub fn run_verify(cfg: Option<&TlkConfig>, lock_path: &str, fix: bool) -> Result<()> {
+    if !fix {
+        return installer::verify_lockfile(cfg, lock_path);
+    }
+    let Some(cfg) = cfg else {
+        return Err(anyhow::anyhow!("No config provided for lock verification"));
+    };
+    let Some(lock) = LockFile::load(lock_path)? else {
+        println!("No {lock_path} present; run 'tlk install' to create it.");
+        return Ok(());
+    };
+    let drift = installer::lock_drift(cfg, &lock);
+    let mut fixable: Vec<String> = drift
+        .iter()
+        .filter(|d| d.fixable)
+        .map(|d| d.tool.clone())
+        .collect();
+    fixable.dedup();
+    if !fixable.is_empty() {
+        println!("Reinstalling from {lock_path}: {}", fixable.join(", "));
+        let opts = InstallOptions { force: true };
+        ops::install_locked(lock_path, Some(cfg), &opts, Some(&fixable))?;
+    }
+    for d in drift.iter().filter(|d| !d.fixable) {
+        println!("Cannot fix without changing the lock: {d} (run 'tlk install' to re-lock)");
+    }
+    installer::verify_lockfile(Some(cfg), lock_path)
+}
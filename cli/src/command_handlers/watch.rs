@@ -0,0 +1,118 @@
+use crate::command_handlers::install::{run_install, InstallArgs};
+use crate::config::TlkConfig;
+use crate::installer;
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+// Watch mode: file system notifications (inotify / FSEvents / ReadDirectoryChangesW through the
+// notify crate) on the directories holding tlk.toml and tlk.lock, and a reconcile whenever one
+// of them changes. The directories are watched rather than the files because editors and tlk
+// itself replace files by renaming over them.
+// Each reconcile ends with a single "[tlk watch] ..." summary line so the output can be piped
+// straight into notify-send / osascript style desktop notifiers.
+pub fn watch(config_path: &str, quiet_ms: u64) -> Result<()> {
+    let lock_path = "tlk.lock";
+    let quiet = Duration::from_millis(quiet_ms.max(50));
+    let config = absolute(config_path)?;
+    let lock = absolute(lock_path)?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("starting file watcher")?;
+    let mut dirs: Vec<&Path> = [&config, &lock].iter().filter_map(|p| p.parent()).collect();
+    dirs.dedup();
+    for dir in dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("watching {}", dir.display()))?;
+    }
+    println!("[tlk watch] watching {config_path} and {lock_path} (Ctrl-C to stop)");
+    loop {
+        let event = rx.recv().context("file watcher stopped")?;
+        let (mut config_changed, lock_changed) = touches(event, &config, &lock);
+        if !config_changed && !lock_changed {
+            continue;
+        }
+        // Editors often write in several steps; wait until the files settle.
+        loop {
+            match rx.recv_timeout(quiet) {
+                Ok(event) => config_changed |= touches(event, &config, &lock).0,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!("file watcher stopped"),
+            }
+        }
+        let outcome = if config_changed {
+            println!("[tlk watch] {config_path} changed; installing");
+            reconcile_config(config_path)
+        } else {
+            println!("[tlk watch] {lock_path} changed; installing locked versions");
+            reconcile_lock(config_path, lock_path)
+        };
+        match outcome {
+            Ok(()) => println!("[tlk watch] tools up to date"),
+            Err(e) => eprintln!("[tlk watch] install failed: {e:#}"),
+        }
+        // Drop the events of our own lock writes so they do not retrigger a cycle.
+        while rx.recv_timeout(quiet).is_ok() {}
+    }
+}
+
+fn absolute(path: &str) -> Result<PathBuf> {
+    std::path::absolute(path).with_context(|| format!("resolving {path}"))
+}
+
+// Whether a watcher event concerns (tlk.toml, tlk.lock). Errors count as neither.
+fn touches(event: notify::Result<notify::Event>, config: &Path, lock: &Path) -> (bool, bool) {
+    let Ok(event) = event else {
+        return (false, false);
+    };
+    if event.kind.is_access() {
+        return (false, false);
+    }
+    let hit = |target: &Path| event.paths.iter().any(|p| p == target);
+    (hit(config), hit(lock))
+}
+
+fn reconcile_config(config_path: &str) -> Result<()> {
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("unable to parse {config_path}");
+    };
+    run_install(InstallArgs {
+        write_lock: true,
+        locked: false,
+        no_verify: false,
+        specs: &[],
+        exact: false,
+        system: false,
+        force: false,
+        reinstall: &[],
+        from_plan: None,
+        prune_lock: false,
+        allow_dirty: false,
+        config_path,
+        cfg: Some(&cfg),
+    })
+}
+
+fn reconcile_lock(config_path: &str, lock_path: &str) -> Result<()> {
+    let Some(cfg) = TlkConfig::load(config_path) else {
+        anyhow::bail!("unable to parse {config_path}");
+    };
+    run_install(InstallArgs {
+        write_lock: false,
+        locked: true,
+        no_verify: false,
+        specs: &[],
+        exact: false,
+        system: false,
+        force: false,
+        reinstall: &[],
+        from_plan: None,
+        prune_lock: false,
+        allow_dirty: false,
+        config_path,
+        cfg: Some(&cfg),
+    })?;
+    installer::verify_lockfile(Some(&cfg), lock_path)
+}
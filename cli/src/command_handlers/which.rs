@@ -0,0 +1,170 @@
+use crate::config::{project_bin_dir, TlkConfig, Tool};
+use crate::platform::platform;
+use crate::{installer, probe};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+// which: the binary a tool name resolves to, in the order the hook puts directories on PATH:
+// the project's install dir (.tlk/bin or the tool's install_dir), the global ~/.tlk/bin, the
+// system dir, then the rest of PATH. --all lists every existing candidate; all but the first are
+// shadowed.
+pub fn which(cfg: Option<&TlkConfig>, name: &str, all: bool) -> Result<()> {
+    let bin = platform().final_binary_name(name);
+    let declared = cfg.and_then(|c| c.tools.iter().chain(&c.skipped).find(|t| t.name == name));
+    let project = match declared {
+        Some(tool) => Some(installer::target_path(tool)?),
+        None => installer::project_root().map(|root| project_bin_dir(&root).join(&bin)),
+    };
+
+    let mut candidates: Vec<(&str, PathBuf)> = Vec::new();
+    candidates.extend(project.map(|p| ("project", p)));
+    candidates.extend(
+        platform()
+            .global_bin_dir()
+            .map(|d| ("global", d.join(&bin))),
+    );
+    candidates.extend(
+        platform()
+            .system_bin_dir()
+            .map(|d| ("system", d.join(&bin))),
+    );
+    if let Some(path) = std::env::var_os("PATH") {
+        candidates.extend(std::env::split_paths(&path).map(|d| ("PATH", d.join(&bin))));
+    }
+
+    let mut found: Vec<(&str, PathBuf)> = Vec::new();
+    for (label, path) in candidates {
+        if !path.is_file() {
+            continue;
+        }
+        // Install paths carry the Windows extended-length prefix; compare and print plain forms.
+        let path = platform().display_path(&std::path::absolute(&path).unwrap_or(path));
+        if !found.iter().any(|(_, p)| p == &path) {
+            found.push((label, path));
+        }
+    }
+    let Some((_, active)) = found.first() else {
+        anyhow::bail!("{name} not found in the project, global or system bin dirs or on PATH");
+    };
+    if !all {
+        println!("{}", active.display());
+        return Ok(());
+    }
+    for (i, (label, path)) in found.iter().enumerate() {
+        let note = if i == 0 { "" } else { " (shadowed)" };
+        println!("{:<8} {}{note}", label, path.display());
+    }
+    Ok(())
+}
+
+/// A declared tool whose name resolves to another binary on the shell's PATH.
+pub struct Shadow {
+    pub tool: String,
+    pub version: String,
+    pub installed: PathBuf,
+    pub shadowing: PathBuf,
+    /// Version the shadowing binary reports ("unprobed" with probing off).
+    pub shadowing_version: String,
+}
+
+// The usual "tlk isn't working": the hook is not active, so a Homebrew / distro copy earlier on
+// PATH wins over .tlk/bin. Copies that are byte-identical or report the pinned version are
+// harmless and not reported. Probing honours the tool's [probe] settings.
+pub fn shadowed(cfg: &TlkConfig) -> Vec<Shadow> {
+    let Some(path) = installer::shell_path() else {
+        return Vec::new();
+    };
+    std::thread::scope(|s| {
+        let handles: Vec<_> = cfg
+            .tools
+            .iter()
+            .map(|t| s.spawn(|| shadow_of(t, &path)))
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|h| h.join().ok().flatten())
+            .collect()
+    })
+}
+
+fn shadow_of(tool: &Tool, path: &std::ffi::OsStr) -> Option<Shadow> {
+    let installed = installer::target_path(tool).ok()?;
+    if !installed.is_file() {
+        return None;
+    }
+    let bin = installed.file_name()?;
+    let first = std::env::split_paths(path)
+        .map(|d| d.join(bin))
+        .find(|p| p.is_file())?;
+    if same_binary(&first, &installed) {
+        return None;
+    }
+    let (version, _) = installer::normalize_version(&tool.version);
+    let settings = tool.probe.clone().unwrap_or_default();
+    let shadowing_version = if settings.enabled() {
+        probe::probe_version(&first, &settings).unwrap_or_else(|_| "unknown".into())
+    } else {
+        "unprobed".into()
+    };
+    if shadowing_version == version {
+        return None;
+    }
+    Some(Shadow {
+        tool: tool.name.clone(),
+        version,
+        installed: platform().display_path(&installed),
+        shadowing: std::path::absolute(&first).unwrap_or(first),
+        shadowing_version,
+    })
+}
+
+fn same_binary(a: &Path, b: &Path) -> bool {
+    if let (Ok(ca), Ok(cb)) = (a.canonicalize(), b.canonicalize()) {
+        if ca == cb {
+            return true;
+        }
+    }
+    let same_len = match (a.metadata(), b.metadata()) {
+        (Ok(ma), Ok(mb)) => ma.len() == mb.len(),
+        _ => false,
+    };
+    same_len && matches!((std::fs::read(a), std::fs::read(b)), (Ok(da), Ok(db)) if da == db)
+}
+
+fn print_shadows(shadows: &[Shadow]) {
+    for s in shadows {
+        eprintln!(
+            "Warning: {} resolves to {} ({}), not tlk's {} at {}",
+            s.tool,
+            s.shadowing.display(),
+            s.shadowing_version,
+            s.version,
+            s.installed.display()
+        );
+    }
+    if let Some(dir) = shadows.first().and_then(|s| s.installed.parent()) {
+        eprintln!(
+            "  Put {} first on PATH: add 'eval \"$(tlk hook)\"' to your shell rc, or run tools through 'tlk exec'.",
+            dir.display()
+        );
+    }
+}
+
+/// After installs: report shadowed tools without failing the install.
+pub fn warn_shadowed(cfg: &TlkConfig) {
+    print_shadows(&shadowed(cfg));
+}
+
+// diagnose --kind path-shadowing: the same scan, failing when anything is shadowed.
+pub fn diagnose_shadowed(cfg: Option<&TlkConfig>) -> Result<()> {
+    let Some(cfg) = cfg else {
+        anyhow::bail!("No config provided for diagnose");
+    };
+    let shadows = shadowed(cfg);
+    if shadows.is_empty() {
+        println!("No declared tool is shadowed on PATH");
+        return Ok(());
+    }
+    print_shadows(&shadows);
+    anyhow::bail!("{} tool(s) shadowed on PATH", shadows.len());
+}
@@ -1,13 +1,292 @@
-use crate::known_tools::extract_shorthand;
+use crate::known_tools::{extract_shorthand, SourceSpec};
+use crate::naming::NamingScheme;
+use crate::template::{self, TemplateMaps};
 use crate::unknown_tools::{augment_binary_fields, parse_unknown};
 use anyhow::{Context, Result};
-use serde::Deserialize;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 
 #[derive(Debug, Clone)]
 pub struct TlkConfig {
     pub tools: Vec<Tool>,
+    /// Declared tools whose `when` condition is false in this environment. They are not
+    /// installed or verified, but their lock entries are kept.
+    pub skipped: Vec<Tool>,
+    pub settings: Settings,
+    pub cache: CacheSettings,
+    pub network: NetworkSettings,
+    /// [mirrors]: upstream URL prefix -> mirror prefix, applied to every download.
+    pub mirrors: BTreeMap<String, String>,
+}
+
+/// Limits for the shared download cache from the optional [cache] table.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct CacheSettings {
+    /// Size cap such as "5GB"; least recently used downloads are evicted beyond it.
+    #[serde(default)]
+    pub max_size: Option<String>,
+    /// Keep artifacts recorded at a new version by a lock write, exempt from eviction.
+    #[serde(default)]
+    pub archive: Option<bool>,
+    /// Directory or http(s) base URL that archived artifacts are also copied to (implies archive).
+    #[serde(default)]
+    pub archive_to: Option<String>,
+}
+
+/// Proxy for every request from the optional [network] table. Without it the usual
+/// HTTPS_PROXY / HTTP_PROXY / ALL_PROXY / NO_PROXY environment variables apply.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct NetworkSettings {
+    /// http://, https://, socks5:// or socks5h:// URL, credentials included if needed.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Comma separated hosts / domains / CIDRs that bypass `proxy` (defaults to NO_PROXY).
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+    /// PEM file of extra trusted root certificates (TLS-intercepting proxies); relative paths
+    /// are resolved against the directory of tlk.toml.
+    #[serde(default)]
+    pub ca_bundle: Option<String>,
+    /// Accept any server certificate, like `--insecure`.
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+/// Project wide options from the optional [settings] table.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Settings {
+    /// Defaults applied to every tool that does not declare its own permissions.
+    #[serde(default)]
+    pub permissions: Option<Permissions>,
+    /// Defaults for running installed binaries to detect their version.
+    #[serde(default)]
+    pub probe: Option<ProbeSettings>,
+    /// Notification endpoints ([[settings.webhooks]]) for lock updates and verify failures.
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    /// Pin the first download's sha256 in tlk.lock for tools without a configured sha256.
+    #[serde(default)]
+    pub tofu: bool,
+    /// TLS policy for downloads ([settings.tls]).
+    #[serde(default)]
+    pub tls: Option<TlsSettings>,
+    /// Message template for `--commit` on install/update; {changes} lists the bumps
+    /// ("terraform 1.7.5 -> 1.8.2, jq 1.7.1").
+    #[serde(default)]
+    pub commit_message: Option<String>,
+    /// Attempts per HTTP request before a transient failure (connection error, timeout, 5xx)
+    /// fails the command (default 3; 1 disables retrying).
+    #[serde(default)]
+    pub http_attempts: Option<u32>,
+    /// Tools installed concurrently (default 8); `--jobs` overrides it.
+    #[serde(default)]
+    pub jobs: Option<usize>,
+    /// Seconds to wait for another tlk run in the project to finish (default 600).
+    #[serde(default)]
+    pub lock_timeout: Option<u64>,
+    /// Combined download rate for all tools ("2M" = 2 MiB/s); `--limit-rate` overrides it.
+    #[serde(default)]
+    pub limit_rate: Option<String>,
+    /// Kubernetes version of the cluster the project targets ("1.29"); kubectl pins more than
+    /// one minor version away get a skew warning on install / verify.
+    #[serde(default)]
+    pub cluster_version: Option<String>,
+    /// Environment variable holding a GitHub token for API calls, checked before GITHUB_TOKEN and
+    /// GH_TOKEN. The token itself never belongs in tlk.toml.
+    #[serde(default)]
+    pub github_token_env: Option<String>,
+    /// Where the project's tools are installed, relative to the project root (default
+    /// ".tlk/bin"); see [`project_bin_dir`].
+    #[serde(default)]
+    pub bin_dir: Option<String>,
+    /// Warn after installs when another copy of a tool comes first on PATH (default true).
+    #[serde(default)]
+    pub path_check: Option<bool>,
+    /// License policy enforced by `tlk licenses` ([settings.licenses]).
+    #[serde(default)]
+    pub licenses: Option<LicensePolicy>,
+    /// sha256 of tlk.lock kept current by every lock write, or "file" to keep it in
+    /// .tlk/lock.sha256 instead (see the lock checksum section of lock.rs).
+    #[serde(default)]
+    pub lock_hash: Option<String>,
+}
+
+/// SPDX ids allowed / denied for pinned tools. With a non-empty `allow`, every license must be
+/// listed (unknown licenses fail too); `deny` always wins.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct LicensePolicy {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Minimum TLS version ("1.2" default, or "1.3") and per-host options keyed by host name.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TlsSettings {
+    #[serde(default)]
+    pub min_version: Option<String>,
+    #[serde(default)]
+    pub hosts: HashMap<String, TlsHost>,
+}
+
+/// `pin_sha256`: accepted hex sha256 hashes of the server certificate's public key (SPKI).
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TlsHost {
+    #[serde(default)]
+    pub pin_sha256: Vec<String>,
+}
+
+/// An HTTP endpoint notified on events. `url_env` names an environment variable holding the
+/// URL so secrets stay out of tlk.toml. `format` is "json" (default) or "slack"; `template`
+/// replaces the payload entirely ({event}, {project}, {summary}, {details}).
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Webhook {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub url_env: Option<String>,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Event names to send (lock-updated, verify-failed); all when omitted.
+    #[serde(default)]
+    pub events: Option<Vec<String>>,
+    /// Only fire when running in CI (the CI environment variable is set).
+    #[serde(default)]
+    pub ci_only: bool,
+}
+
+/// Expected sha256 of the downloaded artifact: a single value, or one per platform keyed like the
+/// lock's `sources` matrix (`sha256 = { "linux-amd64" = "…", "darwin-arm64" = "…" }`). A table
+/// only lets platforms it does not list install unverified when it says so with
+/// `"*" = "unverified"`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Checksum {
+    Single(String),
+    PerPlatform(HashMap<String, String>),
+}
+
+impl Checksum {
+    pub fn for_platform(&self, key: &str) -> Option<&str> {
+        match self {
+            Checksum::Single(sum) => Some(sum),
+            Checksum::PerPlatform(map) => map.get(key).map(String::as_str),
+        }
+    }
+
+    /// The checksum for the artifact this machine downloads.
+    pub fn for_host(&self) -> Option<&str> {
+        self.for_platform(&host_platform_key())
+    }
+
+    /// Whether platforms missing from a per-platform table may install without verification.
+    pub fn allows_unverified(&self) -> bool {
+        match self {
+            Checksum::Single(_) => false,
+            Checksum::PerPlatform(map) => map.get("*").is_some_and(|v| v == UNVERIFIED),
+        }
+    }
+
+    /// Reject per-platform keys that can never match a machine (`linux-x86_64` for
+    /// `linux-amd64`), since a misspelt key would leave that platform unverified.
+    pub fn check_keys(&self) -> Result<()> {
+        let Checksum::PerPlatform(map) = self else {
+            return Ok(());
+        };
+        let mut keys: Vec<_> = map.keys().collect();
+        keys.sort();
+        for key in keys {
+            if key == "*" {
+                if map[key] != UNVERIFIED {
+                    anyhow::bail!("sha256 \"*\" only accepts \"{UNVERIFIED}\"");
+                }
+                continue;
+            }
+            if *key == host_platform_key() {
+                continue;
+            }
+            let Some((os, arch)) = key.split_once('-') else {
+                anyhow::bail!(
+                    "sha256 key '{key}' is not a platform key (expected os-arch, e.g. linux-amd64)"
+                );
+            };
+            let (os, arch) = (
+                crate::naming::canonical_os(os),
+                crate::naming::canonical_arch(arch),
+            );
+            if !KNOWN_OSES.contains(&os) || !KNOWN_ARCHES.contains(&arch) {
+                anyhow::bail!(
+                    "sha256 key '{key}' is not a platform key (expected os-arch, e.g. linux-amd64)"
+                );
+            }
+            if format!("{os}-{arch}") != *key {
+                anyhow::bail!("sha256 key '{key}' is not a platform key (use '{os}-{arch}')");
+            }
+        }
+        Ok(())
+    }
+}
+
+const UNVERIFIED: &str = "unverified";
+// Platform key parts as `host_platform_key()` spells them on the machines tlk runs on.
+const KNOWN_OSES: &[&str] = &["linux", "darwin", "windows", "freebsd"];
+const KNOWN_ARCHES: &[&str] = &["amd64", "arm64", "x86", "arm", "riscv64", "s390x", "powerpc64"];
+
+/// Install directory of the project rooted at `root`: `[settings] bin_dir` from its tlk.toml, or
+/// `.tlk/bin`. Only that key is read, so PATH helpers stay cheap and work with an otherwise broken
+/// config.
+pub fn project_bin_dir(root: &std::path::Path) -> std::path::PathBuf {
+    let configured = fs::read_to_string(root.join("tlk.toml"))
+        .ok()
+        .and_then(|data| data.parse::<toml::Value>().ok())
+        .and_then(|v| {
+            v.get("settings")?
+                .get("bin_dir")?
+                .as_str()
+                .map(str::to_string)
+        });
+    match configured {
+        Some(dir) => root.join(dir),
+        None => root.join(".tlk").join("bin"),
+    }
+}
+
+/// Platform key of this machine in the `{os}-{arch}` naming of source templates.
+pub fn host_platform_key() -> String {
+    format!(
+        "{}-{}",
+        crate::known_tools::placeholder_os(),
+        crate::known_tools::placeholder_arch()
+    )
+}
+
+/// How installed binaries are executed (`<bin> --version`) to detect their version.
+/// `enabled = false` never executes the binary (installed state is matched by lock digest);
+/// `sandbox = true` scrubs the environment and blocks network access where possible.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ProbeSettings {
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub sandbox: Option<bool>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Target permissions for installed binaries and the directories holding them.
+/// Modes are octal strings (e.g. "0750"); group may be a name or numeric gid.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Permissions {
+    #[serde(default)]
+    pub file_mode: Option<String>,
+    #[serde(default)]
+    pub dir_mode: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -16,22 +295,82 @@ pub struct Tool {
     pub version: String,
     #[serde(default = "default_kind")]
     pub kind: ToolKind,
-    /// URL template. Supports {version}, {os}, {arch}
+    /// URL template. Supports {version}, {os}, {arch} with optional |filters (see template.rs)
     pub source: String,
+    /// Named mapping tables usable as filters in source templates, e.g. {arch|x64}
+    #[serde(default)]
+    pub maps: Option<TemplateMaps>,
+    /// Naming scheme {os}/{arch} expand to (go-style default, gnu-style, node-style, macos-style)
     #[serde(default)]
-    pub sha256: Option<String>,
+    pub naming: Option<NamingScheme>,
+    #[serde(default)]
+    pub sha256: Option<Checksum>,
     #[serde(default)]
     pub binary: Option<String>,
+    /// Extra archive paths tried for the binary before the built-in candidates of `binary`
+    /// (e.g. "dist/tool" when a release changed its archive layout)
+    #[serde(default)]
+    pub archive_entries: Option<Vec<String>>,
+    /// Archive paths (below its top-level directory) of every command to put on PATH, e.g.
+    /// ["bin/node", "bin/npm", "bin/npx"]; the archive is kept whole under .tlk/toolchains
+    #[serde(default)]
+    pub binaries: Option<Vec<String>>,
+    /// "all": keep the whole archive under .tlk/toolchains and run the binary from there, for
+    /// binaries that load files shipped next to them
+    #[serde(default)]
+    pub extract: Option<Extract>,
+    /// Install location override (relative paths resolve against the project root)
     #[serde(default)]
     pub install_dir: Option<String>,
+    #[serde(default)]
+    pub permissions: Option<Permissions>,
+    #[serde(default)]
+    pub probe: Option<ProbeSettings>,
+    /// Install only when the condition holds: "env:NAME", "env:NAME=value", negated with "!"
+    #[serde(default)]
+    pub when: Option<String>,
+    /// Trust-on-first-use checksum pinning (defaults to `[settings] tofu`)
+    #[serde(default)]
+    pub tofu: Option<bool>,
+    /// "corepack": run the package manager through the node toolchain's corepack instead of
+    /// downloading its standalone binary
+    #[serde(default)]
+    pub via: Option<Via>,
+    /// Download rate for this tool ("500K" = 500 KiB/s), on top of the global limit
+    #[serde(default)]
+    pub limit_rate: Option<String>,
+    /// SPDX license id for `tlk licenses`, overriding the recipe's or GitHub's
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Environment variables exported with the project toolchain (`tlk env`, `tlk exec`);
+    /// `{root}` expands to the project root
+    #[serde(default)]
+    pub env: Option<HashMap<String, String>>,
     /// Per-OS templates (keys: linux, mac, windows). Supports {version} and {arch}.
     #[serde(default)]
     pub per_os: Option<PerOsSources>,
     /// Per OS+Arch templates. Allows fine grained override.
     #[serde(default)]
     pub per_os_arch: Option<PerOsArchSources>,
+    /// Known-tool source builder (version, os, arch) used instead of templates when present.
+    #[serde(skip)]
+    pub source_fn: Option<SourceFn>,
+    /// Version-scoped sources of a known tool, chosen by the version being rendered.
+    #[serde(skip)]
+    pub variants: &'static [crate::known_tools::Variant],
+    /// Alternative sources of a known tool tried when the primary download fails.
+    #[serde(skip)]
+    pub fallbacks: &'static [SourceSpec],
+    /// Commands shipped alongside a known tool's binary that get shims of their own.
+    #[serde(skip)]
+    pub companions: &'static [crate::known_tools::Companion],
+    /// sha256 the installed binary must have (set from the lock digest by locked installs).
+    #[serde(skip)]
+    pub expected_digest: Option<String>,
 }
 
+pub type SourceFn = fn(&str, &str, &str) -> String;
+
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct PerOsSources {
     #[serde(default)]
@@ -65,13 +404,25 @@ pub struct ArchSources {
     pub aarch64: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum ToolKind {
     Archive,
     Direct,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Via {
+    Corepack,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Extract {
+    All,
+}
+
 fn default_kind() -> ToolKind {
     ToolKind::Archive
 }
@@ -83,7 +434,7 @@ impl TlkConfig {
             Ok(d) => d,
             Err(_) => return None,
         };
-        let value: toml::Value = match data.parse::<toml::Value>() {
+        let mut value: toml::Value = match data.parse::<toml::Value>() {
             Ok(v) => v,
             Err(_) => {
                 if data.trim_start().starts_with('{') {
@@ -106,17 +457,415 @@ impl TlkConfig {
                 }
             }
         };
+        let base = std::path::Path::new(path)
+            .parent()
+            .unwrap_or(std::path::Path::new(""));
+        if let Err(e) = apply_profile(&mut value).and_then(|()| substitute_vars(&mut value)) {
+            eprintln!("Error: {e:#}");
+            return None;
+        }
+        if let Err(e) = apply_version_files(&mut value, base) {
+            eprintln!("Error: {e:#}");
+            return None;
+        }
         // Unknown (user-provided) tools from [[tools]] entries
         let tools = parse_unknown(&value);
         let Ok(mut tools) = tools else { return None };
         let explicit_names: HashSet<String> = tools.iter().map(|t| t.name.clone()).collect();
         // Known shorthand single-line entries (terraform = "1.2.3")
-        let mut shorthand = extract_shorthand(&value, &explicit_names);
+        let mut shorthand = match extract_shorthand(&value, &explicit_names) {
+            Ok(tools) => tools,
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                return None;
+            }
+        };
         tools.append(&mut shorthand);
         augment_binary_fields(&mut tools);
 
-        Some(TlkConfig { tools })
+        let settings: Settings = match section(&value, "settings") {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                return None;
+            }
+        };
+        for t in tools.iter_mut() {
+            settings.apply(t);
+        }
+        let cache: CacheSettings = match section(&value, "cache") {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                return None;
+            }
+        };
+        let mut network: NetworkSettings = match section(&value, "network") {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                return None;
+            }
+        };
+        if let Some(bundle) = &mut network.ca_bundle {
+            *bundle = base.join(&*bundle).to_string_lossy().to_string();
+        }
+        let mirrors: BTreeMap<String, String> = match section(&value, "mirrors") {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                return None;
+            }
+        };
+        let mut skipped = Vec::new();
+        let mut active = Vec::new();
+        for t in tools {
+            if let Err(e) = t.check_templates() {
+                eprintln!("Error: {e:#}");
+                return None;
+            }
+            match t.condition_met() {
+                Ok(true) => active.push(t),
+                Ok(false) => skipped.push(t),
+                Err(e) => {
+                    eprintln!("Error: {e:#}");
+                    return None;
+                }
+            }
+        }
+
+        Some(TlkConfig {
+            tools: active,
+            skipped,
+            settings,
+            cache,
+            network,
+            mirrors,
+        })
+    }
+
+    /// Clone of this config with every tool redirected into `dir`.
+    pub fn with_install_dir(&self, dir: &str) -> TlkConfig {
+        let mut cfg = self.clone();
+        for t in cfg.tools.iter_mut() {
+            t.install_dir = Some(dir.to_string());
+        }
+        cfg
+    }
+}
+
+impl Tool {
+    /// Configured sha256 of this machine's artifact, if any.
+    pub fn host_sha256(&self) -> Option<&str> {
+        self.sha256.as_ref().and_then(Checksum::for_host)
+    }
+
+    /// Whether this tool's `when` condition holds in the current environment (true when unset).
+    pub fn condition_met(&self) -> Result<bool> {
+        let Some(expr) = self.when.as_deref().map(str::trim) else {
+            return Ok(true);
+        };
+        let (negate, cond) = match expr.strip_prefix('!') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, expr),
+        };
+        let Some(var) = cond.strip_prefix("env:") else {
+            anyhow::bail!(
+                "tool '{}': unsupported when = \"{expr}\" (use \"env:NAME\", \"env:NAME=value\" or \"!env:NAME\")",
+                self.name
+            );
+        };
+        let holds = match var.split_once('=') {
+            Some((name, want)) => std::env::var(name).is_ok_and(|v| v == want),
+            // Set counts as true unless it is empty or an explicit false value.
+            None => std::env::var(var)
+                .is_ok_and(|v| !matches!(v.to_ascii_lowercase().as_str(), "" | "0" | "false")),
+        };
+        Ok(holds != negate)
+    }
+}
+
+impl Settings {
+    /// Fill tool fields left unset from project wide [settings].
+    pub fn apply(&self, tool: &mut Tool) {
+        if tool.permissions.is_none() {
+            tool.permissions = self.permissions.clone();
+        }
+        if tool.probe.is_none() {
+            tool.probe = self.probe.clone();
+        }
+        if tool.tofu.is_none() {
+            tool.tofu = Some(self.tofu);
+        }
+    }
+}
+
+impl Webhook {
+    pub fn wants(&self, event: &str) -> bool {
+        if self.ci_only && std::env::var_os("CI").is_none() {
+            return false;
+        }
+        self.events
+            .as_ref()
+            .is_none_or(|evs| evs.iter().any(|e| e == event))
+    }
+
+    pub fn resolve_url(&self) -> Option<String> {
+        if let Some(var) = &self.url_env {
+            return std::env::var(var).ok().filter(|u| !u.is_empty());
+        }
+        self.url.clone()
+    }
+}
+
+impl ProbeSettings {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+    pub fn sandbox(&self) -> bool {
+        self.sandbox.unwrap_or(false)
+    }
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs.unwrap_or(10))
+    }
+}
+
+impl Permissions {
+    pub fn file_mode(&self) -> Result<u32> {
+        parse_mode(self.file_mode.as_deref(), 0o755)
+    }
+    pub fn dir_mode(&self) -> Result<u32> {
+        parse_mode(self.dir_mode.as_deref(), 0o755)
+    }
+}
+
+// Profiles: `[profiles.<name>]` tables mirror the top level of tlk.toml (shorthand tools,
+// [tools.x], [settings], [mirrors], [vars], ...) and are merged over it when `--profile <name>` or
+// TLK_PROFILE selects them, so prod pipelines can pin stricter than laptops. Tables merge key by
+// key; any other value replaces the base one, except that a bare version string for a shorthand
+// tool declared as an inline table only replaces its `version`.
+
+static PROFILE: once_cell::sync::OnceCell<String> = once_cell::sync::OnceCell::new();
+
+pub fn set_profile(name: String) {
+    let _ = PROFILE.set(name);
+}
+
+/// The profile selected for this run, if any.
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+// A top-level table such as [settings], or its default when tlk.toml has none.
+fn section<T: serde::de::DeserializeOwned + Default>(root: &toml::Value, key: &str) -> Result<T> {
+    match root.get(key) {
+        Some(v) => v.clone().try_into().with_context(|| format!("parsing [{key}]")),
+        None => Ok(T::default()),
+    }
+}
+
+fn apply_profile(root: &mut toml::Value) -> Result<()> {
+    let Some(tbl) = root.as_table_mut() else {
+        return Ok(());
+    };
+    let profiles = tbl.remove("profiles");
+    let Some(name) = profile() else {
+        return Ok(());
+    };
+    let Some(overlay) = profiles.as_ref().and_then(|p| p.get(name)) else {
+        let mut known: Vec<&str> = profiles
+            .as_ref()
+            .and_then(|p| p.as_table())
+            .map(|p| p.keys().map(String::as_str).collect())
+            .unwrap_or_default();
+        known.sort();
+        if known.is_empty() {
+            anyhow::bail!("profile '{name}' is not defined (tlk.toml has no [profiles] tables)");
+        }
+        anyhow::bail!(
+            "profile '{name}' is not defined (available: {})",
+            known.join(", ")
+        );
+    };
+    let Some(overlay) = overlay.as_table() else {
+        anyhow::bail!("[profiles.{name}] must be a table");
+    };
+    for (key, value) in overlay {
+        match (tbl.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::String(version))
+                if base.contains_key("version") =>
+            {
+                base.insert("version".into(), toml::Value::String(version.clone()));
+            }
+            (Some(base), _) => merge(base, value),
+            (None, _) => {
+                tbl.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge(base: &mut toml::Value, overlay: &toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+// `${name}` in any string value expands to `[vars] name` (after the profile's own [vars] are
+// merged). References to names that are not declared are left alone, so shell snippets using
+// `${HOME}` pass through untouched.
+fn substitute_vars(root: &mut toml::Value) -> Result<()> {
+    let Some(vars) = root.as_table_mut().and_then(|t| t.remove("vars")) else {
+        return Ok(());
+    };
+    let Some(vars) = vars.as_table() else {
+        anyhow::bail!("[vars] must be a table of strings");
+    };
+    let mut values = HashMap::new();
+    for (name, value) in vars {
+        let Some(value) = value.as_str() else {
+            anyhow::bail!("[vars] {name} must be a string");
+        };
+        values.insert(name.as_str(), value);
+    }
+    expand(root, &values);
+    Ok(())
+}
+
+fn expand(value: &mut toml::Value, vars: &HashMap<&str, &str>) {
+    match value {
+        toml::Value::String(s) if s.contains("${") => {
+            let mut out = String::with_capacity(s.len());
+            let mut rest = s.as_str();
+            while let Some(start) = rest.find("${") {
+                let Some(len) = rest[start..].find('}') else {
+                    break;
+                };
+                let name = &rest[start + 2..start + len];
+                out.push_str(&rest[..start]);
+                match vars.get(name) {
+                    Some(v) => out.push_str(v),
+                    None => out.push_str(&rest[start..=start + len]),
+                }
+                rest = &rest[start + len + 1..];
+            }
+            out.push_str(rest);
+            *s = out;
+        }
+        toml::Value::Array(items) => items.iter_mut().for_each(|v| expand(v, vars)),
+        toml::Value::Table(tbl) => tbl.iter_mut().for_each(|(_, v)| expand(v, vars)),
+        _ => {}
+    }
+}
+
+/// Top-level tables of tlk.toml that are not tool declarations.
+pub const RESERVED_KEYS: [&str; 7] = [
+    "tools", "settings", "cache", "network", "mirrors", "profiles", "vars",
+];
+
+// Tools may take their version from a tool-native pin file via `version_from` (relative to the
+// config): `[tools.foo] version_from = ".foo-version"` or, for known tools,
+// `terraform = { version_from = ".terraform-version" }`. The file stays the source of truth; the
+// pin is filled in as `version` before tools are built.
+fn apply_version_files(root: &mut toml::Value, base: &std::path::Path) -> Result<()> {
+    let Some(tbl) = root.as_table_mut() else {
+        return Ok(());
+    };
+    for (name, entry) in tbl.iter_mut() {
+        if RESERVED_KEYS.contains(&name.as_str()) {
+            continue;
+        }
+        let file = entry.get("version_from").and_then(|v| v.as_str());
+        if let (Some(file), toml::Value::Table(t)) = (file.map(str::to_string), &mut *entry) {
+            let version = read_version_file(&base.join(file), name)?;
+            t.insert("version".into(), toml::Value::String(version));
+        }
+    }
+    let pin = |name: &str, entry: &mut toml::Table| -> Result<()> {
+        if let Some(file) = entry.get("version_from").and_then(|v| v.as_str()) {
+            let version = read_version_file(&base.join(file), name)?;
+            entry.insert("version".into(), toml::Value::String(version));
+        }
+        Ok(())
+    };
+    match tbl.get_mut("tools") {
+        Some(toml::Value::Table(tools)) => {
+            for (name, entry) in tools.iter_mut() {
+                if let toml::Value::Table(t) = entry {
+                    pin(name, t)?;
+                }
+            }
+        }
+        Some(toml::Value::Array(items)) => {
+            for item in items.iter_mut() {
+                if let toml::Value::Table(t) = item {
+                    let name = t
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    pin(&name, t)?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// First meaningful line of a pin file (leading "v" dropped). asdf-style .tool-versions files are
+// searched for the tool's own line. Aliases such as "lts/*" or "latest" are rejected because the
+// lock must record a concrete version.
+fn read_version_file(path: &std::path::Path, tool: &str) -> Result<String> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("reading version file {} for {tool}", path.display()))?;
+    let asdf = path.file_name() == Some(".tool-versions".as_ref());
+    let found = raw
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default().trim())
+        .filter(|l| !l.is_empty())
+        .find_map(|l| {
+            if asdf {
+                let mut parts = l.split_whitespace();
+                (parts.next() == Some(tool)).then(|| parts.next().map(str::to_string))?
+            } else {
+                Some(l.to_string())
+            }
+        })
+        .ok_or_else(|| anyhow::anyhow!("no version for {tool} in {}", path.display()))?;
+    let version = found.trim_start_matches('v').to_string();
+    if !version.starts_with(|c: char| c.is_ascii_digit() || "^~=<>".contains(c)) {
+        anyhow::bail!(
+            "{}: '{found}' is an alias, not a version; pin an exact version for {tool}",
+            path.display()
+        );
+    }
+    Ok(version)
+}
+
+fn parse_mode(raw: Option<&str>, default: u32) -> Result<u32> {
+    let Some(raw) = raw else {
+        return Ok(default);
+    };
+    let digits = raw.trim().trim_start_matches("0o");
+    let mode = u32::from_str_radix(digits, 8).with_context(|| {
+        format!("invalid permission mode '{raw}' (expected octal like \"0755\")")
+    })?;
+    if mode > 0o7777 {
+        return Err(anyhow::anyhow!("permission mode '{raw}' out of range"));
     }
+    Ok(mode)
 }
 
 impl Tool {
@@ -168,6 +917,61 @@ impl Tool {
         }
         self.source.clone()
     }
+
+    /// Concrete (pre platform-adjustment) source URL for any canonical os/arch, not just the host.
+    pub fn source_for(&self, version: &str, os: &str, arch: &str) -> String {
+        if let Some(v) = self.variants.iter().find(|v| v.matches(version)) {
+            return match v.source {
+                SourceSpec::Template(tpl) => self.render_template(tpl, version, os, arch),
+                SourceSpec::Custom(f) => f(version, os, arch),
+            };
+        }
+        if let Some(f) = self.source_fn {
+            return f(version, os, arch);
+        }
+        let template = self.effective_source_template(os, arch);
+        self.render_template(&template, version, os, arch)
+    }
+
+    /// Fallback URLs for a canonical os/arch, in the order they are tried.
+    pub fn fallback_sources_for(&self, version: &str, os: &str, arch: &str) -> Vec<String> {
+        self.fallbacks
+            .iter()
+            .map(|spec| match spec {
+                SourceSpec::Template(tpl) => self.render_template(tpl, version, os, arch),
+                SourceSpec::Custom(f) => f(version, os, arch),
+            })
+            .collect()
+    }
+
+    /// Render a source template for a canonical os/arch, applying this tool's naming scheme and maps.
+    pub fn render_template(&self, template: &str, version: &str, os: &str, arch: &str) -> String {
+        let (os, arch) = match self.naming {
+            Some(scheme) => (scheme.os(os), scheme.arch(arch)),
+            None => (os, arch),
+        };
+        // Configured templates are checked when the config loads (check_templates); a bad one
+        // from elsewhere (an old lock's source_template) renders as written.
+        template::render(template, version, os, arch, self.maps.as_ref())
+            .unwrap_or_else(|_| template.to_string())
+    }
+
+    /// Reject source templates that use unknown filters.
+    pub fn check_templates(&self) -> Result<()> {
+        let per_os = self.per_os.iter().flat_map(|p| [&p.linux, &p.mac, &p.windows]);
+        let per_os_arch = self
+            .per_os_arch
+            .iter()
+            .flat_map(|p| [&p.linux, &p.mac, &p.windows])
+            .flatten()
+            .flat_map(|a| [&a.amd64, &a.arm64, &a.x86_64, &a.aarch64]);
+        let templates = per_os.chain(per_os_arch).flatten();
+        for tpl in std::iter::once(&self.source).chain(templates) {
+            template::check(tpl, self.maps.as_ref())
+                .with_context(|| format!("tool '{}': source {tpl}", self.name))?;
+        }
+        Ok(())
+    }
 }
 
 // placeholder helpers available in known_tools
@@ -245,9 +1049,7 @@ fn split_top_level(s: &str) -> Vec<String> {
                     cur.push(c);
                 }
                 '}' => {
-                    if depth_brace > 0 {
-                        depth_brace -= 1;
-                    }
+                    depth_brace = depth_brace.saturating_sub(1);
                     cur.push(c);
                 }
                 '[' => {
@@ -255,9 +1057,7 @@ fn split_top_level(s: &str) -> Vec<String> {
                     cur.push(c);
                 }
                 ']' => {
-                    if depth_bracket > 0 {
-                        depth_bracket -= 1;
-                    }
+                    depth_bracket = depth_bracket.saturating_sub(1);
                     cur.push(c);
                 }
                 ',' if depth_brace == 0 && depth_bracket == 0 => {
@@ -301,9 +1101,7 @@ fn split_inline_tables(s: &str) -> Vec<String> {
                 cur.push(c);
             }
             '}' => {
-                if depth > 0 {
-                    depth -= 1;
-                }
+                depth = depth.saturating_sub(1);
                 cur.push(c);
                 if depth == 0 {
                     out.push(
@@ -326,3 +1124,51 @@ fn split_inline_tables(s: &str) -> Vec<String> {
     }
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(pairs: &[(&str, &str)]) -> Checksum {
+        Checksum::PerPlatform(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn per_platform_keys_must_be_canonical() {
+        assert!(table(&[("linux-amd64", "ab"), ("darwin-arm64", "cd")])
+            .check_keys()
+            .is_ok());
+        let err = table(&[("linux-x86_64", "ab")]).check_keys().unwrap_err();
+        assert!(err.to_string().contains("use 'linux-amd64'"), "{err}");
+        assert!(table(&[("linux", "ab")]).check_keys().is_err());
+        assert!(table(&[("*", "skip")]).check_keys().is_err());
+    }
+
+    #[test]
+    fn only_an_explicit_wildcard_allows_unverified_platforms() {
+        assert!(!table(&[("linux-amd64", "ab")]).allows_unverified());
+        assert!(table(&[("linux-amd64", "ab"), ("*", "unverified")]).allows_unverified());
+        assert!(!Checksum::Single("ab".into()).allows_unverified());
+    }
+
+    #[test]
+    fn bad_filters_in_per_os_arch_synonyms_fail_the_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tlk.toml");
+        let config = |filter: &str| {
+            format!(
+                "[tools.foo]\nversion = \"1.0.0\"\nkind = \"direct\"\nsource = \"https://x/{{version}}\"\n\
+                 [tools.foo.per_os_arch.linux]\nx86_64 = \"https://x/{{version}}-{{arch|{filter}}}\"\n"
+            )
+        };
+        std::fs::write(&path, config("upper")).unwrap();
+        assert!(TlkConfig::load(&path.to_string_lossy()).is_some());
+        std::fs::write(&path, config("typo")).unwrap();
+        assert!(TlkConfig::load(&path.to_string_lossy()).is_none());
+    }
+}
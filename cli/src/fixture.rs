@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+use std::path::{Path, PathBuf};
+
+// Fixture mode (hidden `--fixture-dir DIR`, or TLK_FIXTURE_DIR): everything tlk would fetch or
+// read off the clock comes from DIR instead, so install / lock / verify runs are reproducible
+// byte for byte and a bug report can ship as a directory:
+//
+//   DIR/now                      RFC 3339 timestamp used as "now" (lock and report times)
+//   DIR/versions/<tool>.json     version list, the `tlk registry mirror` format
+//   DIR/downloads/<host>/<path>  body served for http(s)://<host>/<path> (":" in the host is "_")
+//
+// Any other request fails as in --offline, and the caches are bypassed so results depend only on
+// DIR.
+
+static DIR: OnceCell<PathBuf> = OnceCell::new();
+
+pub fn set_dir(dir: PathBuf) {
+    let _ = DIR.set(dir);
+}
+
+pub fn dir() -> Option<&'static Path> {
+    DIR.get().map(PathBuf::as_path)
+}
+
+static NOW: OnceCell<Option<DateTime<Utc>>> = OnceCell::new();
+
+/// The current time, or the fixture's `now` when one is set.
+pub fn now() -> DateTime<Utc> {
+    let fixed = NOW.get_or_init(|| {
+        let path = dir()?.join("now");
+        let text = std::fs::read_to_string(&path).ok()?;
+        match DateTime::parse_from_rfc3339(text.trim()) {
+            Ok(t) => Some(t.with_timezone(&Utc)),
+            Err(e) => {
+                eprintln!("Warning: ignoring {}: {e}", path.display());
+                None
+            }
+        }
+    });
+    fixed.unwrap_or_else(Utc::now)
+}
+
+/// The fixture file serving `url`, in fixture mode.
+pub fn download(url: &str) -> Option<Result<std::fs::File>> {
+    let dir = dir()?;
+    let path = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .replace(':', "_");
+    let path = dir.join("downloads").join(path.trim_start_matches('/'));
+    Some(std::fs::File::open(&path).with_context(|| {
+        format!(
+            "fixture mode: no download fixture for {url} (expected {})",
+            path.display()
+        )
+    }))
+}
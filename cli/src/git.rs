@@ -0,0 +1,128 @@
+use crate::lock::LockFile;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process::Command;
+
+// Keep tool bumps in their own commits: before tlk rewrites tlk.toml / tlk.lock, check whether the
+// git worktree already has other uncommitted changes (untracked files are ignored), and with
+// `--commit` commit just those two files afterwards. Outside a git repository, or without git
+// installed, nothing is checked.
+
+const DEFAULT_COMMIT_MESSAGE: &str = "chore(tools): bump {changes}";
+
+/// Tracked files with uncommitted changes in the worktree holding `dir`, other than tlk's own files.
+fn unrelated_changes(dir: &Path) -> Option<Vec<String>> {
+    let out = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .filter_map(|l| l.get(3..))
+            .filter(|path| {
+                let file = path.rsplit('/').next().unwrap_or(path);
+                !matches!(file.trim_matches('"'), "tlk.toml" | "tlk.lock")
+            })
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Ok when the worktree is clean (apart from tlk.toml / tlk.lock), `allow_dirty` is set, or the
+/// user confirms on a terminal; an error otherwise.
+pub fn ensure_clean(config_path: &str, allow_dirty: bool) -> Result<()> {
+    if allow_dirty {
+        return Ok(());
+    }
+    let dir = Path::new(config_path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let Some(changes) = unrelated_changes(dir).filter(|c| !c.is_empty()) else {
+        return Ok(());
+    };
+    eprintln!("The git worktree has uncommitted changes besides tlk.toml / tlk.lock:");
+    for path in changes.iter().take(10) {
+        eprintln!("  {path}");
+    }
+    if changes.len() > 10 {
+        eprintln!("  ... and {} more", changes.len() - 10);
+    }
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("refusing to rewrite {config_path} in a dirty worktree; commit or stash first, or pass --allow-dirty");
+    }
+    eprint!("Update {config_path} and tlk.lock anyway? [y/N] ");
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    if !matches!(line.trim(), "y" | "Y" | "yes") {
+        anyhow::bail!("aborted; commit or stash your changes first, or pass --allow-dirty");
+    }
+    Ok(())
+}
+
+/// Locked version of each tool, to diff against after an install or update.
+pub fn locked_versions(lock_path: &str) -> BTreeMap<String, String> {
+    LockFile::load(lock_path)
+        .ok()
+        .flatten()
+        .map(|l| l.tools.into_iter().map(|(k, e)| (k, e.version)).collect())
+        .unwrap_or_default()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .context("running git")?;
+    if !status.success() {
+        anyhow::bail!("git {} failed ({status})", args.join(" "));
+    }
+    Ok(())
+}
+
+/// Commit tlk.toml and tlk.lock (only those paths, whatever else is staged) with `template`
+/// (default "chore(tools): bump {changes}") filled from the lock versions before and after.
+pub fn commit_tool_changes(
+    config_path: &str,
+    lock_path: &str,
+    before: &BTreeMap<String, String>,
+    template: Option<&str>,
+) -> Result<()> {
+    let after = locked_versions(lock_path);
+    let changes: Vec<String> = after
+        .iter()
+        .filter(|(name, version)| before.get(*name) != Some(version))
+        .map(|(name, version)| match before.get(name) {
+            Some(old) => format!("{name} {old} -> {version}"),
+            None => format!("{name} {version}"),
+        })
+        .collect();
+    if changes.is_empty() {
+        println!("No tool versions changed; nothing to commit");
+        return Ok(());
+    }
+    let message = template
+        .unwrap_or(DEFAULT_COMMIT_MESSAGE)
+        .replace("{changes}", &changes.join(", "));
+    let paths: Vec<&str> = [config_path, lock_path]
+        .into_iter()
+        .filter(|p| Path::new(p).exists())
+        .collect();
+    let dir = Path::new(".");
+    git(dir, &[&["add", "--"][..], &paths].concat())?;
+    git(
+        dir,
+        &[&["commit", "-q", "-m", &message, "--"][..], &paths].concat(),
+    )?;
+    println!("Committed: {message}");
+    Ok(())
+}
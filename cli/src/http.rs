@@ -0,0 +1,487 @@
+use crate::config::{NetworkSettings, TlsSettings};
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::OnceCell;
+use reqwest::blocking::{Client, ClientBuilder, Request, RequestBuilder, Response};
+use reqwest::header::{
+    AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION,
+    PROXY_AUTHORIZATION, TRANSFER_ENCODING,
+};
+use reqwest::tls::Version;
+use reqwest::Certificate;
+use reqwest::{redirect, Method, NoProxy, Proxy, StatusCode};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// TLS policy shared by every outbound request: TLS 1.2 or newer (`[settings.tls] min_version`
+// raises it to 1.3), optional per-host certificate pins, and `--insecure` for lab networks behind
+// intercepting proxies. A pin is the hex sha256 of the server certificate's SubjectPublicKeyInfo.
+// Pinned hosts are reached through a separate rustls client whose verifier checks the leaf's key
+// during the handshake, so nothing (headers, tokens) is sent to a peer that does not hold a pinned
+// key; for those hosts the pin replaces CA validation and is enforced even with --insecure. While
+// any pin is configured, redirects are followed here hop by hop so each hop goes through the
+// client matching its host.
+//
+// GET / HEAD requests are retried on transient failures (connection errors, timeouts, 5xx) with
+// exponential backoff and jitter, `[settings] http_attempts` times in total.
+
+const DEFAULT_ATTEMPTS: u32 = 3;
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+const BACKOFF_MAX: Duration = Duration::from_secs(8);
+const MAX_REDIRECTS: usize = 10;
+
+struct Policy {
+    tls: TlsSettings,
+    insecure: bool,
+    attempts: u32,
+    network: NetworkSettings,
+}
+
+static POLICY: OnceCell<Policy> = OnceCell::new();
+
+// --offline / TLK_OFFLINE: every request fails before it is sent, so nothing can reach the network
+// even from code paths that do not consult the cache policy.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_offline() {
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+pub fn offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+pub fn set_policy(
+    tls: TlsSettings,
+    insecure: bool,
+    attempts: Option<u32>,
+    network: NetworkSettings,
+) {
+    let insecure = insecure || network.insecure_skip_verify;
+    if insecure {
+        eprintln!(
+            "WARNING: TLS certificate verification is disabled (--insecure / [network] \
+             insecure_skip_verify); downloads can be intercepted or tampered with. Use only on \
+             trusted lab networks, and prefer [network] ca_bundle for intercepting proxies."
+        );
+    }
+    let _ = POLICY.set(Policy {
+        tls,
+        insecure,
+        attempts: attempts.unwrap_or(DEFAULT_ATTEMPTS).max(1),
+        network,
+    });
+}
+
+/// Client builder with the TLS and proxy policy applied.
+pub fn builder() -> Result<ClientBuilder> {
+    let mut builder = Client::builder().min_tls_version(Version::TLS_1_2);
+    let Some(policy) = POLICY.get() else {
+        return Ok(builder);
+    };
+    if tls13_only(policy)? {
+        builder = builder.min_tls_version(Version::TLS_1_3);
+    }
+    if pinning() {
+        builder = builder.redirect(redirect::Policy::none());
+    }
+    if policy.insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    // Added to the system roots, so public hosts keep working next to the corporate CA.
+    if let Some(path) = &policy.network.ca_bundle {
+        let pem =
+            std::fs::read(path).with_context(|| format!("reading [network] ca_bundle {path}"))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("parsing [network] ca_bundle {path}"))?;
+        if certs.is_empty() {
+            return Err(anyhow!(
+                "[network] ca_bundle {path} contains no PEM certificates"
+            ));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    // reqwest already honours the proxy environment variables; [network] proxy takes precedence.
+    if let Some(url) = &policy.network.proxy {
+        let scheme = url.split_once("://").map_or("", |(s, _)| s);
+        if !matches!(
+            scheme,
+            "http" | "https" | "socks4" | "socks4a" | "socks5" | "socks5h"
+        ) {
+            return Err(anyhow!(
+                "unsupported [network] proxy '{}' (use http://, https://, socks5:// or socks5h://)",
+                redact(url)
+            ));
+        }
+        let no_proxy = match &policy.network.no_proxy {
+            Some(list) => NoProxy::from_string(list),
+            None => NoProxy::from_env(),
+        };
+        let proxy = Proxy::all(url)
+            .with_context(|| format!("invalid [network] proxy '{}'", redact(url)))?
+            .no_proxy(no_proxy);
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+fn tls13_only(policy: &Policy) -> Result<bool> {
+    match policy.tls.min_version.as_deref() {
+        None | Some("1.2") => Ok(false),
+        Some("1.3") => Ok(true),
+        Some(other) => Err(anyhow!(
+            "unsupported [settings.tls] min_version '{other}' (use \"1.2\" or \"1.3\")"
+        )),
+    }
+}
+
+static CLIENT: OnceCell<Client> = OnceCell::new();
+
+/// The process-wide client. Clones share one connection pool (and TLS sessions), so parallel
+/// installs from the same host reuse connections instead of each opening their own.
+pub fn client() -> Result<Client> {
+    CLIENT
+        .get_or_try_init(|| builder()?.build().context("building HTTP client"))
+        .cloned()
+}
+
+/// Send `req` and enforce certificate pins for its host. Idempotent requests are retried on
+/// transient failures; a 5xx that persists is returned as a [`ServerError`].
+pub fn send(req: RequestBuilder) -> Result<Response> {
+    let request = req.try_clone().and_then(|r| r.build().ok());
+    let Some(request) = request.filter(|r| matches!(*r.method(), Method::GET | Method::HEAD))
+    else {
+        return send_once(req);
+    };
+    let url = request.url().to_string();
+    retrying(&url, || {
+        let resp = send_once(req.try_clone().expect("request cloned above"))?;
+        if resp.status().is_server_error() {
+            return Err(ServerError {
+                url: url.clone(),
+                status: resp.status(),
+            }
+            .into());
+        }
+        Ok(resp)
+    })
+}
+
+/// Send `req` once (no retries), for callers that retry a larger operation such as a download
+/// including its body.
+pub fn send_once(req: RequestBuilder) -> Result<Response> {
+    if offline() {
+        let url = req
+            .try_clone()
+            .and_then(|r| r.build().ok())
+            .map(|r| redact(r.url().as_str()))
+            .unwrap_or_default();
+        anyhow::bail!("offline mode (--offline / TLK_OFFLINE): not fetching {url}");
+    }
+    if crate::fixture::dir().is_some() {
+        let url = req
+            .try_clone()
+            .and_then(|r| r.build().ok())
+            .map(|r| redact(r.url().as_str()))
+            .unwrap_or_default();
+        anyhow::bail!("fixture mode: not fetching {url}");
+    }
+    if !pinning() {
+        return Ok(req.send()?);
+    }
+    let (client, request) = req.build_split();
+    follow(&client, request?)
+}
+
+// Send `request` and the redirects it leads to, each hop to a pinned host through the pinning
+// client. Mirrors reqwest's own redirect handling: 301/302/303 turn into a bodiless GET (HEAD stays
+// HEAD), 307/308 replay the request, and credentials are dropped when the host changes.
+fn follow(client: &Client, mut request: Request) -> Result<Response> {
+    for _ in 0..=MAX_REDIRECTS {
+        let host = request.url().host_str().unwrap_or_default().to_string();
+        let replay = request.try_clone();
+        let resp = if pins_for(&host).is_some() {
+            if request.url().scheme() != "https" {
+                anyhow::bail!(
+                    "{host} is pinned; refusing to fetch {} without TLS",
+                    redact(request.url().as_str())
+                );
+            }
+            pin_client()?.execute(request)?
+        } else {
+            client.execute(request)?
+        };
+        let status = resp.status();
+        if !matches!(status.as_u16(), 301..=303 | 307 | 308) {
+            return Ok(resp);
+        }
+        let (Some(mut next), Some(location)) = (
+            replay,
+            resp.headers().get(LOCATION).and_then(|l| l.to_str().ok()),
+        ) else {
+            return Ok(resp);
+        };
+        let url = resp
+            .url()
+            .join(location)
+            .with_context(|| format!("invalid redirect from {}", redact(resp.url().as_str())))?;
+        if matches!(status.as_u16(), 301..=303) {
+            if *next.method() != Method::HEAD {
+                *next.method_mut() = Method::GET;
+            }
+            *next.body_mut() = None;
+            for header in [
+                TRANSFER_ENCODING,
+                CONTENT_TYPE,
+                CONTENT_ENCODING,
+                CONTENT_LENGTH,
+            ] {
+                next.headers_mut().remove(header);
+            }
+        }
+        if url.host_str() != Some(host.as_str()) {
+            for header in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+                next.headers_mut().remove(header);
+            }
+        }
+        *next.url_mut() = url;
+        request = next;
+    }
+    anyhow::bail!("too many redirects (more than {MAX_REDIRECTS})")
+}
+
+/// A 5xx response, which is worth retrying.
+#[derive(Debug)]
+pub struct ServerError {
+    pub url: String,
+    pub status: StatusCode,
+}
+
+impl std::fmt::Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} returned {}", self.url, self.status)
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+/// Run `op` until it succeeds, fails with a non-transient error, or runs out of attempts.
+pub fn retrying<T>(what: &str, mut op: impl FnMut() -> Result<T>) -> Result<T> {
+    let attempts = POLICY.get().map_or(DEFAULT_ATTEMPTS, |p| p.attempts);
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if attempt < attempts && is_transient(&e) => {
+                let delay = backoff(attempt);
+                eprintln!(
+                    "Warning: {what} failed ({e:#}); retrying in {:.1}s (attempt {}/{attempts})",
+                    delay.as_secs_f64(),
+                    attempt + 1
+                );
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            Err(e) if attempt > 1 => return Err(e.context(format!("after {attempt} attempts"))),
+            result => return result,
+        }
+    }
+}
+
+fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if cause.is::<ServerError>() {
+            return true;
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_connect() || e.is_timeout() || e.is_request() || e.is_body();
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind::*;
+            return matches!(
+                e.kind(),
+                ConnectionReset | ConnectionAborted | TimedOut | UnexpectedEof | BrokenPipe
+            );
+        }
+        false
+    })
+}
+
+// Doubling delay capped at BACKOFF_MAX, plus up to half of it again as jitter so parallel
+// installs do not retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BACKOFF_BASE
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(BACKOFF_MAX);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    delay + delay.mul_f64(f64::from(nanos % 1000) / 2000.0)
+}
+
+fn pinning() -> bool {
+    POLICY
+        .get()
+        .is_some_and(|p| p.tls.hosts.values().any(|h| !h.pin_sha256.is_empty()))
+}
+
+fn pins_for(host: &str) -> Option<&'static [String]> {
+    POLICY
+        .get()?
+        .tls
+        .hosts
+        .get(host)
+        .map(|h| h.pin_sha256.as_slice())
+        .filter(|p| !p.is_empty())
+}
+
+static PIN_CLIENT: OnceCell<Client> = OnceCell::new();
+
+// Client for pinned hosts: the proxy and timeout policy of `builder()`, with TLS replaced by a
+// rustls configuration that only accepts pinned keys.
+fn pin_client() -> Result<Client> {
+    PIN_CLIENT
+        .get_or_try_init(|| {
+            let policy = POLICY.get().context("no TLS policy")?;
+            let provider = Arc::new(rustls::crypto::ring::default_provider());
+            let versions: &[&rustls::SupportedProtocolVersion] = if tls13_only(policy)? {
+                &[&rustls::version::TLS13]
+            } else {
+                &[&rustls::version::TLS12, &rustls::version::TLS13]
+            };
+            let tls = rustls::ClientConfig::builder_with_provider(provider.clone())
+                .with_protocol_versions(versions)
+                .context("configuring TLS for pinned hosts")?
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinVerifier { provider }))
+                .with_no_client_auth();
+            builder()?
+                .use_preconfigured_tls(tls)
+                .build()
+                .context("building HTTP client for pinned hosts")
+        })
+        .cloned()
+}
+
+// Accepts a server only when its leaf certificate carries a pinned key for the requested name. The
+// handshake signature is still verified against that key, so only its holder gets through.
+#[derive(Debug)]
+struct PinVerifier {
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = server_name.to_str();
+        let pins = pins_for(&host)
+            .ok_or_else(|| rustls::Error::General(format!("{host} has no certificate pins")))?;
+        let spki = spki_sha256(end_entity).ok_or_else(|| {
+            rustls::Error::General(format!(
+                "cannot read the public key of {host}'s certificate"
+            ))
+        })?;
+        if !pins.iter().any(|p| p.eq_ignore_ascii_case(&spki)) {
+            return Err(rustls::Error::General(format!(
+                "certificate pin mismatch for {host}: public key sha256 {spki} is not pinned"
+            )));
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+// sha256 of the DER SubjectPublicKeyInfo inside an X.509 certificate:
+// Certificate ::= SEQUENCE { tbsCertificate SEQUENCE { [0] version OPTIONAL, serialNumber,
+// signature, issuer, validity, subject, subjectPublicKeyInfo, ... }, ... }
+fn spki_sha256(cert: &[u8]) -> Option<String> {
+    let (_, cert_body, _) = der_element(cert)?;
+    let (_, tbs, _) = der_element(cert_body)?;
+    let mut rest = tbs;
+    if rest.first() == Some(&0xa0) {
+        rest = der_element(rest)?.2;
+    }
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        rest = der_element(rest)?.2;
+    }
+    let (spki, _, _) = der_element(rest)?;
+    Some(hex::encode(Sha256::digest(spki)))
+}
+
+// (whole element, contents, remainder) of the DER element at the start of `buf`.
+fn der_element(buf: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let first = *buf.get(1)?;
+    let (len, header) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 {
+            return None;
+        }
+        let len = buf
+            .get(2..2 + n)?
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, 2 + n)
+    };
+    let end = header.checked_add(len)?;
+    if end > buf.len() {
+        return None;
+    }
+    Some((&buf[..end], &buf[header..end], &buf[end..]))
+}
+
+// Proxy URLs may carry credentials; keep them out of error messages.
+pub fn redact(url: &str) -> String {
+    match (url.split_once("://"), url.rfind('@')) {
+        (Some((scheme, _)), Some(at)) => format!("{scheme}://***@{}", &url[at + 1..]),
+        _ => url.to_string(),
+    }
+}
@@ -1,50 +1,120 @@
-use crate::config::{TlkConfig, Tool, ToolKind};
-use crate::known_tools::{placeholder_arch, placeholder_os};
+use crate::cache;
+use crate::checksum;
+use crate::config::{Checksum, Extract, TlkConfig, Tool, ToolKind, Via};
+use crate::http;
+use crate::known_tools::{self, placeholder_arch, placeholder_os};
 use crate::lock::{to_locked_entry, LockFile};
+use crate::notify;
 use crate::platform::platform;
+use crate::probe;
+use crate::progress::{self, Outcome, ProgressReader, ProgressReporter, Stage};
+use crate::receipt::{self, Written};
+use crate::throttle;
+use crate::tmp::{self, TempDir};
+use crate::unpack;
 use anyhow::{anyhow, Context, Result};
 use flate2::read::GzDecoder;
 use fs_err as fs;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use once_cell::sync::{Lazy, OnceCell};
 use reqwest::blocking::Client;
-use semver::Version;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tar::Archive;
 use zip::ZipArchive;
 
-pub fn plan(cfg: Option<&TlkConfig>) -> Result<()> {
-    if let Some(cfg) = cfg {
-        for t in &cfg.tools {
-            println!("{} {} -> {}", t.name, t.version, t.source);
-        }
+pub fn print_skipped(cfg: &TlkConfig) {
+    for t in &cfg.skipped {
+        println!(
+            "{} {} skipped (when = \"{}\")",
+            t.name,
+            t.version,
+            t.when.as_deref().unwrap_or_default()
+        );
     }
-    Ok(())
 }
 
-pub fn list(cfg: Option<&TlkConfig>) -> Result<()> {
+pub fn list(cfg: Option<&TlkConfig>, long: bool) -> Result<()> {
     if let Some(cfg) = cfg {
         for t in &cfg.tools {
             let installed =
                 find_installed_version(t).unwrap_or_else(|_| "<not installed>".to_string());
             println!("{} desired={} installed={}", t.name, t.version, installed);
+            if let Some(meta) = long.then(|| known_tools::metadata(&t.name)).flatten() {
+                println!("    {}", meta.description);
+                println!("    {} ({})", meta.homepage, meta.license);
+            }
         }
+        print_skipped(cfg);
     }
     Ok(())
 }
 
-pub fn install_all(cfg: Option<&TlkConfig>) -> Result<()> {
+static JOBS: AtomicUsize = AtomicUsize::new(DEFAULT_JOBS);
+const DEFAULT_JOBS: usize = 8;
+
+/// Cap on concurrent tool installs (`--jobs` / `[settings] jobs`, default 8).
+pub fn set_jobs(jobs: Option<usize>) {
+    JOBS.store(jobs.unwrap_or(DEFAULT_JOBS).max(1), Ordering::SeqCst);
+}
+
+fn jobs() -> usize {
+    JOBS.load(Ordering::SeqCst)
+}
+
+// [mirrors] prefixes, longest first so the most specific mirror wins. Lock entries keep the
+// upstream URL; the mirror is substituted only when a request is made.
+static MIRRORS: OnceCell<Vec<(String, String)>> = OnceCell::new();
+
+pub fn set_mirrors(mirrors: &BTreeMap<String, String>) {
+    let mut list: Vec<(String, String)> = mirrors
+        .iter()
+        .map(|(from, to)| {
+            (
+                from.trim_end_matches('/').to_string(),
+                to.trim_end_matches('/').to_string(),
+            )
+        })
+        .collect();
+    list.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+    let _ = MIRRORS.set(list);
+}
+
+/// `url` with its upstream prefix replaced by the configured mirror, if any. Prefixes match
+/// whole path segments: "https://example.com/a" does not rewrite "https://example.com/ab".
+pub fn mirror_url(url: &str) -> String {
+    for (from, to) in MIRRORS.get().into_iter().flatten() {
+        if let Some(rest) = url.strip_prefix(from.as_str()) {
+            if rest.is_empty() || rest.starts_with(['/', '?']) {
+                return format!("{to}{rest}");
+            }
+        }
+    }
+    url.to_string()
+}
+
+/// Per-run knobs shared by every install entry point.
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Reinstall even when the installed version already matches (recovers corrupted binaries)
+    pub force: bool,
+}
+
+pub fn install_all(cfg: Option<&TlkConfig>, opts: &InstallOptions) -> Result<()> {
     let Some(cfg) = cfg else {
         return Ok(());
     };
     // Use parallel strategy for speed; fall back to sequential if only one
 
     if cfg.tools.len() <= 1 {
-        return install_all_sequential(cfg);
+        return install_all_sequential(cfg, opts);
     }
-    let results = install_tools_parallel(&cfg.tools);
+    let results = install_tools_parallel(&cfg.tools, opts);
     let out = summarize_parallel(results);
     if out.is_ok() {
         refresh_path();
@@ -52,67 +122,86 @@ pub fn install_all(cfg: Option<&TlkConfig>) -> Result<()> {
     out
 }
 
-fn install_all_sequential(cfg: &TlkConfig) -> Result<()> {
-    let m = MultiProgress::new();
-    let client = Client::new();
-    for t in &cfg.tools {
-        let pb = m.add(ProgressBar::new_spinner());
-        pb.set_style(ProgressStyle::with_template("{spinner} {msg}").unwrap());
-        pb.set_message(format!("Installing {} {}", t.name, t.version));
-        if let Err(e) = install_tool(&client, t, Some(&pb)) {
-            pb.finish_with_message(format!("{} FAILED: {e}", t.name));
-        } else {
-            pb.finish_with_message(format!("{} OK", t.name));
-        }
-    }
+fn install_all_sequential(cfg: &TlkConfig, opts: &InstallOptions) -> Result<()> {
+    let reporter = progress::for_mode(&run_list(&cfg.tools));
+    let client = http::client()?;
+    let results = cfg
+        .tools
+        .iter()
+        .map(|t| {
+            (
+                t.name.clone(),
+                install_tool(&client, t, reporter.as_ref(), opts),
+            )
+        })
+        .collect();
     refresh_path();
-    Ok(())
+    summarize_parallel(results)
 }
 
-pub fn install_tools_parallel(tools: &[Tool]) -> Vec<(String, Result<()>)> {
+pub fn install_tools_parallel(tools: &[Tool], opts: &InstallOptions) -> Vec<(String, Result<()>)> {
+    install_tools_with(tools, opts, progress::for_mode(&run_list(tools)))
+}
+
+/// Install `tools` concurrently, reporting progress to `reporter`.
+pub fn install_tools_with(
+    tools: &[Tool],
+    opts: &InstallOptions,
+    reporter: Arc<dyn ProgressReporter>,
+) -> Vec<(String, Result<()>)> {
     use std::thread;
     use std::time::Duration;
-    let m = MultiProgress::new();
-    let style = ProgressStyle::with_template("{spinner} {msg}").unwrap();
-    let (tx, rx) = std::sync::mpsc::channel();
-    for tool in tools {
-        let pb = m.add(ProgressBar::new_spinner());
-        pb.set_style(style.clone());
-        pb.set_message(format!("Starting {} {}", tool.name, tool.version));
-        pb.enable_steady_tick(Duration::from_millis(120));
-        let tool_clone = tool.clone();
-        let txc = tx.clone();
-        thread::spawn(move || {
-            let client = Client::new();
-            pb.set_message(format!(
-                "Downloading {} {}",
-                tool_clone.name, tool_clone.version
-            ));
-            let res = install_tool(&client, &tool_clone, Some(&pb));
-            match &res {
-                Ok(_) => pb.finish_with_message(format!(
-                    "Installed {} {}",
-                    tool_clone.name, tool_clone.version
-                )),
-                Err(e) => pb.finish_with_message(format!(
-                    "FAILED {} {}: {e}",
-                    tool_clone.name, tool_clone.version
-                )),
-            }
-            let _ = txc.send((tool_clone.name.clone(), res));
-        });
-    }
-    drop(tx); // close sending side when workers exit
-              // Collect all results; channel closes when all worker threads done
-    let mut results = Vec::with_capacity(tools.len());
-    for msg in rx.iter() {
-        results.push(msg);
+    // Corepack-backed tools run node's corepack, so they are set up once node's toolchain is in
+    // place.
+    let (corepack, tools): (Vec<&Tool>, Vec<&Tool>) =
+        tools.iter().partition(|t| t.via == Some(Via::Corepack));
+    // One client for every worker: its connection pool is what makes many GitHub downloads cheap.
+    let client = match http::client() {
+        Ok(client) => client,
+        Err(e) => {
+            let msg = format!("{e:#}");
+            return tools
+                .iter()
+                .chain(&corepack)
+                .map(|t| (t.name.clone(), Err(anyhow!(msg.clone()))))
+                .collect();
+        }
+    };
+    // A bounded pool: each worker takes the next pending tool until none are left.
+    let next = AtomicUsize::new(0);
+    let workers = jobs().min(tools.len());
+    let mut results = Vec::with_capacity(tools.len() + corepack.len());
+    thread::scope(|scope| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (next, tools, reporter, client) = (&next, &tools, &reporter, &client);
+            scope.spawn(move || {
+                while let Some(tool) = tools.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    let res = install_tool(client, tool, reporter.as_ref(), opts);
+                    let _ = tx.send((tool.name.clone(), res));
+                }
+            });
+        }
+        drop(tx); // the channel closes once every worker is done
+        results.extend(rx.iter());
+    });
+    for tool in corepack {
+        let res = install_tool(&client, tool, reporter.as_ref(), opts);
+        results.push((tool.name.clone(), res));
     }
     // Give bars a moment to flush final lines
     std::thread::sleep(Duration::from_millis(20));
     results
 }
 
+fn run_list(tools: &[Tool]) -> Vec<(String, String)> {
+    tools
+        .iter()
+        .map(|t| (t.name.clone(), t.version.clone()))
+        .collect()
+}
+
 fn summarize_parallel(results: Vec<(String, Result<()>)>) -> Result<()> {
     let mut failures = Vec::new();
     for (name, res) in results {
@@ -121,15 +210,25 @@ fn summarize_parallel(results: Vec<(String, Result<()>)>) -> Result<()> {
         }
     }
     if failures.is_empty() {
-        Ok(())
-    } else {
-        Err(anyhow!(format!("{} tool(s) failed", failures.len())))
+        return Ok(());
     }
+    // Progress bars are hidden when stderr is not a terminal; the error carries the causes.
+    let details: Vec<String> = failures
+        .iter()
+        .map(|(name, e)| format!("  {name}: {e:#}"))
+        .collect();
+    Err(anyhow!(
+        "{} tool(s) failed:\n{}",
+        failures.len(),
+        details.join("\n")
+    ))
 }
 
-pub fn install_single(tool: &Tool) -> Result<()> {
-    let client = Client::new();
-    let res = install_tool(&client, tool, None);
+pub fn install_single(tool: &Tool, opts: &InstallOptions) -> Result<()> {
+    let _project = crate::lock::lock_project("tlk.lock", &format!("install {}", tool.name))?;
+    let client = http::client()?;
+    let reporter = progress::for_mode(&run_list(std::slice::from_ref(tool)));
+    let res = install_tool(&client, tool, reporter.as_ref(), opts);
     if res.is_ok() {
         refresh_path();
     }
@@ -137,6 +236,8 @@ pub fn install_single(tool: &Tool) -> Result<()> {
 }
 // Attempt to mimic the hook's PATH adjustment once after installs so newly installed binaries are immediately usable when user did not yet eval the hook.
 pub fn refresh_path() {
+    mark_installed();
+    let _ = SHELL_PATH.set(std::env::var_os("PATH"));
     // Skip if disabled
     if std::env::var("TLK_NO_AUTO_PATH").is_ok() {
         return;
@@ -144,7 +245,7 @@ pub fn refresh_path() {
     if let Ok(mut dir) = std::env::current_dir() {
         loop {
             if dir.join("tlk.toml").exists() {
-                let bindir = dir.join(".tlk/bin");
+                let bindir = crate::config::project_bin_dir(&dir);
                 if bindir.is_dir() {
                     let bindir_s = bindir.to_string_lossy().to_string();
                     let path = std::env::var("PATH").unwrap_or_default();
@@ -165,16 +266,39 @@ pub fn refresh_path() {
     }
 }
 
+// PATH as the invoking shell has it, before refresh_path puts the project bin dir first.
+static SHELL_PATH: OnceCell<Option<std::ffi::OsString>> = OnceCell::new();
+
+/// The PATH the user's shell resolves commands with.
+pub fn shell_path() -> Option<std::ffi::OsString> {
+    SHELL_PATH
+        .get()
+        .cloned()
+        .unwrap_or_else(|| std::env::var_os("PATH"))
+}
+
+/// Bump ~/.tlk/install-stamp. The POSIX hook compares it on every prompt and runs `hash -r` when
+/// it changed, so shells drop command lookups cached before a tool was installed or removed.
+pub fn mark_installed() {
+    let Some(home) = platform().home_dir() else {
+        return;
+    };
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let dir = home.join(".tlk");
+    // Best effort: a read-only home only costs the rehash.
+    let _ = fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(dir.join("install-stamp"), format!("{stamp}\n")));
+}
+
 // Expand placeholders into a concrete URL for locking and downloading
 pub fn render_source(tool: &Tool) -> String {
     let os = placeholder_os();
     let arch = placeholder_arch();
-    let template = tool.effective_source_template(os, arch);
     let (exact, _req) = normalize_version(&tool.version);
-    let base = template
-        .replace("{version}", &exact)
-        .replace("{os}", os)
-        .replace("{arch}", arch);
+    let base = tool.source_for(&exact, os, arch);
     platform().adjust_direct_url(&base)
 }
 
@@ -184,28 +308,40 @@ pub fn write_lockfile(cfg: Option<&TlkConfig>, path: &str) -> Result<()> {
             "No config provided for lockfile generation"
         ));
     };
-    use std::collections::HashMap;
+    let _guard = crate::lock::lock_for_write(path)?;
+    let previous = LockFile::load(path).ok().flatten();
     let mut map: HashMap<String, crate::lock::LockedEntry> = HashMap::new();
+    // Tools skipped by `when` here may be installed elsewhere; keep what the lock says for them.
+    if let Some(prev) = &previous {
+        for t in &cfg.skipped {
+            if let Some(entry) = prev.tools.get(&t.name) {
+                map.insert(t.name.clone(), entry.clone());
+            }
+        }
+    }
+    let mut digests = installed_digests(&cfg.tools);
     for t in &cfg.tools {
-        let digest = compute_installed_digest(t).ok();
+        let digest = digests.remove(&t.name).flatten();
         let tpl = t.effective_source_template(placeholder_os(), placeholder_arch());
         // Determine exact version (strip range operators if any)
         let (exact, requested) = normalize_version(&t.version);
         let rendered = render_source(t).replace(&t.version, &exact); // ensure rendered uses exact if different
-        let (name, entry) = to_locked_entry(
-            &t.name,
-            &exact,
-            requested.as_deref(),
-            &rendered,
-            &tpl,
-            &t.sha256,
-            digest,
-        );
+        let (name, mut entry) =
+            to_locked_entry(t, &exact, requested.as_deref(), &rendered, &tpl, digest);
+        let prev = previous.as_ref().and_then(|p| p.tools.get(&name));
+        entry.tofu_sha256 = crate::tofu::pins(t, &exact, prev);
+        entry.carry_served_from(prev);
         map.insert(name, entry);
     }
     let lf = LockFile::new(map);
     lf.save(path)?;
     println!("Wrote lockfile {path}");
+    cache::archive(lf.tools.iter().filter_map(|(name, entry)| {
+        let prev = previous.as_ref().and_then(|p| p.tools.get(name));
+        (prev.map(|p| &p.version) != Some(&entry.version)).then_some(name.as_str())
+    }));
+    let changes = notify::lock_changes(previous.as_ref(), &lf);
+    notify::emit(cfg, notify::Event::LockUpdated(changes));
     Ok(())
 }
 
@@ -217,69 +353,177 @@ pub fn verify_lockfile(cfg: Option<&TlkConfig>, path: &str) -> Result<()> {
         println!("No {path} present; run 'tlk install' to create it.");
         return Ok(());
     };
+    crate::lock::check_lock_hash(path)?;
+    print_skipped(cfg);
+    for name in lock.tools.keys() {
+        let declared = cfg
+            .tools
+            .iter()
+            .chain(&cfg.skipped)
+            .any(|t| &t.name == name);
+        if !declared {
+            println!(
+                "Warning: lock contains extra tool '{name}' not in config (run 'tlk lock prune')"
+            );
+        }
+    }
+    let errors: Vec<String> = lock_drift(cfg, &lock)
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+    if errors.is_empty() {
+        println!("Lock verification passed");
+        Ok(())
+    } else {
+        notify::emit(cfg, notify::Event::VerifyFailed(errors.clone()));
+        Err(anyhow::anyhow!(format!(
+            "lock verification failed:\n - {}",
+            errors.join("\n - ")
+        )))
+    }
+}
+
+/// One verification problem. `fixable` problems concern only the installed binary and can be
+/// repaired by reinstalling from the locked source; the rest need config or lock changes.
+#[derive(Debug, Clone)]
+pub struct Drift {
+    pub tool: String,
+    pub message: String,
+    pub fixable: bool,
+}
+
+impl Drift {
+    fn config(tool: &str, message: String) -> Self {
+        Self {
+            tool: tool.to_string(),
+            message,
+            fixable: false,
+        }
+    }
+}
+
+impl std::fmt::Display for Drift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Differences between config, lock and installed binaries (empty when in sync).
+pub fn lock_drift(cfg: &TlkConfig, lock: &LockFile) -> Vec<Drift> {
     let mut errors = Vec::new();
+    let digests = installed_digests(&cfg.tools);
     for t in &cfg.tools {
         match lock.tools.get(&t.name) {
-            None => errors.push(format!("tool '{}' missing from lock", t.name)),
+            None => errors.push(Drift::config(
+                &t.name,
+                format!("tool '{}' missing from lock", t.name),
+            )),
             Some(lt) => {
                 // Determine if config version is a range; locked version must satisfy it
                 if is_range(&t.version) {
                     if !range_satisfies(&t.version, &lt.version) {
-                        errors.push(format!(
-                            "tool '{}' locked version {} does not satisfy range {}",
-                            t.name, lt.version, t.version
+                        errors.push(Drift::config(
+                            &t.name,
+                            format!(
+                                "tool '{}' locked version {} does not satisfy range {}",
+                                t.name, lt.version, t.version
+                            ),
                         ));
                     }
                 } else if lt.version != t.version {
-                    errors.push(format!(
-                        "tool '{}' version mismatch lock={} config={}",
-                        t.name, lt.version, t.version
+                    errors.push(Drift::config(
+                        &t.name,
+                        format!(
+                            "tool '{}' version mismatch lock={} config={}",
+                            t.name, lt.version, t.version
+                        ),
                     ));
                 }
                 // Template/source check
                 if let Some(tpl) = &lt.source_template {
-                    let expected = tpl
-                        .replace("{version}", &lt.version)
-                        .replace("{os}", placeholder_os())
-                        .replace("{arch}", placeholder_arch());
+                    let expected =
+                        t.render_template(tpl, &lt.version, placeholder_os(), placeholder_arch());
                     let rendered = render_source(t).replace(&t.version, &lt.version);
                     if expected != rendered {
-                        errors.push(format!("tool '{}' source mismatch", t.name));
+                        errors.push(Drift::config(
+                            &t.name,
+                            format!("tool '{}' source mismatch", t.name),
+                        ));
                     }
                 }
-                if let (Some(cfg_sum), Some(lock_sum)) = (&t.sha256, &lt.sha256) {
-                    if cfg_sum != lock_sum {
-                        errors.push(format!("tool '{}' checksum mismatch", t.name));
+                let checksum_drift = match &t.sha256 {
+                    Some(Checksum::Single(sum)) => lt.sha256.as_ref().is_some_and(|l| l != sum),
+                    Some(Checksum::PerPlatform(map)) => {
+                        lt.sha256_by_platform.as_ref().is_some_and(|l| l != map)
                     }
+                    None => false,
+                };
+                if checksum_drift {
+                    errors.push(Drift::config(
+                        &t.name,
+                        format!("tool '{}' checksum mismatch", t.name),
+                    ));
                 }
-                if let Some(expected_digest) = &lt.digest {
-                    if let Ok(actual) = compute_installed_digest(t) {
-                        if actual != *expected_digest {
-                            errors.push(format!("tool '{}' digest mismatch", t.name));
-                        }
-                    }
+                if let Some(message) = binary_drift(t, lt, digests.get(&t.name)) {
+                    errors.push(Drift {
+                        tool: t.name.clone(),
+                        message,
+                        fixable: true,
+                    });
                 }
             }
         }
     }
-    for name in lock.tools.keys() {
-        if !cfg.tools.iter().any(|t| &t.name == name) {
-            println!("Warning: lock contains extra tool '{name}' not in config");
-        }
+    errors
+}
+
+// How the installed binary of `tool` differs from its lock entry, if it does: missing, another
+// version (when the binary reports one), or different bytes. Probing only runs for a binary that
+// already failed the digest check, or for corepack shims, which name their version.
+fn binary_drift(
+    tool: &Tool,
+    locked: &crate::lock::LockedEntry,
+    installed: Option<&Option<String>>,
+) -> Option<String> {
+    let name = &tool.name;
+    let other_version = || {
+        find_installed_version(tool).ok().filter(|v| {
+            v != "unprobed" && v.trim_start_matches('v') != locked.version.trim_start_matches('v')
+        })
+    };
+    if tool.via == Some(Via::Corepack) {
+        return match find_installed_version(tool) {
+            Err(_) => Some(format!(
+                "tool '{name}' is locked at {} but not installed",
+                locked.version
+            )),
+            Ok(_) => other_version().map(|v| {
+                format!(
+                    "tool '{name}' installed version {v} differs from locked {}",
+                    locked.version
+                )
+            }),
+        };
     }
-    if errors.is_empty() {
-        println!("Lock verification passed");
-        Ok(())
-    } else {
-        Err(anyhow::anyhow!(format!(
-            "lock verification failed:\n - {}",
-            errors.join("\n - ")
-        )))
+    let expected = locked.digest.as_ref()?;
+    match installed? {
+        None => Some(format!(
+            "tool '{name}' is locked at {} but not installed",
+            locked.version
+        )),
+        Some(actual) if actual != expected => Some(match other_version() {
+            Some(v) => format!(
+                "tool '{name}' installed version {v} differs from locked {}",
+                locked.version
+            ),
+            None => format!("tool '{name}' digest mismatch"),
+        }),
+        Some(_) => None,
     }
 }
 
 // --- Range helpers (kept local to avoid circular dep on main) ---
-fn is_range(spec: &str) -> bool {
+pub fn is_range(spec: &str) -> bool {
     if semver::Version::parse(spec.trim()).is_ok() {
         return false;
     }
@@ -289,7 +533,7 @@ fn is_range(spec: &str) -> bool {
         .any(|t| s.contains(t))
 }
 
-fn normalize_version(spec: &str) -> (String, Option<String>) {
+pub fn normalize_version(spec: &str) -> (String, Option<String>) {
     // If spec parses exactly as semver -> already exact
     if semver::Version::parse(spec.trim()).is_ok() {
         return (spec.trim().to_string(), None);
@@ -343,148 +587,1150 @@ fn range_satisfies(range: &str, version: &str) -> bool {
     false
 }
 
-fn install_tool(client: &Client, tool: &Tool, pb: Option<&ProgressBar>) -> Result<()> {
-    if let Ok(installed) = find_installed_version(tool) {
-        if should_skip_for_version(tool, &installed) {
-            if let Some(p) = pb {
-                p.set_message(format!("{} already at {} (skip)", tool.name, installed));
-            } else {
-                println!("{} already at {} (skipping)", tool.name, installed);
+fn install_tool(
+    client: &Client,
+    tool: &Tool,
+    reporter: &dyn ProgressReporter,
+    opts: &InstallOptions,
+) -> Result<()> {
+    reporter.on_start(&tool.name, &tool.version);
+    let res = install_tool_inner(client, tool, reporter, opts);
+    match &res {
+        Ok(outcome) => reporter.on_finish(&tool.name, &tool.version, outcome.as_outcome()),
+        Err(e) => reporter.on_error(&tool.name, &tool.version, e),
+    }
+    res.map(|_| ())
+}
+
+// Owned form of progress::Outcome, handed back from the worker.
+enum Done {
+    Installed,
+    Skipped(String),
+}
+
+impl Done {
+    fn as_outcome(&self) -> Outcome<'_> {
+        match self {
+            Done::Installed => Outcome::Installed,
+            Done::Skipped(installed) => Outcome::Skipped { installed },
+        }
+    }
+}
+
+fn install_tool_inner(
+    client: &Client,
+    tool: &Tool,
+    reporter: &dyn ProgressReporter,
+    opts: &InstallOptions,
+) -> Result<Done> {
+    if tool.via == Some(Via::Corepack) {
+        return install_corepack_shim(tool, opts);
+    }
+    // --force always downloads afresh
+    let installed = if opts.force {
+        None
+    } else {
+        find_installed_version(tool).ok()
+    };
+    if let Some(installed) = installed {
+        if should_skip_for_version(tool, &installed) && companions_installed(tool) {
+            return Ok(Done::Skipped(installed));
+        }
+    }
+    let backup = Backup::take(tool)?;
+    let res = match tool.kind {
+        ToolKind::Archive => install_archive(client, tool, reporter),
+        ToolKind::Direct => install_direct(client, tool, reporter),
+    };
+    match res {
+        Ok(written) => {
+            backup.commit(&written);
+            receipt::record(tool, written);
+            Ok(Done::Installed)
+        }
+        Err(e) => {
+            backup.restore();
+            Err(e)
+        }
+    }
+}
+
+// A failed install must not take a working tool down with it. Before installing, the previous
+// binary and shims are saved as hard links (copies across filesystems) under
+// .tlk/backup/<tool>/, next to a manifest of what existed; the new ones replace them atomically
+// anyway. Toolchains are never moved away: the new one is unpacked next to the old, a
+// same-version one is set aside only for the final rename, and superseded toolchains are removed
+// once the whole install succeeds. A failure removes whatever the attempt added and puts the saved
+// files back. The backup lives outside the swept .tlk/tmp, so an install killed halfway is
+// restored by the next tlk run that gets the project lock (see `recover_interrupted`).
+#[derive(Serialize, Deserialize)]
+struct Backup {
+    name: String,
+    toolchains: PathBuf,
+    // Toolchain dirs of the tool before the attempt
+    before: Vec<PathBuf>,
+    // (installed path, saved copy)
+    files: Vec<(PathBuf, PathBuf)>,
+    // Paths that did not exist before the attempt
+    absent: Vec<PathBuf>,
+    #[serde(skip)]
+    dir: PathBuf,
+    #[serde(skip)]
+    _project: Option<crate::lock::ProjectGuard>,
+}
+
+fn backups_dir() -> PathBuf {
+    project_root()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+        .join(".tlk")
+        .join("backup")
+}
+
+fn toolchains_of(toolchains: &Path, name: &str) -> Vec<PathBuf> {
+    fs::read_dir(toolchains)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| is_toolchain_of(&e.file_name().to_string_lossy(), name))
+        .map(|e| e.path())
+        .collect()
+}
+
+// `toolchains/.<name>-<version>.old`: a same-version toolchain set aside by `install_toolchain`.
+fn set_aside(root: &Path) -> PathBuf {
+    let name = root.file_name().unwrap_or_default().to_string_lossy();
+    root.with_file_name(format!(".{name}.old"))
+}
+
+impl Backup {
+    fn take(tool: &Tool) -> Result<Backup> {
+        // Held until the backup is resolved, so no other process mistakes it for an interrupted
+        // one.
+        let project = crate::lock::lock_project("tlk.lock", &format!("install {}", tool.name))?;
+        let dir = std::path::absolute(install_dir(tool)?)?;
+        let toolchains = toolchains_dir(&dir);
+        let mut files = vec![dir.join(target_bin_filename(tool))];
+        for (command, _) in toolchain_commands(tool) {
+            files.extend([dir.join(&command), dir.join(format!("{command}.cmd"))]);
+        }
+        let mut before = toolchains_of(&toolchains, &tool.name);
+        if let Ok(Some(previous)) = receipt::load(&tool.name) {
+            files.extend(previous.files.into_iter().chain(previous.shims));
+            before.extend(
+                previous
+                    .dirs
+                    .into_iter()
+                    .filter(|d| d.starts_with(&toolchains)),
+            );
+        }
+        files.sort();
+        files.dedup();
+        before.sort();
+        before.dedup();
+        let backup_dir = backups_dir().join(&tool.name);
+        if backup_dir.exists() {
+            fs::remove_dir_all(&backup_dir)?;
+        }
+        ensure_dir(&backup_dir)?;
+        let mut backup = Backup {
+            name: tool.name.clone(),
+            toolchains,
+            before,
+            files: Vec::new(),
+            absent: Vec::new(),
+            dir: backup_dir,
+            _project: Some(project),
+        };
+        for (i, path) in files.into_iter().enumerate() {
+            if !path.exists() {
+                backup.absent.push(path);
+                continue;
+            }
+            let saved = backup.dir.join(format!("file-{i}"));
+            if fs::hard_link(&path, &saved).is_ok() || fs::copy(&path, &saved).is_ok() {
+                backup.files.push((path, saved));
             }
-            return Ok(());
         }
+        let manifest = backup.dir.join("manifest.json");
+        let tmp = manifest.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&backup)?)?;
+        fs::rename(&tmp, &manifest)?;
+        Ok(backup)
     }
-    match tool.kind {
-        ToolKind::Archive => install_archive(client, tool, pb),
-        ToolKind::Direct => install_direct(client, tool, pb),
+
+    fn load(dir: &Path) -> Result<Backup> {
+        let path = dir.join("manifest.json");
+        let mut backup: Backup = serde_json::from_slice(&fs::read(&path)?)
+            .with_context(|| format!("parsing {}", path.display()))?;
+        backup.dir = dir.to_path_buf();
+        Ok(backup)
     }
+
+    /// The install succeeded: drop superseded toolchains and the saved copies.
+    fn commit(self, written: &Written) {
+        if !written.dirs.is_empty() {
+            for old in toolchains_of(&self.toolchains, &self.name) {
+                let current = written
+                    .dirs
+                    .iter()
+                    .any(|d| std::path::absolute(d).is_ok_and(|d| d == old));
+                if !current {
+                    let _ = fs::remove_dir_all(&old);
+                }
+            }
+            for root in &written.dirs {
+                let aside = set_aside(root);
+                if aside.exists() {
+                    let _ = fs::remove_dir_all(&aside);
+                }
+            }
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+
+    fn restore(self) {
+        for path in &self.absent {
+            let _ = fs::remove_file(path);
+        }
+        for dir in toolchains_of(&self.toolchains, &self.name) {
+            if !self.before.contains(&dir) {
+                let _ = fs::remove_dir_all(&dir);
+            }
+        }
+        for root in &self.before {
+            let aside = set_aside(root);
+            if !aside.exists() {
+                continue;
+            }
+            if root.exists() {
+                let _ = fs::remove_dir_all(root);
+            }
+            if let Err(e) = fs::rename(&aside, root) {
+                eprintln!(
+                    "Warning: could not restore {} after the failed install of {}: {e}",
+                    root.display(),
+                    self.name
+                );
+            }
+        }
+        for (path, saved) in &self.files {
+            if let Err(e) = tmp::persist(saved, path) {
+                eprintln!(
+                    "Warning: could not restore {} after the failed install of {}: {e:#}",
+                    path.display(),
+                    self.name
+                );
+            }
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Restore the previous install of every tool whose install was interrupted (killed or crashed
+/// with a backup still in place). Only call with the project lock held.
+pub fn recover_interrupted() {
+    for entry in fs::read_dir(backups_dir()).into_iter().flatten().flatten() {
+        match Backup::load(&entry.path()) {
+            Ok(backup) => {
+                eprintln!(
+                    "Restoring the previous install of {} (an earlier install was interrupted)",
+                    backup.name
+                );
+                backup.restore();
+            }
+            Err(e) => {
+                eprintln!("Warning: discarding unreadable install backup: {e:#}");
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+}
+
+/// Whether an interrupted install left a backup to restore.
+pub fn has_interrupted() -> bool {
+    fs::read_dir(backups_dir()).is_ok_and(|mut d| d.next().is_some())
+}
+
+// Package managers provided "via corepack" are not downloaded: their shim runs
+// `corepack <name>@<version>` from the node toolchain, so corepack fetches and verifies the package
+// manager itself, exactly as it does for package.json's packageManager field.
+fn install_corepack_shim(tool: &Tool, opts: &InstallOptions) -> Result<Done> {
+    const MANAGERS: [&str; 2] = ["pnpm", "yarn"];
+    if !MANAGERS.contains(&tool.name.as_str()) {
+        return Err(anyhow!(
+            "{} cannot be provided via corepack (supported: {})",
+            tool.name,
+            MANAGERS.join(", ")
+        ));
+    }
+    let (exact, _) = normalize_version(&tool.version);
+    if !opts.force && find_installed_version(tool).is_ok_and(|v| v == exact) {
+        return Ok(Done::Skipped(exact));
+    }
+    let dir = install_dir(tool)?;
+    let entry = if cfg!(windows) {
+        "corepack.cmd"
+    } else {
+        "bin/corepack"
+    };
+    let corepack = fs::read_dir(toolchains_dir(&dir))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| is_toolchain_of(&e.file_name().to_string_lossy(), "node"))
+        .map(|e| e.path().join(entry))
+        .find(|p| p.exists())
+        .ok_or_else(|| {
+            anyhow!(
+                "{} via corepack needs node (>= 16.9, which ships corepack) declared in tlk.toml",
+                tool.name
+            )
+        })?;
+    ensure_install_dir(tool, &dir)?;
+    let spec = format!("{}@{exact}", tool.name);
+    platform().write_shim(&dir.join(&tool.name), &corepack, &[&spec])?;
+    receipt::record(
+        tool,
+        Written {
+            shims: shim_files(&dir.join(&tool.name)),
+            ..Written::default()
+        },
+    );
+    Ok(Done::Installed)
 }
 
 fn ensure_dir(p: &Path) -> Result<()> {
     fs::create_dir_all(p).with_context(|| format!("creating dir {p:?}"))
 }
 
+// Create the install dir and apply configured dir mode/group to it (and its .tlk parent).
+fn ensure_install_dir(tool: &Tool, p: &Path) -> Result<()> {
+    ensure_dir(p)?;
+    let perms = tool.permissions.clone().unwrap_or_default();
+    let mode = perms.dir_mode()?;
+    let mut dirs = vec![p.to_path_buf()];
+    if let Some(parent) = p
+        .parent()
+        .filter(|d| d.file_name() == Some(".tlk".as_ref()))
+    {
+        dirs.push(parent.to_path_buf());
+    }
+    for d in dirs {
+        platform().set_dir_mode(&d, mode)?;
+        if let Some(group) = &perms.group {
+            platform().set_group(&d, group)?;
+        }
+    }
+    Ok(())
+}
+
 fn expand_source(tool: &Tool) -> String {
     render_source(tool)
 }
 
 fn target_bin_filename(tool: &Tool) -> String {
+    if tool.via == Some(Via::Corepack) && cfg!(windows) {
+        return format!("{}.cmd", tool.name);
+    }
     platform().final_binary_name(&tool.name)
 }
 
-fn install_direct(client: &Client, tool: &Tool, pb: Option<&ProgressBar>) -> Result<()> {
-    let url = expand_source(tool);
-    if let Some(p) = pb {
-        p.set_message(format!("GET {}", tool.name));
+// The body is streamed to a scratch file and hashed as it is written, so large archives are never
+// held in memory and the checksum is known the moment the last chunk arrives. A body that does
+// not match `expected` is rejected here, before it can reach the download cache.
+fn download(
+    client: &Client,
+    tool: &str,
+    url: &str,
+    expected: Option<&str>,
+    limit: Option<u64>,
+    reporter: &dyn ProgressReporter,
+) -> Result<cache::Artifact> {
+    let artifact = http::retrying(url, || download_once(client, tool, url, limit, reporter))?;
+    check_artifact(tool, &artifact, expected, false, reporter)?;
+    Ok(artifact)
+}
+
+// One attempt, body included: a connection reset halfway through restarts the whole download.
+fn download_once(
+    client: &Client,
+    tool: &str,
+    url: &str,
+    limit: Option<u64>,
+    reporter: &dyn ProgressReporter,
+) -> Result<cache::Artifact> {
+    if let Some(file) = crate::fixture::download(url) {
+        let file = file?;
+        let total = file.metadata().ok().map(|m| m.len());
+        return save_body(file, total, tool, url, limit, reporter);
+    }
+    let mut resp = http::send_once(client.get(url)).with_context(|| format!("GET {url}"))?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        if let Some(api_url) = crate::versioning::private_release_asset(url)? {
+            resp = http::send_once(crate::versioning::github_asset_request(client, &api_url))
+                .with_context(|| format!("GET {api_url}"))?;
+        }
+    }
+    if resp.status().is_server_error() {
+        return Err(http::ServerError {
+            url: url.to_string(),
+            status: resp.status(),
+        }
+        .into());
     }
-    let resp = client
-        .get(&url)
-        .send()
-        .with_context(|| format!("GET {url}"))?;
     if !resp.status().is_success() {
         return Err(anyhow!("download failed {}", resp.status()));
     }
-    let bytes = resp.bytes().with_context(|| "reading body")?.to_vec();
+    let total = resp.content_length();
+    save_body(resp, total, tool, url, limit, reporter)
+}
 
-    if let Some(expected) = &tool.sha256 {
-        verify_sha256(&bytes, expected)?;
-    }
+fn save_body(
+    body: impl Read,
+    total: Option<u64>,
+    tool: &str,
+    url: &str,
+    limit: Option<u64>,
+    reporter: &dyn ProgressReporter,
+) -> Result<cache::Artifact> {
+    reporter.on_stage(tool, Stage::Download { url, total });
+    let scratch = TempDir::new()?;
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(scratch.path().join("download"))?;
+    let mut reader = HashingReader {
+        inner: ProgressReader::new(throttle::reader(body, limit)?, tool, reporter, total),
+        hasher: Sha256::new(),
+    };
+    let size = std::io::copy(&mut reader, &mut file).with_context(|| "reading body")?;
+    Ok(cache::Artifact::downloaded(
+        file,
+        size,
+        hex::encode(reader.hasher.finalize()),
+        scratch,
+    ))
+}
 
-    let install_dir = install_dir(tool)?;
-    ensure_dir(&install_dir)?;
-    let bin_path = install_dir.join(&target_bin_filename(tool));
-    fs::write(&bin_path, &bytes)?;
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&bin_path)?.permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&bin_path, perms)?;
+fn check_artifact(
+    tool: &str,
+    artifact: &cache::Artifact,
+    expected: Option<&str>,
+    cached: bool,
+    reporter: &dyn ProgressReporter,
+) -> Result<()> {
+    reporter.on_stage(
+        tool,
+        Stage::Downloaded {
+            bytes: artifact.size,
+            sha256: &artifact.sha256,
+            cached,
+        },
+    );
+    if let Some(expected) = expected {
+        reporter.on_stage(tool, Stage::Verify { expected });
+        checksum::verify(artifact, expected)?;
     }
     Ok(())
 }
 
-fn install_archive(client: &Client, tool: &Tool, pb: Option<&ProgressBar>) -> Result<()> {
-    let url = expand_source(tool);
-    if let Some(p) = pb {
-        p.set_message(format!("GET {}", tool.name));
+// Fallback URLs that served installs in this run, by tool (recorded in the lock as served_from).
+static SERVED_FROM: std::sync::Mutex<Option<HashMap<String, String>>> = std::sync::Mutex::new(None);
+
+/// The fallback URL `name` was downloaded from in this run, if its primary source failed.
+pub fn served_from(name: &str) -> Option<String> {
+    SERVED_FROM.lock().ok()?.as_ref()?.get(name).cloned()
+}
+
+// Download the artifact from the tool's source, failing over to its fallback sources in order.
+// Returns the URL that served it (archive handling depends on its extension).
+fn fetch_artifact(
+    client: &Client,
+    tool: &Tool,
+    reporter: &dyn ProgressReporter,
+) -> Result<(String, cache::Artifact)> {
+    if let Some(sum @ Checksum::PerPlatform(map)) = &tool.sha256 {
+        sum.check_keys()
+            .with_context(|| format!("{}: invalid sha256 table", tool.name))?;
+        if tool.host_sha256().is_none() {
+            let mut keys: Vec<&str> = map
+                .keys()
+                .map(String::as_str)
+                .filter(|k| *k != "*")
+                .collect();
+            keys.sort_unstable();
+            let host = crate::config::host_platform_key();
+            if !sum.allows_unverified() {
+                return Err(anyhow!(
+                    "{}: sha256 lists {} but not {host}; add it with 'tlk hash --tool {} --platform {host}', or add \"*\" = \"unverified\" to the table to install unverified on unlisted platforms",
+                    tool.name,
+                    keys.join(", "),
+                    tool.name
+                ));
+            }
+            eprintln!(
+                "Warning: {}: sha256 lists {} but not {host}; the download is not verified (\"*\" = \"unverified\")",
+                tool.name,
+                keys.join(", "),
+            );
+        }
     }
-    let resp = client
-        .get(&url)
-        .send()
-        .with_context(|| format!("GET {url}"))?;
-    if !resp.status().is_success() {
-        return Err(anyhow!("download failed {}", resp.status()));
+    let primary = expand_source(tool);
+    let (exact, _) = normalize_version(&tool.version);
+    let fallbacks = tool
+        .fallback_sources_for(&exact, placeholder_os(), placeholder_arch())
+        .into_iter()
+        .map(|u| platform().adjust_direct_url(&u));
+    let mut urls = std::iter::once(primary.clone()).chain(fallbacks).peekable();
+    while let Some(url) = urls.next() {
+        match fetch_one(client, tool, &mirror_url(&url), reporter) {
+            Ok(artifact) => {
+                if url != primary {
+                    if let Ok(mut map) = SERVED_FROM.lock() {
+                        map.get_or_insert_with(HashMap::new)
+                            .insert(tool.name.clone(), url.clone());
+                    }
+                }
+                crate::tofu::check(tool, &artifact.sha256)?;
+                cache::note_fetched(&tool.name, &url, &artifact);
+                return Ok((url, artifact));
+            }
+            Err(e) => match urls.peek() {
+                Some(next) => eprintln!("Warning: {url} failed ({e:#}); trying {next}"),
+                None => {
+                    return match fetch_archived(client, tool, reporter) {
+                        Some(Ok(artifact)) => {
+                            eprintln!("Warning: {url} failed ({e:#}); using the archived copy");
+                            Ok((url, artifact))
+                        }
+                        Some(Err(archive_err)) => {
+                            Err(e
+                                .context(format!("archived copy unavailable too: {archive_err:#}")))
+                        }
+                        None => Err(e),
+                    }
+                }
+            },
+        }
+    }
+    unreachable!("the primary source is always tried")
+}
+
+// The [cache] archive_to copy of the artifact, when its sha256 is known (configured, or pinned
+// in tlk.lock).
+fn fetch_archived(
+    client: &Client,
+    tool: &Tool,
+    reporter: &dyn ProgressReporter,
+) -> Option<Result<cache::Artifact>> {
+    let sha = tool
+        .host_sha256()
+        .and_then(checksum::sha256_hex)
+        .map(str::to_string)
+        .or_else(|| crate::tofu::pin(tool))?;
+    let location = cache::archived_location(&sha)?;
+    if location.starts_with("http://") || location.starts_with("https://") {
+        let expected = format!("sha256:{sha}");
+        return Some(
+            cache::artifact(&tool.name, &location, Some(&sha), || {
+                download(
+                    client,
+                    &tool.name,
+                    &location,
+                    Some(&expected),
+                    None,
+                    reporter,
+                )
+            })
+            .and_then(|a| checksum::verify(&a, &expected).map(|()| a)),
+        );
     }
-    let bytes = resp.bytes().with_context(|| "reading body")?.to_vec();
-    if let Some(expected) = &tool.sha256 {
-        verify_sha256(&bytes, expected)?;
+    Some(
+        cache::Artifact::from_cached(Path::new(&location)).and_then(|a| {
+            checksum::verify(&a, &sha)
+                .map(|()| a)
+                .with_context(|| format!("archived copy {location}"))
+        }),
+    )
+}
+
+fn fetch_one(
+    client: &Client,
+    tool: &Tool,
+    url: &str,
+    reporter: &dyn ProgressReporter,
+) -> Result<cache::Artifact> {
+    let expected = tool.host_sha256();
+    let limit = tool_rate(tool)?;
+    let mut fetched = false;
+    // Only sha256 checksums name cached blobs; others find the download by URL.
+    let by_content = expected.and_then(checksum::sha256_hex);
+    let artifact = cache::artifact(&tool.name, url, by_content, || {
+        fetched = true;
+        download(client, &tool.name, url, expected, limit, reporter)
+    })?;
+    if !fetched {
+        check_artifact(&tool.name, &artifact, expected, true, reporter)?;
+    }
+    Ok(artifact)
+}
+
+/// Download the artifact for `tool` bypassing any cached copy, refresh the cache with it and
+/// return its sha256.
+pub fn fetch_fresh_sha256(tool: &Tool) -> Result<String> {
+    let url = mirror_url(&render_source(tool));
+    let reporter = progress::for_mode(&[]);
+    let artifact = download(
+        &http::client()?,
+        &tool.name,
+        &url,
+        None,
+        tool_rate(tool)?,
+        reporter.as_ref(),
+    )?;
+    cache::store(&tool.name, &url, &artifact);
+    Ok(artifact.sha256)
+}
+
+fn tool_rate(tool: &Tool) -> Result<Option<u64>> {
+    tool.limit_rate
+        .as_deref()
+        .map(throttle::parse_rate)
+        .transpose()
+        .with_context(|| format!("{}: limit_rate", tool.name))
+}
+
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
     }
+}
+
+fn install_direct(
+    client: &Client,
+    tool: &Tool,
+    reporter: &dyn ProgressReporter,
+) -> Result<Written> {
+    let (url, artifact) = fetch_artifact(client, tool, reporter)?;
 
     let install_dir = install_dir(tool)?;
-    ensure_dir(&install_dir)?;
-
-    // Detect archive type
-    let mut extracted = false;
-    if url.ends_with(".tar.gz") || url.ends_with(".tgz") {
-        if let Some(p) = pb {
-            p.set_message(format!("Extract {}", tool.name));
-        }
-        let tar = GzDecoder::new(&bytes[..]);
-        let mut archive = Archive::new(tar);
-        let bin_rel = tool.binary.clone().unwrap_or_else(|| tool.name.clone());
-        let candidates = platform().candidate_archive_entry_names(&bin_rel);
-        for entry in archive.entries()? {
-            let mut e = entry?;
-            let path = e.path()?;
-            let path_str = path.to_string_lossy();
-            let file_name_match = path
-                .file_name()
-                .map(|f| f.to_string_lossy())
-                .unwrap_or_default();
-            if candidates.iter().any(|c| {
-                path.ends_with(c) || file_name_match == *c || path_str.ends_with(&format!("/{c}"))
-            }) {
-                let bin_path = install_dir.join(&target_bin_filename(tool));
-                let mut out = File::create(&bin_path)?;
-                std::io::copy(&mut e, &mut out)?;
-                chmod_exec(&bin_path)?;
-                extracted = true;
+    ensure_install_dir(tool, &install_dir)?;
+    let scratch = TempDir::new()?;
+    let staged = scratch.path().join(target_bin_filename(tool));
+    // Formats that cannot be told apart fail later, when the binary is run.
+    if ArchiveFormat::detect(&artifact, &url).ok().flatten() == Some(ArchiveFormat::Gz) {
+        // A bare gzip'd binary (tool-linux-amd64.gz) served to a direct tool.
+        reporter.on_stage(&tool.name, Stage::Extract { format: "gz" });
+        let mut gz = GzDecoder::new(BufReader::new(artifact.reader()?));
+        std::io::copy(&mut gz, &mut File::create(&staged)?)?;
+    } else {
+        std::io::copy(&mut artifact.reader()?, &mut File::create(&staged)?)?;
+    }
+    if let Some(expected) = &tool.expected_digest {
+        reporter.on_stage(&tool.name, Stage::Verify { expected });
+    }
+    verify_staged_digest(tool, &staged)?;
+    chmod_exec(tool, &staged)?;
+    let target = install_dir.join(target_bin_filename(tool));
+    tmp::persist(&staged, &target)?;
+    Ok(Written {
+        source: Some(url),
+        sha256: Some(artifact.sha256.clone()),
+        files: vec![target],
+        ..Written::default()
+    })
+}
+
+fn install_archive(
+    client: &Client,
+    tool: &Tool,
+    reporter: &dyn ProgressReporter,
+) -> Result<Written> {
+    let (url, artifact) = fetch_artifact(client, tool, reporter)?;
+
+    let install_dir = install_dir(tool)?;
+    ensure_install_dir(tool, &install_dir)?;
+    // Extract into scratch space; only a complete binary is moved into install_dir.
+    let scratch = TempDir::new()?;
+    let staged = scratch.path().join(target_bin_filename(tool));
+
+    let Some(format) = ArchiveFormat::detect(&artifact, &url)? else {
+        return Err(anyhow!(
+            "unsupported archive type for {url} (not gzip, tar or zip)"
+        ));
+    };
+    reporter.on_stage(
+        &tool.name,
+        Stage::Extract {
+            format: format.name(),
+        },
+    );
+    // Archive path of the entry installed as the binary
+    let mut matched: Option<String> = None;
+    match format {
+        ArchiveFormat::TarGz | ArchiveFormat::Tar => {
+            let candidates = archive_candidates(tool);
+            let mut archive = Archive::new(format.tar_reader(artifact.reader()?));
+            for entry in archive.entries()? {
+                let mut e = entry?;
+                let path = e.path()?.into_owned();
+                if tar_entry_matches(&candidates, &path) {
+                    let mut out = File::create(&staged)?;
+                    std::io::copy(&mut e, &mut out)?;
+                    matched = Some(path.to_string_lossy().into_owned());
+                }
             }
         }
-    } else if url.ends_with(".zip") {
-        if let Some(p) = pb {
-            p.set_message(format!("Extract {}", tool.name));
-        }
-        let cursor = std::io::Cursor::new(&bytes);
-        let mut zip = ZipArchive::new(cursor)?;
-        let bin_rel = tool.binary.clone().unwrap_or_else(|| tool.name.clone());
-        let candidates = platform().candidate_archive_entry_names(&bin_rel);
-        for i in 0..zip.len() {
-            let mut file = zip.by_index(i)?;
-            let name = file.name().to_string();
-            if candidates
-                .iter()
-                .any(|c| name.ends_with(c) || name.ends_with(&format!("/{c}")))
-            {
-                let bin_path = install_dir.join(&target_bin_filename(tool));
-                let mut out = File::create(&bin_path)?;
-                std::io::copy(&mut file, &mut out)?;
-                chmod_exec(&bin_path)?;
-                extracted = true;
+        ArchiveFormat::Gz => {
+            // A single gzip'd binary: the only member is the binary, whatever it was named.
+            let mut gz = GzDecoder::new(BufReader::new(artifact.reader()?));
+            std::io::copy(&mut gz, &mut File::create(&staged)?)?;
+            matched = Some(tool.name.clone());
+        }
+        ArchiveFormat::Zip => {
+            let mut zip = ZipArchive::new(BufReader::new(artifact.reader()?))?;
+            let candidates = archive_candidates(tool);
+            for i in 0..zip.len() {
+                let mut file = zip.by_index(i)?;
+                if zip_entry_matches(&candidates, file.name()) {
+                    let mut out = File::create(&staged)?;
+                    std::io::copy(&mut file, &mut out)?;
+                    matched = Some(file.name().to_string());
+                }
             }
         }
-    } else {
-        return Err(anyhow!("unsupported archive type for {url}"));
     }
-    if !extracted {
+    let Some(matched) = matched else {
         return Err(anyhow!(format!(
             "did not find expected binary '{}' inside archive for {}",
             tool.binary.clone().unwrap_or_else(|| tool.name.clone()),
             tool.name
         )));
+    };
+    if let Some(expected) = &tool.expected_digest {
+        reporter.on_stage(&tool.name, Stage::Verify { expected });
     }
-    Ok(())
+    verify_staged_digest(tool, &staged)?;
+    let mut written = Written {
+        source: Some(url.clone()),
+        sha256: Some(artifact.sha256.clone()),
+        ..Written::default()
+    };
+    if keeps_toolchain(tool) {
+        let (root, shims) = install_toolchain(tool, &artifact, format, &install_dir, &matched)?;
+        written.dirs.push(root);
+        written.shims = shims;
+        if runs_from_toolchain(tool) {
+            // The shim written for the tool itself is its install; a copy would lose the tree.
+            return Ok(written);
+        }
+    }
+    chmod_exec(tool, &staged)?;
+    let target = install_dir.join(target_bin_filename(tool));
+    tmp::persist(&staged, &target)?;
+    written.files.push(target);
+    Ok(written)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Tar,
+    /// A single gzip'd file rather than a tarball.
+    Gz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Format of a downloaded artifact from its leading bytes, so URLs with query strings or
+    /// without an extension still unpack; the URL suffix is the fallback for anything the magic
+    /// numbers do not settle (pre-POSIX tarballs).
+    pub fn detect(artifact: &cache::Artifact, url: &str) -> Result<Option<Self>> {
+        let mut head = Vec::with_capacity(512);
+        artifact.reader()?.take(512).read_to_end(&mut head)?;
+        let sniffed = if head.starts_with(&[0x1f, 0x8b]) {
+            // gzip wraps either a tarball or a single binary; look at what it decompresses to.
+            let mut inner = Vec::with_capacity(512);
+            let _ = GzDecoder::new(artifact.reader()?)
+                .take(512)
+                .read_to_end(&mut inner);
+            Some(if is_tar(&inner) {
+                Self::TarGz
+            } else {
+                Self::Gz
+            })
+        } else if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if is_tar(&head) {
+            Some(Self::Tar)
+        } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0]) {
+            return Err(anyhow!("{url} is xz-compressed, which tlk cannot unpack"));
+        } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            return Err(anyhow!("{url} is zstd-compressed, which tlk cannot unpack"));
+        } else {
+            None
+        };
+        Ok(sniffed.or_else(|| Self::from_url(url)))
+    }
+
+    fn from_url(url: &str) -> Option<Self> {
+        if url.ends_with(".tar.gz") || url.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if url.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if url.ends_with(".gz") {
+            Some(Self::Gz)
+        } else if url.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::Tar => "tar",
+            Self::Gz => "gz",
+            Self::Zip => "zip",
+        }
+    }
+
+    // Tar stream of a tarball, decompressed as needed.
+    pub fn tar_reader<R: Read + 'static>(self, inner: R) -> Box<dyn Read> {
+        match self {
+            Self::TarGz => Box::new(GzDecoder::new(BufReader::new(inner))),
+            _ => Box::new(BufReader::new(inner)),
+        }
+    }
+}
+
+// POSIX tar headers carry "ustar" at offset 257.
+fn is_tar(block: &[u8]) -> bool {
+    block.get(257..262) == Some(b"ustar".as_slice())
+}
+
+// Directory holding unpacked toolchains (tools with companions), next to the install dir:
+// .tlk/bin -> .tlk/toolchains/<name>-<version>.
+fn toolchains_dir(install_dir: &Path) -> PathBuf {
+    install_dir
+        .parent()
+        .unwrap_or(install_dir)
+        .join("toolchains")
+}
+
+fn toolchain_root(tool: &Tool, install_dir: &Path) -> PathBuf {
+    let (exact, _) = normalize_version(&tool.version);
+    toolchains_dir(install_dir).join(format!("{}-{exact}", tool.name))
+}
+
+/// Commands run from a tool's unpacked toolchain, as (name, path below the archive's top-level
+/// directory): a known tool's companions and the configured `binaries`.
+pub fn toolchain_commands(tool: &Tool) -> Vec<(String, String)> {
+    let mut commands: Vec<(String, String)> = tool
+        .companions
+        .iter()
+        .map(|c| (c.name.to_string(), c.entry().to_string()))
+        .collect();
+    for path in tool.binaries.iter().flatten() {
+        let file = path.rsplit('/').next().unwrap_or(path);
+        let name = file
+            .strip_suffix(".exe")
+            .or_else(|| file.strip_suffix(".cmd"))
+            .unwrap_or(file);
+        if !commands.iter().any(|(n, _)| n == name) {
+            commands.push((name.to_string(), path.clone()));
+        }
+    }
+    commands
+}
+
+// Tools unpacked whole into .tlk/toolchains rather than installed as a single file.
+fn keeps_toolchain(tool: &Tool) -> bool {
+    tool.extract == Some(Extract::All) || !toolchain_commands(tool).is_empty()
+}
+
+// The tool's own command is a shim into its toolchain, not a copy of the binary.
+fn runs_from_toolchain(tool: &Tool) -> bool {
+    tool.extract == Some(Extract::All)
+        || toolchain_commands(tool)
+            .iter()
+            .any(|(name, _)| name == &tool.name)
+}
+
+// `binaries` are written with unix paths; on Windows "bin/tool" also finds bin/tool.exe / .cmd.
+fn toolchain_entry(root: &Path, entry: &str) -> PathBuf {
+    let path = root.join(entry);
+    if cfg!(windows) && !path.exists() {
+        for ext in ["exe", "cmd"] {
+            let alt = path.with_extension(ext);
+            if alt.exists() {
+                return alt;
+            }
+        }
+    }
+    path
+}
+
+fn companions_installed(tool: &Tool) -> bool {
+    let Ok(dir) = install_dir(tool) else {
+        return false;
+    };
+    let root = toolchain_root(tool, &dir);
+    if keeps_toolchain(tool) && !root.is_dir() {
+        return false;
+    }
+    toolchain_commands(tool).iter().all(|(name, entry)| {
+        let shim = dir.join(name);
+        toolchain_entry(&root, entry).exists()
+            && (shim.exists() || shim.with_extension("cmd").exists())
+    })
+}
+
+// Companion commands (node's npm / npx, configured `binaries`) and `extract = "all"` binaries need
+// the rest of the archive, not just the binary: it is unpacked next to the toolchains dir, moved
+// into place as <name>-<version> (dropping the archive's single top-level directory) and each
+// command gets a shim in the install dir. Toolchains of other versions of the tool are removed.
+fn install_toolchain(
+    tool: &Tool,
+    artifact: &cache::Artifact,
+    format: ArchiveFormat,
+    install_dir: &Path,
+    matched: &str,
+) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let toolchains = toolchains_dir(install_dir);
+    let root = toolchain_root(tool, install_dir);
+    let partial = toolchains.join(format!(".{}.partial", tool.name));
+    ensure_dir(&toolchains)?;
+    if partial.exists() {
+        fs::remove_dir_all(&partial)?;
+    }
+    // Delta update: files unchanged since the most recently installed toolchain are linked.
+    let previous = fs::read_dir(&toolchains)?
+        .flatten()
+        .filter(|e| is_toolchain_of(&e.file_name().to_string_lossy(), &tool.name))
+        .map(|e| e.path())
+        .max_by_key(|dir| fs::metadata(dir).and_then(|m| m.modified()).ok());
+    let previous = previous.as_deref();
+    match format {
+        ArchiveFormat::TarGz | ArchiveFormat::Tar => unpack::tar(
+            &mut Archive::new(format.tar_reader(artifact.reader()?)),
+            &partial,
+            previous,
+        )
+        .with_context(|| format!("unpacking {} toolchain", tool.name))?,
+        ArchiveFormat::Zip => unpack::zip(
+            &artifact.reader()?,
+            &partial,
+            previous,
+            unpack::zip_threads(),
+        )
+        .with_context(|| format!("unpacking {} toolchain", tool.name))?,
+        ArchiveFormat::Gz => {
+            return Err(anyhow!(
+                "{} is kept as a whole toolchain, but a single gzip'd file has nothing besides the binary",
+                tool.name
+            ))
+        }
+    };
+    let top: Vec<PathBuf> = fs::read_dir(&partial)?
+        .flatten()
+        .map(|e| e.path())
+        .collect();
+    let unpacked = match top.as_slice() {
+        [single] if single.is_dir() => single.clone(),
+        _ => partial.clone(),
+    };
+    let mut commands = toolchain_commands(tool);
+    if tool.extract == Some(Extract::All) && !commands.iter().any(|(n, _)| n == &tool.name) {
+        // The binary's own archive path, below the top-level directory dropped here.
+        let mut parts = Path::new(matched)
+            .components()
+            .filter(|c| matches!(c, std::path::Component::Normal(_)));
+        if unpacked != partial {
+            parts.next();
+        }
+        let entry: PathBuf = parts.collect();
+        commands.push((tool.name.clone(), entry.to_string_lossy().into_owned()));
+    }
+    // Older toolchains stay usable until the install succeeds (see Backup); a reinstall of the
+    // same version sets the old tree aside only for the swap.
+    if root.exists() {
+        let aside = set_aside(&root);
+        if aside.exists() {
+            fs::remove_dir_all(&aside)?;
+        }
+        fs::rename(&root, &aside)?;
+    }
+    fs::rename(&unpacked, &root).with_context(|| format!("moving toolchain into {root:?}"))?;
+    if partial.exists() {
+        let _ = fs::remove_dir_all(&partial);
+    }
+    let mut shims = Vec::new();
+    for (name, entry) in &commands {
+        let target = toolchain_entry(&root, entry);
+        if !target.exists() {
+            return Err(anyhow!(
+                "{entry} not found in the {} {} archive",
+                tool.name,
+                tool.version
+            ));
+        }
+        platform().write_shim(&install_dir.join(name), &target, &[])?;
+        shims.extend(shim_files(&install_dir.join(name)));
+    }
+    Ok((root, shims))
+}
+
+// Files `write_shim` produced for `path` (a script, plus a .cmd next to it on Windows).
+fn shim_files(path: &Path) -> Vec<PathBuf> {
+    let mut cmd = path.as_os_str().to_owned();
+    cmd.push(".cmd");
+    [path.to_path_buf(), PathBuf::from(cmd)]
+        .into_iter()
+        .filter(|p| p.exists())
+        .collect()
+}
+
+// "node-20.11.0" belongs to node (but "node-gyp-10.0.0" does not).
+fn is_toolchain_of(dir: &str, name: &str) -> bool {
+    dir.strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Remove the shims of `commands` and the unpacked toolchains of `name` from `install_dir`.
+pub fn remove_toolchain(name: &str, commands: &[String], install_dir: &Path) {
+    for command in commands {
+        for file in [
+            install_dir.join(command),
+            install_dir.join(format!("{command}.cmd")),
+        ] {
+            let _ = fs::remove_file(file);
+        }
+    }
+    let Ok(entries) = fs::read_dir(toolchains_dir(install_dir)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let dir = entry.file_name().to_string_lossy().into_owned();
+        if is_toolchain_of(&dir, name) {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+// Archive paths that may hold the binary: configured archive_entries, then the platform's
+// candidates for `binary` (default: the tool name). When several entries match, the last one in
+// the archive is installed.
+pub fn archive_candidates(tool: &Tool) -> Vec<String> {
+    let bin_rel = tool.binary.clone().unwrap_or_else(|| tool.name.clone());
+    let mut candidates = tool.archive_entries.clone().unwrap_or_default();
+    candidates.extend(platform().candidate_archive_entry_names(&bin_rel));
+    candidates
+}
+
+pub fn tar_entry_matches(candidates: &[String], path: &Path) -> bool {
+    let path_str = path.to_string_lossy();
+    let file_name = path
+        .file_name()
+        .map(|f| f.to_string_lossy())
+        .unwrap_or_default();
+    candidates.iter().any(|c| match candidate_pattern(c) {
+        Some(re) => re.is_match(path_str.trim_start_matches("./")),
+        None => path.ends_with(c) || file_name == *c || path_str.ends_with(&format!("/{c}")),
+    })
+}
+
+pub fn zip_entry_matches(candidates: &[String], name: &str) -> bool {
+    candidates.iter().any(|c| match candidate_pattern(c) {
+        Some(re) => re.is_match(name.trim_start_matches("./")),
+        None => name.ends_with(c) || name.ends_with(&format!("/{c}")),
+    })
+}
+
+// `binary` / `archive_entries` values are literal paths unless they are globs (`*` within a path
+// segment, `**` across segments, `?`, `[...]`), which match the entry path from any segment
+// boundary, or `regex:` patterns searched in the entry path. Archives whose inner directory embeds
+// the version or platform (gh_2.40.0_linux_amd64/bin/gh) then need no per-release value.
+pub fn is_entry_pattern(candidate: &str) -> bool {
+    candidate.starts_with("regex:") || candidate.contains(['*', '?', '['])
+}
+
+fn candidate_pattern(candidate: &str) -> Option<regex::Regex> {
+    // Compiled once per run; an invalid pattern is reported once and matches nothing.
+    static PATTERNS: Lazy<Mutex<HashMap<String, regex::Regex>>> =
+        Lazy::new(|| Mutex::new(HashMap::new()));
+    if !is_entry_pattern(candidate) {
+        return None;
+    }
+    let source = match candidate.strip_prefix("regex:") {
+        Some(re) => re.to_string(),
+        None => glob_regex(candidate),
+    };
+    let mut patterns = PATTERNS.lock().unwrap();
+    let re = patterns.entry(candidate.to_string()).or_insert_with(|| {
+        regex::Regex::new(&source).unwrap_or_else(|e| {
+            eprintln!("Warning: ignoring invalid archive entry pattern '{candidate}': {e}");
+            regex::Regex::new("$^").expect("valid regex")
+        })
+    });
+    Some(re.clone())
+}
+
+fn glob_regex(glob: &str) -> String {
+    let mut out = String::from("(?:^|/)");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    out.push_str("(?:[^/]*/)*");
+                } else {
+                    out.push_str(".*");
+                }
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '[' => {
+                out.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    out.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                out.push(']');
+            }
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out.push('$');
+    out
+}
+
+/// The artifact at `url` for `tool`, through the download cache (no install).
+pub fn fetch_cached(tool: &Tool, url: &str) -> Result<cache::Artifact> {
+    let reporter = progress::for_mode(&[]);
+    fetch_one(&http::client()?, tool, url, reporter.as_ref())
 }
 
 fn should_skip_for_version(tool: &Tool, installed: &str) -> bool {
@@ -498,29 +1744,68 @@ fn should_skip_for_version(tool: &Tool, installed: &str) -> bool {
     false
 }
 
-fn chmod_exec(path: &Path) -> Result<()> {
-    platform().make_executable(path)
+fn chmod_exec(tool: &Tool, path: &Path) -> Result<()> {
+    let perms = tool.permissions.clone().unwrap_or_default();
+    platform().make_executable(path, perms.file_mode()?)?;
+    if let Some(group) = &perms.group {
+        platform().set_group(path, group)?;
+    }
+    Ok(())
 }
 
-fn verify_sha256(data: &[u8], expected: &str) -> Result<()> {
+// Checked before the binary is moved into place so a mismatch leaves the old install untouched.
+fn verify_staged_digest(tool: &Tool, staged: &Path) -> Result<()> {
+    let Some(expected) = &tool.expected_digest else {
+        return Ok(());
+    };
     let mut hasher = Sha256::new();
-    hasher.update(data);
-    let digest = hasher.finalize();
-    let hex = hex::encode(digest);
-    if &hex != expected {
-        return Err(anyhow!("checksum mismatch expected {expected} got {hex}"));
+    std::io::copy(&mut File::open(staged)?, &mut hasher)?;
+    let actual = hex::encode(hasher.finalize());
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(anyhow!(
+            "{}: installed binary digest {actual} does not match locked digest {expected}",
+            tool.name
+        ));
     }
     Ok(())
 }
 
-fn install_dir(_tool: &Tool) -> Result<PathBuf> {
-    Ok(project_root()
-        .unwrap_or(std::env::current_dir()?)
-        .join(".tlk")
-        .join("bin"))
+/// Final on-disk location of a tool's binary.
+pub fn target_path(tool: &Tool) -> Result<PathBuf> {
+    Ok(install_dir(tool)?.join(target_bin_filename(tool)))
+}
+
+fn install_dir(tool: &Tool) -> Result<PathBuf> {
+    let root = project_root().unwrap_or(std::env::current_dir()?);
+    let dir = match &tool.install_dir {
+        Some(dir) => root.join(dir),
+        None => crate::config::project_bin_dir(&root),
+    };
+    Ok(platform().long_path(&dir))
 }
 
-fn project_root() -> Option<PathBuf> {
+/// Directories installs of `name` write to, canonicalized: the project's bin dir and the tool's
+/// configured install dir, each with the toolchains dir next to it.
+pub fn install_roots(name: &str) -> Vec<PathBuf> {
+    let Some(root) = project_root().or_else(|| std::env::current_dir().ok()) else {
+        return Vec::new();
+    };
+    let mut dirs = vec![crate::config::project_bin_dir(&root)];
+    let config = TlkConfig::load(&root.join("tlk.toml").to_string_lossy());
+    if let Some(tool) = config
+        .iter()
+        .flat_map(|c| &c.tools)
+        .find(|t| t.name == name)
+    {
+        dirs.extend(install_dir(tool).ok());
+    }
+    dirs.into_iter()
+        .flat_map(|d| [toolchains_dir(&d), d])
+        .filter_map(|d| d.canonicalize().ok())
+        .collect()
+}
+
+pub fn project_root() -> Option<PathBuf> {
     let mut dir = std::env::current_dir().ok()?;
     loop {
         if dir.join("tlk.toml").exists() {
@@ -533,45 +1818,91 @@ fn project_root() -> Option<PathBuf> {
     None
 }
 
-fn find_installed_version(tool: &Tool) -> Result<String> {
-    // naive: run --version and parse first semver
+pub fn find_installed_version(tool: &Tool) -> Result<String> {
+    // run --version (see probe.rs) and parse first semver
     let dir = install_dir(tool)?;
-    let bin = dir.join(&target_bin_filename(tool));
+    let bin = dir.join(target_bin_filename(tool));
     if !bin.exists() {
         // Try without extension for legacy installs
         #[cfg(windows)]
         {
             let legacy = dir.join(&tool.name);
             if legacy.exists() {
-                return Ok(extract_version_from_binary(&legacy)?);
+                return extract_version_from_binary(tool, &legacy);
             }
         }
         return Err(anyhow!("not installed"));
     }
-    extract_version_from_binary(&bin)
+    if tool.via == Some(Via::Corepack) {
+        // The shim names the version it hands to corepack; running it could trigger a download.
+        let script = fs::read_to_string(&bin)?;
+        return script
+            .split(&format!("{}@", tool.name))
+            .nth(1)
+            .map(|rest| {
+                rest.chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || ".-+".contains(*c))
+                    .collect::<String>()
+            })
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| anyhow!("{} is not a corepack shim", bin.display()));
+    }
+    extract_version_from_binary(tool, &bin)
 }
 
-fn extract_version_from_binary(path: &Path) -> Result<String> {
-    let output = Command::new(path).arg("--version").output()?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    for tok in stdout.split_whitespace() {
-        if let Ok(v) = Version::parse(tok.trim_start_matches('v')) {
-            return Ok(v.to_string());
+fn extract_version_from_binary(tool: &Tool, path: &Path) -> Result<String> {
+    let settings = tool.probe.clone().unwrap_or_default();
+    if settings.enabled() {
+        let version = probe::probe_version(path, &settings)?;
+        if version != "unprobed" {
+            return Ok(version);
         }
     }
-    Ok("unknown".into())
+    // Probing disabled, or a sandboxed probe gave nothing: trust the locked version only when the
+    // binary is byte-identical.
+    let data = fs::read(path)?;
+    let digest = hex::encode(Sha256::digest(&data));
+    let locked = LockFile::load("tlk.lock")?.and_then(|l| l.tools.get(&tool.name).cloned());
+    match locked {
+        Some(lt) if lt.digest.as_deref() == Some(digest.as_str()) => Ok(lt.version),
+        _ => Ok("unprobed".into()),
+    }
 }
 
 pub fn compute_installed_digest(tool: &Tool) -> Result<String> {
+    // A corepack shim embeds absolute paths; there is no downloaded binary to pin.
+    if tool.via == Some(Via::Corepack) {
+        return Err(anyhow!("{} is provided via corepack", tool.name));
+    }
     let dir = install_dir(tool)?;
-    let bin = dir.join(&target_bin_filename(tool));
+    let bin = dir.join(target_bin_filename(tool));
     if !bin.exists() {
         return Err(anyhow!("not installed"));
     }
-    let data = fs::read(&bin)?;
+    // Stream through the hasher rather than loading the whole binary.
     let mut hasher = Sha256::new();
-    hasher.update(&data);
+    std::io::copy(&mut File::open(&bin)?, &mut hasher)?;
     Ok(hex::encode(hasher.finalize()))
 }
 
+/// Installed digests for many tools, hashed concurrently (keyed by tool name; None when not
+/// installed).
+pub fn installed_digests(tools: &[Tool]) -> HashMap<String, Option<String>> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = tools
+            .iter()
+            .map(|t| {
+                (
+                    t.name.clone(),
+                    scope.spawn(|| compute_installed_digest(t).ok()),
+                )
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|(name, h)| (name, h.join().ok().flatten()))
+            .collect()
+    })
+}
+
 // platform-specific helpers moved to platform module
@@ -1,10 +1,11 @@
-use crate::config::{Tool, ToolKind};
+use crate::config::{SourceFn, Tool, ToolKind};
+use crate::naming::{canonical_arch, canonical_os, NamingScheme};
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug)]
 pub enum SourceSpec {
     Template(&'static str),
-    Custom(fn(&str) -> String), // takes version (already sanitized of leading 'v')
+    Custom(SourceFn), // takes version (already sanitized of leading 'v'), os, arch
 }
 
 #[derive(Debug)]
@@ -14,68 +15,364 @@ pub struct KnownToolDef {
     pub binary_rel: Option<&'static str>,
 }
 
+/// Source used instead of the tool's own for versions matching `range` (a semver requirement such
+/// as "<1.7"), for releases published under an older asset naming. The first match wins.
+#[derive(Debug)]
+pub struct Variant {
+    pub range: &'static str,
+    pub source: SourceSpec,
+}
+
+impl Variant {
+    pub fn matches(&self, version: &str) -> bool {
+        version_in(self.range, version)
+    }
+}
+
+fn version_in(range: &str, version: &str) -> bool {
+    let Ok(req) = semver::VersionReq::parse(range) else {
+        return false;
+    };
+    // Tolerate short versions such as jq's "1.6".
+    let v = version.trim_start_matches('v');
+    let (core, pre) = v.split_at(v.find(['-', '+']).unwrap_or(v.len()));
+    let padded = match core.matches('.').count() {
+        0 => format!("{core}.0.0{pre}"),
+        1 => format!("{core}.0{pre}"),
+        _ => v.to_string(),
+    };
+    semver::Version::parse(&padded).is_ok_and(|v| req.matches(&v))
+}
+
+/// Version-scoped sources of a known tool, newest packaging changes first.
+pub fn variants(name: &str) -> &'static [Variant] {
+    // jq before 1.7: jq-linux64, jq-osx-amd64, jq-win64.exe
+    fn jq_legacy_source(version: &str, os: &str, arch: &str) -> String {
+        let asset = match (os, arch) {
+            ("linux", "amd64") => "jq-linux64".to_string(),
+            ("linux", "x86") => "jq-linux32".to_string(),
+            ("darwin", "amd64") => "jq-osx-amd64".to_string(),
+            ("windows", "amd64") => "jq-win64.exe".to_string(),
+            ("windows", "x86") => "jq-win32.exe".to_string(),
+            // not published before 1.7 (e.g. arm64); the URL will 404 with a clear error
+            (o, a) => format!("jq-{o}-{a}"),
+        };
+        format!("https://github.com/jqlang/jq/releases/download/jq-{version}/{asset}")
+    }
+    static JQ: [Variant; 1] = [Variant {
+        range: "<1.7",
+        source: SourceSpec::Custom(jq_legacy_source),
+    }];
+    match name {
+        "jq" => &JQ,
+        _ => &[],
+    }
+}
+
+/// Alternative sources of a known tool, tried in order when its own source fails to download
+/// (throttled or removed assets). Artifacts still have to pass the tool's checksum checks.
+pub fn fallback_sources(name: &str) -> &'static [SourceSpec] {
+    fn node_mirror(version: &str, os: &str, arch: &str) -> String {
+        let os = NamingScheme::Node.os(os);
+        let arch = NamingScheme::Node.arch(arch);
+        let ext = if os == "win" { "zip" } else { "tar.gz" };
+        format!("https://npmmirror.com/mirrors/node/v{version}/node-v{version}-{os}-{arch}.{ext}")
+    }
+    static KUBECTL: [SourceSpec; 1] = [SourceSpec::Template(
+        "https://storage.googleapis.com/kubernetes-release/release/v{version}/bin/{os}/{arch}/kubectl",
+    )];
+    static NODE: [SourceSpec; 1] = [SourceSpec::Custom(node_mirror)];
+    match name {
+        "kubectl" => &KUBECTL,
+        "node" => &NODE,
+        _ => &[],
+    }
+}
+
+/// A command shipped in a known tool's archive next to its binary (node's npm and npx). Tools
+/// with companions are unpacked whole into a toolchain directory and each companion gets a shim
+/// in the install directory. A companion named like the tool itself replaces the copied binary
+/// with a shim, for binaries that find their data relative to their own location (protoc's
+/// include/ of well-known .proto files).
+#[derive(Debug)]
+pub struct Companion {
+    pub name: &'static str,
+    /// Path inside the archive's top-level directory on unix
+    pub unix: &'static str,
+    /// Path inside the archive's top-level directory on windows
+    pub windows: &'static str,
+}
+
+impl Companion {
+    pub fn entry(&self) -> &'static str {
+        if cfg!(windows) {
+            self.windows
+        } else {
+            self.unix
+        }
+    }
+}
+
+pub fn companions(name: &str) -> &'static [Companion] {
+    static NODE: [Companion; 2] = [
+        Companion {
+            name: "npm",
+            unix: "bin/npm",
+            windows: "npm.cmd",
+        },
+        Companion {
+            name: "npx",
+            unix: "bin/npx",
+            windows: "npx.cmd",
+        },
+    ];
+    static PROTOC: [Companion; 1] = [Companion {
+        name: "protoc",
+        unix: "bin/protoc",
+        windows: "bin/protoc.exe",
+    }];
+    match name {
+        "node" => &NODE,
+        "protoc" => &PROTOC,
+        _ => &[],
+    }
+}
+
+/// What a known tool is, for `tlk list --long`, `tlk info` and `tlk search`.
+#[derive(Debug, Clone, Copy)]
+pub struct ToolMeta {
+    pub description: &'static str,
+    pub homepage: &'static str,
+    /// SPDX identifier of the license of current releases
+    pub license: &'static str,
+}
+
+pub fn metadata(name: &str) -> Option<ToolMeta> {
+    let (description, homepage, license) = match name {
+        "terraform" => (
+            "Infrastructure as code: plan and apply cloud resources",
+            "https://www.terraform.io",
+            "BUSL-1.1",
+        ),
+        "kubectl" => (
+            "Command line client for Kubernetes clusters",
+            "https://kubernetes.io/docs/reference/kubectl/",
+            "Apache-2.0",
+        ),
+        "helm" => (
+            "Package manager for Kubernetes",
+            "https://helm.sh",
+            "Apache-2.0",
+        ),
+        "gh" => (
+            "GitHub on the command line",
+            "https://cli.github.com",
+            "MIT",
+        ),
+        "buf" => (
+            "Protocol Buffers linting, breaking change detection and code generation",
+            "https://buf.build",
+            "Apache-2.0",
+        ),
+        "node" => (
+            "JavaScript runtime built on V8 (with npm and npx)",
+            "https://nodejs.org",
+            "MIT",
+        ),
+        "pnpm" => (
+            "Fast, disk space efficient JavaScript package manager",
+            "https://pnpm.io",
+            "MIT",
+        ),
+        "yarn" => (
+            "JavaScript package manager",
+            "https://yarnpkg.com",
+            "BSD-2-Clause",
+        ),
+        "just" => (
+            "Command runner for project recipes",
+            "https://just.systems",
+            "CC0-1.0",
+        ),
+        "jq" => (
+            "Command line JSON processor",
+            "https://jqlang.github.io/jq/",
+            "MIT",
+        ),
+        "cosign" => (
+            "Sign and verify containers and artifacts (Sigstore)",
+            "https://docs.sigstore.dev/cosign/",
+            "Apache-2.0",
+        ),
+        "age" => (
+            "Simple, modern file encryption",
+            "https://age-encryption.org",
+            "BSD-3-Clause",
+        ),
+        "moon" => (
+            "Build system and monorepo management",
+            "https://moonrepo.dev",
+            "MIT",
+        ),
+        "firebase" => (
+            "Firebase CLI: deploy and manage Firebase projects",
+            "https://firebase.google.com/docs/cli",
+            "MIT",
+        ),
+        "flyctl" => (
+            "Fly.io command line interface",
+            "https://fly.io/docs/flyctl/",
+            "Apache-2.0",
+        ),
+        "stripe" => (
+            "Stripe CLI: webhooks, fixtures and API requests from the terminal",
+            "https://stripe.com/docs/stripe-cli",
+            "Apache-2.0",
+        ),
+        "supabase" => (
+            "Supabase CLI: local development, migrations and deploys",
+            "https://supabase.com/docs/guides/cli",
+            "MIT",
+        ),
+        "trivy" => (
+            "Vulnerability, misconfiguration and secret scanner for images, filesystems and IaC",
+            "https://trivy.dev",
+            "Apache-2.0",
+        ),
+        "grype" => (
+            "Vulnerability scanner for container images and filesystems",
+            "https://github.com/anchore/grype",
+            "Apache-2.0",
+        ),
+        "syft" => (
+            "SBOM generator for container images and filesystems",
+            "https://github.com/anchore/syft",
+            "Apache-2.0",
+        ),
+        "hadolint" => (
+            "Dockerfile linter",
+            "https://github.com/hadolint/hadolint",
+            "GPL-3.0",
+        ),
+        "tflint" => (
+            "Terraform linter",
+            "https://github.com/terraform-linters/tflint",
+            "MPL-2.0",
+        ),
+        "terragrunt" => (
+            "Thin wrapper for Terraform / OpenTofu keeping configurations DRY",
+            "https://terragrunt.gruntwork.io",
+            "MIT",
+        ),
+        "checkov" => (
+            "Static analysis for infrastructure as code security (standalone build)",
+            "https://www.checkov.io",
+            "Apache-2.0",
+        ),
+        "protoc" => (
+            "Protocol Buffers compiler (with the well-known type includes)",
+            "https://protobuf.dev",
+            "BSD-3-Clause",
+        ),
+        "protoc-gen-go" => (
+            "protoc plugin generating Go code for Protocol Buffers",
+            "https://protobuf.dev/reference/go/go-generated/",
+            "BSD-3-Clause",
+        ),
+        "protoc-gen-grpc-web" => (
+            "protoc plugin generating gRPC-Web clients for browsers",
+            "https://github.com/grpc/grpc-web",
+            "Apache-2.0",
+        ),
+        _ => return None,
+    };
+    Some(ToolMeta {
+        description,
+        homepage,
+        license,
+    })
+}
+
+/// License of a specific release: relicensed projects keep the old license for releases before
+/// the change.
+pub fn license(name: &str, version: &str) -> Option<&'static str> {
+    static RELICENSED: [(&str, &str, &str); 1] = [("terraform", "<1.6", "MPL-2.0")];
+    RELICENSED
+        .iter()
+        .find(|(tool, range, _)| *tool == name && version_in(range, version))
+        .map(|(_, _, license)| *license)
+        .or_else(|| metadata(name).map(|m| m.license))
+}
+
 impl KnownToolDef {
     fn build(&self, name: &str, version: &str) -> Tool {
         let clean_version = version.trim_start_matches('v').to_string();
-        let source = match self.source {
-            SourceSpec::Template(tpl) => tpl.to_string(),
-            SourceSpec::Custom(f) => (f)(&clean_version),
+        let variants = variants(name);
+        let spec = variants
+            .iter()
+            .find(|v| v.matches(&clean_version))
+            .map(|v| &v.source)
+            .unwrap_or(&self.source);
+        let (source, source_fn) = match *spec {
+            SourceSpec::Template(tpl) => (tpl.to_string(), None),
+            SourceSpec::Custom(f) => (f(&clean_version, detect_os(), detect_arch()), Some(f)),
         };
         Tool {
             name: name.to_string(),
             version: clean_version,
             kind: self.kind.clone(),
             source,
+            maps: None,
+            naming: None,
             sha256: None,
             binary: self.binary_rel.map(|s| s.to_string()),
+            archive_entries: None,
+            binaries: None,
+            extract: None,
             install_dir: None,
+            permissions: None,
+            probe: None,
+            when: None,
+            tofu: None,
+            via: None,
+            limit_rate: None,
+            license: None,
+            env: None,
             per_os: None,
             per_os_arch: None,
+            source_fn,
+            variants,
+            fallbacks: fallback_sources(name),
+            companions: companions(name),
+            expected_digest: None,
         }
     }
 }
 
 pub fn known_tools_map() -> HashMap<&'static str, KnownToolDef> {
     use ToolKind::*;
-    fn map_arch_for_node(a: &str) -> &str {
-        match a {
-            "amd64" => "x64",
-            other => other,
-        }
-    }
-    fn map_arch_for_pnpm(a: &str) -> &str {
-        match a {
-            "amd64" => "x64",
-            other => other,
-        }
-    }
-    fn node_source(version: &str) -> String {
-        let os = detect_os();
-        let arch = map_arch_for_node(detect_arch());
-        // Windows uses win & .zip; others tar.gz. For now prefer unix patterns; extend later as needed.
-        if os == "windows" {
+    // Custom builders receive the target platform in canonical (go-style) names so they can
+    // render sources for any platform, not just the host.
+    fn node_source(version: &str, os: &str, arch: &str) -> String {
+        let os = NamingScheme::Node.os(os);
+        let arch = NamingScheme::Node.arch(arch);
+        // Windows uses win & .zip; others tar.gz.
+        if os == "win" {
             format!("https://nodejs.org/dist/v{version}/node-v{version}-win-{arch}.zip")
         } else {
             format!("https://nodejs.org/dist/v{version}/node-v{version}-{os}-{arch}.tar.gz")
         }
     }
-    fn pnpm_source(version: &str) -> String {
-        let os_raw = detect_os();
-        let arch = map_arch_for_pnpm(detect_arch());
-        // Map OS to pnpm asset naming
-        let os = match os_raw {
-            "darwin" => "macos",
-            "windows" => "win",
-            other => other,
-        };
+    fn pnpm_source(version: &str, os: &str, arch: &str) -> String {
+        let arch = NamingScheme::Node.arch(arch);
+        // pnpm mixes schemes: node-style win/x64 but macos rather than darwin
+        let os = NamingScheme::Macos.os(NamingScheme::Node.os(os));
         let ext = if os == "win" { ".exe" } else { "" };
         // Use new standalone binary assets (linux uses "linuxstatic").
         let os_segment = if os == "linux" { "linuxstatic" } else { os };
         format!("https://github.com/pnpm/pnpm/releases/download/v{version}/pnpm-{os_segment}-{arch}{ext}")
     }
-    fn just_source(version: &str) -> String {
-        let os = detect_os();
-        let arch = detect_arch();
+    fn just_source(version: &str, os: &str, arch: &str) -> String {
         let triple = match (os, arch) {
             ("darwin", "amd64") => "x86_64-apple-darwin",
             ("darwin", "arm64") => "aarch64-apple-darwin",
@@ -91,39 +388,26 @@ pub fn known_tools_map() -> HashMap<&'static str, KnownToolDef> {
         // just filenames omit the leading 'v' in the asset itself.
         format!("https://github.com/casey/just/releases/download/{version}/just-{version}-{triple}.{ext}")
     }
-    fn jq_source(version: &str) -> String {
+    fn jq_source(version: &str, os: &str, arch: &str) -> String {
         // jq uses jq-{os}-{arch}( .exe on windows), version directory is jq-{version}
         // Example: https://github.com/jqlang/jq/releases/download/jq-1.7.1/jq-linux-amd64
-        let os = detect_os();
-        let arch = detect_arch();
-        let os_part = match os {
-            "darwin" => "macos",
-            other => other,
-        };
+        let os_part = NamingScheme::Macos.os(os);
         let ext = if os == "windows" { ".exe" } else { "" };
         format!(
             "https://github.com/jqlang/jq/releases/download/jq-{version}/jq-{os_part}-{arch}{ext}"
         )
     }
-    fn cosign_source(version: &str) -> String {
+    fn cosign_source(version: &str, os: &str, arch: &str) -> String {
         // Example: cosign-linux-amd64 at tag v2.x.y
-        let os = detect_os();
-        let arch = detect_arch();
-        let os_part = match os {
-            "darwin" => "darwin",
-            other => other,
-        }; // keep linux/windows unchanged
         let ext = if os == "windows" { ".exe" } else { "" };
-        format!("https://github.com/sigstore/cosign/releases/download/v{version}/cosign-{os_part}-{arch}{ext}")
+        format!("https://github.com/sigstore/cosign/releases/download/v{version}/cosign-{os}-{arch}{ext}")
     }
-    fn age_source(version: &str) -> String {
+    fn age_source(version: &str, os: &str, arch: &str) -> String {
         // Assets: age-v1.1.1-darwin-amd64.tar.gz, age-v1.1.1-windows-amd64.zip
-        let os = detect_os();
-        let arch = detect_arch();
         let ext = if os == "windows" { "zip" } else { "tar.gz" };
         format!("https://github.com/FiloSottile/age/releases/download/v{version}/age-v{version}-{os}-{arch}.{ext}")
     }
-    fn moon_source(version: &str) -> String {
+    fn moon_source(version: &str, os: &str, arch: &str) -> String {
         // moon publishes raw binaries (no version segment in filename) per target triple, e.g.:
         //   moon-aarch64-apple-darwin
         //   moon-x86_64-unknown-linux-gnu / -musl
@@ -133,11 +417,7 @@ pub fn known_tools_map() -> HashMap<&'static str, KnownToolDef> {
         const MOON_LIBC: &str = "musl";
         #[cfg(not(target_env = "musl"))]
         const MOON_LIBC: &str = "gnu";
-        let os = detect_os();
-        let arch = match detect_arch() {
-            "amd64" => "x86_64",
-            other => other,
-        };
+        let arch = NamingScheme::Gnu.arch(arch);
         let triple = match os {
             "darwin" => format!("{arch}-apple-darwin"),
             "linux" => format!("{arch}-unknown-linux-{libc}", libc = MOON_LIBC),
@@ -147,6 +427,122 @@ pub fn known_tools_map() -> HashMap<&'static str, KnownToolDef> {
         let ext = if os == "windows" { ".exe" } else { "" };
         format!("https://github.com/moonrepo/moon/releases/download/v{version}/moon-{triple}{ext}")
     }
+    fn protoc_source(version: &str, os: &str, arch: &str) -> String {
+        // protoc-25.1-linux-x86_64.zip, protoc-25.1-osx-aarch_64.zip, protoc-25.1-win64.zip; each
+        // holds bin/protoc plus include/google/protobuf/*.proto. Releases since 21.0 have two-part
+        // numbers, which resolved versions carry padded to semver (25.1.0).
+        let version = match version.strip_suffix(".0") {
+            Some(short)
+                if short.split('.').count() == 2
+                    && short
+                        .split('.')
+                        .next()
+                        .and_then(|m| m.parse::<u64>().ok())
+                        .is_some_and(|major| major >= 21) =>
+            {
+                short
+            }
+            _ => version,
+        };
+        let platform = match (os, arch) {
+            ("windows", "x86") => "win32".to_string(),
+            ("windows", _) => "win64".to_string(),
+            (os, arch) => {
+                let os = if os == "darwin" { "osx" } else { os };
+                let arch = match arch {
+                    "amd64" => "x86_64",
+                    "arm64" => "aarch_64",
+                    "x86" => "x86_32",
+                    other => other,
+                };
+                format!("{os}-{arch}")
+            }
+        };
+        format!("https://github.com/protocolbuffers/protobuf/releases/download/v{version}/protoc-{version}-{platform}.zip")
+    }
+    fn protoc_gen_go_source(version: &str, os: &str, arch: &str) -> String {
+        // protoc-gen-go.v1.34.2.linux.amd64.tar.gz; windows ships zips.
+        let ext = if os == "windows" { "zip" } else { "tar.gz" };
+        format!("https://github.com/protocolbuffers/protobuf-go/releases/download/v{version}/protoc-gen-go.v{version}.{os}.{arch}.{ext}")
+    }
+    fn protoc_gen_grpc_web_source(version: &str, os: &str, arch: &str) -> String {
+        // protoc-gen-grpc-web-1.5.0-linux-x86_64 (raw binaries, tags without a 'v')
+        let arch = NamingScheme::Gnu.arch(arch);
+        let ext = if os == "windows" { ".exe" } else { "" };
+        format!("https://github.com/grpc/grpc-web/releases/download/{version}/protoc-gen-grpc-web-{version}-{os}-{arch}{ext}")
+    }
+    fn firebase_source(version: &str, os: &str, _arch: &str) -> String {
+        // Standalone builds: firebase-tools-linux, firebase-tools-macos, firebase-tools-instant-win.exe
+        // (one asset per OS; the macOS build runs on Apple silicon through Rosetta).
+        let asset = match os {
+            "darwin" => "firebase-tools-macos",
+            "windows" => "firebase-tools-instant-win.exe",
+            _ => "firebase-tools-linux",
+        };
+        format!("https://github.com/firebase/firebase-tools/releases/download/v{version}/{asset}")
+    }
+    fn flyctl_source(version: &str, os: &str, arch: &str) -> String {
+        // flyctl_0.2.65_Linux_x86_64.tar.gz, flyctl_0.2.65_macOS_arm64.tar.gz, flyctl_0.2.65_Windows_x86_64.zip
+        let os_part = match os {
+            "darwin" => "macOS",
+            "windows" => "Windows",
+            _ => "Linux",
+        };
+        let arch = if arch == "amd64" { "x86_64" } else { arch };
+        let ext = if os == "windows" { "zip" } else { "tar.gz" };
+        format!("https://github.com/superfly/flyctl/releases/download/v{version}/flyctl_{version}_{os_part}_{arch}.{ext}")
+    }
+    fn stripe_source(version: &str, os: &str, arch: &str) -> String {
+        // stripe_1.19.4_linux_x86_64.tar.gz, stripe_1.19.4_mac-os_arm64.tar.gz, stripe_1.19.4_windows_x86_64.zip
+        let os_part = if os == "darwin" { "mac-os" } else { os };
+        let arch = if arch == "amd64" { "x86_64" } else { arch };
+        let ext = if os == "windows" { "zip" } else { "tar.gz" };
+        format!("https://github.com/stripe/stripe-cli/releases/download/v{version}/stripe_{version}_{os_part}_{arch}.{ext}")
+    }
+    fn trivy_source(version: &str, os: &str, arch: &str) -> String {
+        // trivy_0.50.1_Linux-64bit.tar.gz, trivy_0.50.1_macOS-ARM64.tar.gz, trivy_0.50.1_windows-64bit.zip
+        let os_part = match os {
+            "darwin" => "macOS",
+            "windows" => "windows",
+            _ => "Linux",
+        };
+        let arch = match arch {
+            "amd64" => "64bit",
+            "arm64" => "ARM64",
+            "x86" => "32bit",
+            other => other,
+        };
+        let ext = if os == "windows" { "zip" } else { "tar.gz" };
+        format!("https://github.com/aquasecurity/trivy/releases/download/v{version}/trivy_{version}_{os_part}-{arch}.{ext}")
+    }
+    fn grype_source(version: &str, os: &str, arch: &str) -> String {
+        let ext = if os == "windows" { "zip" } else { "tar.gz" };
+        format!("https://github.com/anchore/grype/releases/download/v{version}/grype_{version}_{os}_{arch}.{ext}")
+    }
+    fn syft_source(version: &str, os: &str, arch: &str) -> String {
+        let ext = if os == "windows" { "zip" } else { "tar.gz" };
+        format!("https://github.com/anchore/syft/releases/download/v{version}/syft_{version}_{os}_{arch}.{ext}")
+    }
+    fn hadolint_source(version: &str, os: &str, arch: &str) -> String {
+        // hadolint-Linux-x86_64, hadolint-Darwin-arm64, hadolint-Windows-x86_64.exe
+        let os_part = match os {
+            "darwin" => "Darwin",
+            "windows" => "Windows",
+            _ => "Linux",
+        };
+        let arch = if arch == "amd64" { "x86_64" } else { arch };
+        let ext = if os == "windows" { ".exe" } else { "" };
+        format!("https://github.com/hadolint/hadolint/releases/download/v{version}/hadolint-{os_part}-{arch}{ext}")
+    }
+    fn terragrunt_source(version: &str, os: &str, arch: &str) -> String {
+        let ext = if os == "windows" { ".exe" } else { "" };
+        format!("https://github.com/gruntwork-io/terragrunt/releases/download/v{version}/terragrunt_{os}_{arch}{ext}")
+    }
+    fn checkov_source(version: &str, os: &str, arch: &str) -> String {
+        // checkov_linux_X86_64.zip / checkov_darwin_arm64.zip holding dist/checkov; tags carry no 'v'
+        let arch = if arch == "amd64" { "X86_64" } else { arch };
+        format!("https://github.com/bridgecrewio/checkov/releases/download/{version}/checkov_{os}_{arch}.zip")
+    }
     HashMap::from([
         ("terraform", KnownToolDef { kind: Archive, source: SourceSpec::Template("https://releases.hashicorp.com/terraform/{version}/terraform_{version}_{os}_{arch}.zip"), binary_rel: Some("terraform") }),
         ("kubectl", KnownToolDef { kind: Direct, source: SourceSpec::Template("https://dl.k8s.io/release/v{version}/bin/{os}/{arch}/kubectl"), binary_rel: None }),
@@ -162,10 +558,27 @@ pub fn known_tools_map() -> HashMap<&'static str, KnownToolDef> {
         ("cosign", KnownToolDef { kind: Direct, source: SourceSpec::Custom(cosign_source), binary_rel: None }),
         ("age", KnownToolDef { kind: Archive, source: SourceSpec::Custom(age_source), binary_rel: Some("age") }),
     ("moon", KnownToolDef { kind: Direct, source: SourceSpec::Custom(moon_source), binary_rel: Some("moon") }),
+        ("protoc", KnownToolDef { kind: Archive, source: SourceSpec::Custom(protoc_source), binary_rel: Some("bin/protoc") }),
+        ("protoc-gen-go", KnownToolDef { kind: Archive, source: SourceSpec::Custom(protoc_gen_go_source), binary_rel: Some("protoc-gen-go") }),
+        ("firebase", KnownToolDef { kind: Direct, source: SourceSpec::Custom(firebase_source), binary_rel: None }),
+        ("flyctl", KnownToolDef { kind: Archive, source: SourceSpec::Custom(flyctl_source), binary_rel: Some("flyctl") }),
+        ("stripe", KnownToolDef { kind: Archive, source: SourceSpec::Custom(stripe_source), binary_rel: Some("stripe") }),
+        ("supabase", KnownToolDef { kind: Archive, source: SourceSpec::Template("https://github.com/supabase/cli/releases/download/v{version}/supabase_{os}_{arch}.tar.gz"), binary_rel: Some("supabase") }),
+        ("trivy", KnownToolDef { kind: Archive, source: SourceSpec::Custom(trivy_source), binary_rel: Some("trivy") }),
+        ("grype", KnownToolDef { kind: Archive, source: SourceSpec::Custom(grype_source), binary_rel: Some("grype") }),
+        ("syft", KnownToolDef { kind: Archive, source: SourceSpec::Custom(syft_source), binary_rel: Some("syft") }),
+        ("hadolint", KnownToolDef { kind: Direct, source: SourceSpec::Custom(hadolint_source), binary_rel: None }),
+        ("tflint", KnownToolDef { kind: Archive, source: SourceSpec::Template("https://github.com/terraform-linters/tflint/releases/download/v{version}/tflint_{os}_{arch}.zip"), binary_rel: Some("tflint") }),
+        ("terragrunt", KnownToolDef { kind: Direct, source: SourceSpec::Custom(terragrunt_source), binary_rel: None }),
+        ("checkov", KnownToolDef { kind: Archive, source: SourceSpec::Custom(checkov_source), binary_rel: Some("dist/checkov") }),
+        ("protoc-gen-grpc-web", KnownToolDef { kind: Direct, source: SourceSpec::Custom(protoc_gen_grpc_web_source), binary_rel: None }),
     ])
 }
 
-pub fn extract_shorthand(root: &toml::Value, existing: &HashSet<String>) -> Vec<Tool> {
+pub fn extract_shorthand(
+    root: &toml::Value,
+    existing: &HashSet<String>,
+) -> anyhow::Result<Vec<Tool>> {
     let mut out = Vec::new();
     let map = known_tools_map();
     if let toml::Value::Table(tbl) = root {
@@ -176,11 +589,51 @@ pub fn extract_shorthand(root: &toml::Value, existing: &HashSet<String>) -> Vec<
             if let Some(def) = map.get(k.as_str()) {
                 if let Some(ver) = v.as_str() {
                     out.push(def.build(k, ver));
+                } else if let Some(ver) = v.get("version").and_then(|x| x.as_str()) {
+                    // Inline table form: terraform = { version = "1.7.5", when = "!env:CI", sha256 = "…" }
+                    // kind / binary / archive_entries / binaries / extract override the recipe's
+                    // packaging, keeping its source.
+                    let mut tool = def.build(k, ver);
+                    if let Some(kind) = field(v, k, "kind")? {
+                        tool.kind = kind;
+                    }
+                    if let Some(binary) = v.get("binary").and_then(|x| x.as_str()) {
+                        tool.binary = Some(binary.to_string());
+                    }
+                    tool.archive_entries = field(v, k, "archive_entries")?;
+                    tool.binaries = field(v, k, "binaries")?;
+                    tool.extract = field(v, k, "extract")?;
+                    tool.via = field(v, k, "via")?;
+                    tool.limit_rate = v
+                        .get("limit_rate")
+                        .and_then(|x| x.as_str())
+                        .map(str::to_string);
+                    tool.license = v
+                        .get("license")
+                        .and_then(|x| x.as_str())
+                        .map(str::to_string);
+                    tool.when = v.get("when").and_then(|x| x.as_str()).map(str::to_string);
+                    tool.sha256 = field(v, k, "sha256")?;
+                    tool.env = field(v, k, "env")?;
+                    out.push(tool);
                 }
             }
         }
     }
-    out
+    Ok(out)
+}
+
+// Optional field `key` of the inline table for `name`; a value of the wrong shape is an error
+// rather than silently ignored (an unreadable sha256 must not install unverified).
+fn field<T: serde::de::DeserializeOwned>(
+    v: &toml::Value,
+    name: &str,
+    key: &str,
+) -> anyhow::Result<Option<T>> {
+    v.get(key)
+        .map(|x| x.clone().try_into())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("{name}.{key}: {e}"))
 }
 
 pub fn build_known_tool(name: &str, version: &str) -> anyhow::Result<Tool> {
@@ -193,17 +646,10 @@ pub fn build_known_tool(name: &str, version: &str) -> anyhow::Result<Tool> {
 }
 
 pub fn detect_os() -> &'static str {
-    match std::env::consts::OS {
-        "macos" => "darwin",
-        other => other,
-    }
+    canonical_os(std::env::consts::OS)
 }
 pub fn detect_arch() -> &'static str {
-    match std::env::consts::ARCH {
-        "x86_64" => "amd64",
-        "aarch64" => "arm64",
-        other => other,
-    }
+    canonical_arch(std::env::consts::ARCH)
 }
 pub fn placeholder_os() -> &'static str {
     detect_os()
@@ -211,3 +657,90 @@ pub fn placeholder_os() -> &'static str {
 pub fn placeholder_arch() -> &'static str {
     detect_arch()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jq_url(version: &str, os: &str, arch: &str) -> String {
+        build_known_tool("jq", version)
+            .unwrap()
+            .source_for(version, os, arch)
+    }
+
+    #[test]
+    fn jq_before_1_7_uses_legacy_asset_names() {
+        assert_eq!(
+            jq_url("1.6", "linux", "amd64"),
+            "https://github.com/jqlang/jq/releases/download/jq-1.6/jq-linux64"
+        );
+        assert_eq!(
+            jq_url("1.5", "darwin", "amd64"),
+            "https://github.com/jqlang/jq/releases/download/jq-1.5/jq-osx-amd64"
+        );
+        assert_eq!(
+            jq_url("1.6", "windows", "amd64"),
+            "https://github.com/jqlang/jq/releases/download/jq-1.6/jq-win64.exe"
+        );
+        // 32-bit x86 hosts report the canonical arch "x86"
+        assert_eq!(
+            jq_url("1.6", "linux", "x86"),
+            "https://github.com/jqlang/jq/releases/download/jq-1.6/jq-linux32"
+        );
+        assert_eq!(
+            jq_url("1.6", "windows", "x86"),
+            "https://github.com/jqlang/jq/releases/download/jq-1.6/jq-win32.exe"
+        );
+    }
+
+    #[test]
+    fn jq_from_1_7_uses_os_arch_asset_names() {
+        assert_eq!(
+            jq_url("1.7", "linux", "amd64"),
+            "https://github.com/jqlang/jq/releases/download/jq-1.7/jq-linux-amd64"
+        );
+        assert_eq!(
+            jq_url("1.7.1", "darwin", "arm64"),
+            "https://github.com/jqlang/jq/releases/download/jq-1.7.1/jq-macos-arm64"
+        );
+        assert_eq!(
+            jq_url("1.7.1", "windows", "amd64"),
+            "https://github.com/jqlang/jq/releases/download/jq-1.7.1/jq-windows-amd64.exe"
+        );
+    }
+
+    #[test]
+    fn ranges_accept_short_and_prefixed_versions() {
+        assert!(version_in("<1.7", "1.6"));
+        assert!(version_in("<1.7", "v1.6"));
+        assert!(version_in("<1.7", "1.6.9"));
+        assert!(!version_in("<1.7", "1.7"));
+        assert!(!version_in("<1.7", "1.7.0"));
+        assert!(!version_in("<1.7", "2"));
+        assert!(version_in("<2", "1"));
+        // pre-releases of the boundary release are not below it under semver's rules
+        assert!(!version_in("<1.7", "1.7-rc1"));
+        assert!(version_in(">=1.7.0-rc1", "1.7-rc2"));
+        assert!(!version_in("<1.7", "not-a-version"));
+    }
+
+    #[test]
+    fn variants_only_apply_to_their_tool() {
+        assert_eq!(variants("jq").len(), 1);
+        assert!(variants("yq").is_empty());
+        let tool = build_known_tool("jq", "1.6").unwrap();
+        assert_eq!(tool.source, jq_url("1.6", detect_os(), detect_arch()));
+    }
+
+    #[test]
+    fn protoc_32_bit_assets() {
+        let url = |os, arch| {
+            build_known_tool("protoc", "25.1.0")
+                .unwrap()
+                .source_for("25.1.0", os, arch)
+        };
+        assert!(url("linux", "x86").ends_with("/protoc-25.1-linux-x86_32.zip"));
+        assert!(url("windows", "x86").ends_with("/protoc-25.1-win32.zip"));
+        assert!(url("windows", "amd64").ends_with("/protoc-25.1-win64.zip"));
+    }
+}
@@ -1,8 +1,16 @@
+use crate::config::Tool;
+use crate::naming::{canonical_arch, canonical_os};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 // ---------------- New schema (v3) ----------------
 
@@ -15,6 +23,14 @@ pub struct LockFile {
     pub schema: Option<u32>,
     /// Map keyed by tool name
     pub tools: HashMap<String, LockedEntry>,
+    /// Per-profile entries that resolve differently from `tools`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ProfileLock>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProfileLock {
+    pub tools: HashMap<String, LockedEntry>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -31,25 +47,54 @@ pub struct LockedEntry {
     pub sources: Option<HashMap<String, String>>, // platform matrix
     #[serde(default)]
     pub sha256: Option<String>,
+    /// Per-platform sha256 values copied from a `sha256 = { … }` table in tlk.toml
+    #[serde(default)]
+    pub sha256_by_platform: Option<HashMap<String, String>>,
     #[serde(default)]
     pub digest: Option<String>,
+    /// Download size in bytes of `source`, when known at lock time
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Trust-on-first-use sha256 pins by platform (recorded from the first download, not
+    /// published by upstream)
+    #[serde(default)]
+    pub tofu_sha256: Option<HashMap<String, String>>,
+    /// Fallback URL that served the artifact on `platform` when `source` could not be downloaded
+    #[serde(default)]
+    pub served_from: Option<String>,
 }
 
 impl LockFile {
     pub fn new(map: HashMap<String, LockedEntry>) -> Self {
         Self {
-            generated: Utc::now(),
+            generated: crate::fixture::now(),
             tlk_version: Some(env!("CARGO_PKG_VERSION").to_string()),
             schema: Some(3),
             tools: map,
+            profiles: BTreeMap::new(),
         }
     }
+    // With a profile selected (`--profile` / TLK_PROFILE) the lock reads as its base entries
+    // overlaid by [profiles.<name>.tools], and writes keep only the entries that resolve
+    // differently from the base (version or source) in that section, leaving the base untouched.
     pub fn load(path: &str) -> Result<Option<Self>> {
+        let Some(mut lf) = Self::load_raw(path)? else {
+            return Ok(None);
+        };
+        if let Some(overrides) = crate::config::profile().and_then(|p| lf.profiles.get(p)) {
+            let overrides = overrides.tools.clone();
+            lf.tools.extend(overrides);
+        }
+        Ok(Some(lf))
+    }
+
+    fn load_raw(path: &str) -> Result<Option<Self>> {
         if !std::path::Path::new(path).exists() {
             return Ok(None);
         }
         let data = fs::read_to_string(path).with_context(|| format!("reading lock file {path}"))?;
-        if let Ok(v3) = toml::from_str::<LockFile>(&data) {
+        if let Ok(mut v3) = toml::from_str::<LockFile>(&data) {
+            v3.canonicalize_platforms();
             return Ok(Some(v3));
         }
         // Attempt legacy upgrade path
@@ -66,26 +111,368 @@ impl LockFile {
                         platform: t.platform,
                         sources: t.sources,
                         sha256: t.sha256,
+                        sha256_by_platform: None,
                         digest: t.digest,
+                        size: None,
+                        tofu_sha256: None,
+                        served_from: None,
                     },
                 );
             }
-            return Ok(Some(LockFile::new(map)));
+            let mut upgraded = LockFile::new(map);
+            upgraded.canonicalize_platforms();
+            return Ok(Some(upgraded));
         }
         Err(anyhow::anyhow!(
             "unable to parse lock file (unsupported schema)"
         ))
     }
+
+    // Locks written before platform keys were unified name the platform and TOFU pins after
+    // Rust's std::env::consts (linux-x86_64, macos-aarch64); read them as host_platform_key()
+    // names (linux-amd64, darwin-arm64) so pins keep matching.
+    fn canonicalize_platforms(&mut self) {
+        let key = |k: &str| match k.split_once('-') {
+            Some((os, arch)) => format!("{}-{}", canonical_os(os), canonical_arch(arch)),
+            None => k.to_string(),
+        };
+        let entries = self.tools.values_mut().chain(
+            self.profiles
+                .values_mut()
+                .flat_map(|p| p.tools.values_mut()),
+        );
+        for entry in entries {
+            if let Some(platform) = &mut entry.platform {
+                *platform = key(platform);
+            }
+            if let Some(pins) = entry.tofu_sha256.take() {
+                entry.tofu_sha256 = Some(pins.into_iter().map(|(k, v)| (key(&k), v)).collect());
+            }
+        }
+    }
+
     pub fn save(&self, path: &str) -> Result<()> {
         let mut clone = self.clone();
+        let raw = Self::load_raw(path).ok().flatten();
+        clone.profiles = raw.as_ref().map(|l| l.profiles.clone()).unwrap_or_default();
+        if let Some(profile) = crate::config::profile() {
+            let base = raw.map(|l| l.tools).unwrap_or_default();
+            let overrides: HashMap<String, LockedEntry> = std::mem::take(&mut clone.tools)
+                .into_iter()
+                .filter(|(name, entry)| {
+                    base.get(name)
+                        .is_none_or(|b| b.version != entry.version || b.source != entry.source)
+                })
+                .collect();
+            clone.tools = base;
+            if overrides.is_empty() {
+                clone.profiles.remove(profile);
+            } else {
+                clone
+                    .profiles
+                    .insert(profile.to_string(), ProfileLock { tools: overrides });
+            }
+        }
         clone.tlk_version = Some(env!("CARGO_PKG_VERSION").to_string());
         if clone.schema.is_none() {
             clone.schema = Some(3);
         }
         let toml_str = toml::to_string_pretty(&clone).with_context(|| "serializing lock file")?;
-        fs::write(path, toml_str).with_context(|| format!("writing lock file {path}"))?;
-        Ok(())
+        // Replace atomically so concurrent readers never see a half-written lock.
+        let tmp = format!("{path}.tmp{}", std::process::id());
+        fs::write(&tmp, &toml_str).with_context(|| format!("writing lock file {tmp}"))?;
+        fs::rename(&tmp, path).with_context(|| format!("replacing lock file {path}"))?;
+        record_lock_hash(path, &toml_str)
     }
+
+    /// Merge per-tool changes into the lock at `path` (created when missing) in one write, under
+    /// the writer lock.
+    pub fn update(
+        path: &str,
+        apply: impl FnOnce(&mut HashMap<String, LockedEntry>),
+    ) -> Result<LockFile> {
+        let _guard = lock_for_write(path)?;
+        let mut tools = LockFile::load(path)?.map(|l| l.tools).unwrap_or_default();
+        apply(&mut tools);
+        let lf = LockFile::new(tools);
+        lf.save(path)?;
+        Ok(lf)
+    }
+}
+
+// --------------- Writer lock ---------------
+//
+// Read-modify-write cycles on tlk.lock hold an exclusive advisory lock on .tlk/lock.lck next to
+// it, so tlk processes running side by side (parallel CI steps, watch + a manual install) merge
+// their changes instead of the last writer dropping the others' entries.
+
+/// Held for the duration of a lock file update; released on drop.
+pub struct WriteGuard {
+    _file: fs::File,
+}
+
+pub fn lock_for_write(lock_path: &str) -> Result<WriteGuard> {
+    let path = tlk_dir(lock_path)?.join("lock.lck");
+    let file = open_lock_file(&path)?;
+    acquire(&file, &path, || {
+        eprintln!("Waiting for another tlk process to finish writing {lock_path}...")
+    })?;
+    Ok(WriteGuard { _file: file })
+}
+
+// --------------- Project lock ---------------
+//
+// Installs and uninstalls hold .tlk/install.lck for their whole run, lock write included, so two
+// tlk invocations in one project (parallel CI jobs, hook-triggered installs) take turns instead
+// of interleaving writes to .tlk/bin. The holder records its pid and command in the file for
+// waiters to report. Re-entrant within a process.
+
+static PROJECT: Mutex<(usize, Option<fs::File>)> = Mutex::new((0, None));
+
+/// Held while an install / uninstall runs; released when the outermost guard drops.
+pub struct ProjectGuard;
+
+impl Drop for ProjectGuard {
+    fn drop(&mut self) {
+        let mut held = PROJECT.lock().unwrap_or_else(|e| e.into_inner());
+        held.0 -= 1;
+        if held.0 == 0 {
+            held.1 = None;
+        }
+    }
+}
+
+pub fn lock_project(lock_path: &str, command: &str) -> Result<ProjectGuard> {
+    let mut held = PROJECT.lock().unwrap_or_else(|e| e.into_inner());
+    if held.0 == 0 {
+        let path = tlk_dir(lock_path)?.join("install.lck");
+        let mut file = open_lock_file(&path)?;
+        acquire(&file, &path, || {
+            let holder = fs::read_to_string(&path).unwrap_or_default();
+            let holder = holder.trim();
+            let holder = if holder.is_empty() {
+                "another tlk process".to_string()
+            } else {
+                format!("another tlk process ({holder})")
+            };
+            eprintln!("Waiting for {holder} to finish in this project...");
+        })?;
+        let _ = file.set_len(0).and_then(|()| {
+            use std::io::Write;
+            write!(file, "pid {}: tlk {command}", std::process::id())
+        });
+        held.1 = Some(file);
+        // Nobody else is installing now, so any backup left in place is from a killed run.
+        crate::installer::recover_interrupted();
+        // Likewise for scratch dirs; failing to clear them must not block the command itself.
+        let _ = crate::tmp::sweep(false);
+    }
+    held.0 += 1;
+    Ok(ProjectGuard)
+}
+
+/// Without taking the project lock (exec): restore tools whose install was killed halfway, when
+/// no other tlk process holds the project lock (it may be the one installing).
+pub fn recover_interrupted(lock_path: &str) {
+    if !crate::installer::has_interrupted() {
+        return;
+    }
+    let Ok(path) = tlk_dir(lock_path).map(|d| d.join("install.lck")) else {
+        return;
+    };
+    if let Ok(file) = open_lock_file(&path) {
+        if file.try_lock().is_ok() {
+            crate::installer::recover_interrupted();
+        }
+    }
+}
+
+static TIMEOUT: OnceCell<u64> = OnceCell::new();
+
+/// Seconds to wait for another process's lock before giving up ([settings] lock_timeout);
+/// TLK_LOCK_TIMEOUT overrides it.
+pub fn set_lock_timeout(secs: Option<u64>) {
+    if let Some(secs) = secs {
+        let _ = TIMEOUT.set(secs);
+    }
+}
+
+fn lock_timeout() -> Duration {
+    let secs = std::env::var("TLK_LOCK_TIMEOUT")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .or_else(|| TIMEOUT.get().copied())
+        .unwrap_or(600);
+    Duration::from_secs(secs)
+}
+
+fn tlk_dir(lock_path: &str) -> Result<std::path::PathBuf> {
+    let dir = Path::new(lock_path)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(".tlk");
+    fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    Ok(dir)
+}
+
+fn open_lock_file(path: &Path) -> Result<fs::File> {
+    fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("opening {}", path.display()))
+}
+
+// Take the exclusive lock on `file`, calling `waiting` once if another process holds it and
+// polling until it is released or the timeout passes. The OS drops the lock of a process that
+// dies, so a lock is never stale.
+fn acquire(file: &fs::File, path: &Path, waiting: impl FnOnce()) -> Result<()> {
+    let mut waiting = Some(waiting);
+    let started = Instant::now();
+    let timeout = lock_timeout();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(()),
+            Err(fs::TryLockError::WouldBlock) => {}
+            Err(fs::TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("locking {}", path.display()))
+            }
+        }
+        if let Some(waiting) = waiting.take() {
+            waiting();
+        }
+        if started.elapsed() >= timeout {
+            anyhow::bail!(
+                "timed out after {}s waiting for {} (held by another tlk process; raise \
+                 [settings] lock_timeout or TLK_LOCK_TIMEOUT to wait longer)",
+                timeout.as_secs(),
+                path.display()
+            );
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+// --------------- Lock file checksum ---------------
+//
+// Opt-in via `[settings] lock_hash` in the tlk.toml next to the lock. Any hex value (or "" to
+// start) stores the sha256 of tlk.lock in tlk.toml itself; the value "file" stores it in
+// .tlk/lock.sha256 instead. Every lock write refreshes it; verify and install --locked refuse a
+// lock whose contents no longer match (edited out-of-band or corrupted in transit).
+
+enum HashRecord {
+    Config(std::path::PathBuf),
+    File(std::path::PathBuf),
+}
+
+fn hash_record(lock_path: &str) -> Result<Option<(HashRecord, Option<String>)>> {
+    let dir = Path::new(lock_path).parent().unwrap_or(Path::new(""));
+    let config = dir.join("tlk.toml");
+    let Ok(raw) = fs::read_to_string(&config) else {
+        return Ok(None);
+    };
+    let root: toml::Value = raw.parse().with_context(|| format!("parsing {config:?}"))?;
+    let Some(settings) = root.get("settings") else {
+        return Ok(None);
+    };
+    let settings: crate::config::Settings = settings
+        .clone()
+        .try_into()
+        .with_context(|| format!("parsing [settings] of {config:?}"))?;
+    let Some(setting) = settings.lock_hash else {
+        return Ok(None);
+    };
+    if setting == "file" {
+        let file = dir.join(".tlk").join("lock.sha256");
+        let stored = fs::read_to_string(&file).ok().map(|s| s.trim().to_string());
+        return Ok(Some((HashRecord::File(file), stored)));
+    }
+    Ok(Some((HashRecord::Config(config), Some(setting))))
+}
+
+fn record_lock_hash(lock_path: &str, contents: &str) -> Result<()> {
+    let Some((record, stored)) = hash_record(lock_path)? else {
+        return Ok(());
+    };
+    let digest = hex::encode(Sha256::digest(contents.as_bytes()));
+    if stored.as_deref() == Some(digest.as_str()) {
+        return Ok(());
+    }
+    match record {
+        HashRecord::File(file) => {
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&file, format!("{digest}\n")).with_context(|| format!("writing {file:?}"))
+        }
+        HashRecord::Config(config) => {
+            // Only the value changes, so the rest of tlk.toml (comments, order) stays as written.
+            let mut raw = fs::read_to_string(&config)?;
+            let Some(value) = lock_hash_value(&raw) else {
+                anyhow::bail!(
+                    "cannot update lock_hash in {config:?}: write it as a `lock_hash = \"\"` line \
+                     under [settings], or use lock_hash = \"file\""
+                );
+            };
+            raw.replace_range(value, &format!("\"{digest}\""));
+            fs::write(&config, raw).with_context(|| format!("writing {config:?}"))
+        }
+    }
+}
+
+// Byte range of the quoted `lock_hash = "…"` value under [settings] in tlk.toml's text.
+fn lock_hash_value(config: &str) -> Option<std::ops::Range<usize>> {
+    static LINE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"^\s*lock_hash\s*=\s*("[^"\n]*"|'[^'\n]*')"#).unwrap());
+    let mut in_settings = false;
+    let mut offset = 0;
+    for line in config.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            let header = trimmed.split('#').next().unwrap_or("").trim();
+            in_settings = header.trim_matches(['[', ']']).trim() == "settings";
+            continue;
+        }
+        if in_settings {
+            if let Some(value) = LINE.captures(line).and_then(|c| c.get(1)) {
+                return Some(start + value.start()..start + value.end());
+            }
+        }
+    }
+    None
+}
+
+/// tlk.toml's text with the recorded lock checksum blanked, for digests of the config (plans)
+/// that should not change whenever the lock is rewritten.
+pub fn without_lock_hash(config: &str) -> String {
+    let mut out = config.to_string();
+    if let Some(value) = lock_hash_value(config) {
+        out.replace_range(value, "\"\"");
+    }
+    out
+}
+
+/// Fail when a recorded lock checksum exists and the lock file no longer matches it.
+pub fn check_lock_hash(lock_path: &str) -> Result<()> {
+    let Some((_, Some(stored))) = hash_record(lock_path)? else {
+        return Ok(());
+    };
+    if stored.is_empty() || !Path::new(lock_path).exists() {
+        return Ok(());
+    }
+    let data = fs::read(lock_path).with_context(|| format!("reading lock file {lock_path}"))?;
+    let digest = hex::encode(Sha256::digest(&data));
+    if !digest.eq_ignore_ascii_case(&stored) {
+        anyhow::bail!(
+            "{lock_path} does not match its recorded checksum (edited outside tlk or corrupted); \
+             if the change is intended run 'tlk install' to rewrite the lock"
+        );
+    }
+    Ok(())
 }
 
 // --------------- Legacy schema (v1/v2) ---------------
@@ -119,32 +506,42 @@ struct OldLockedTool {
 
 // --------------- Helpers ---------------
 
+/// Key recorded in `LockedEntry::platform` for the machine that wrote the entry.
+impl LockedEntry {
+    /// Keep `previous.served_from` when this run downloaded nothing for the same version.
+    pub fn carry_served_from(&mut self, previous: Option<&LockedEntry>) {
+        if self.served_from.is_none() {
+            self.served_from = previous
+                .filter(|p| p.version == self.version && p.platform == self.platform)
+                .and_then(|p| p.served_from.clone());
+        }
+    }
+}
+
 pub fn to_locked_entry(
-    name: &str,
+    tool: &Tool,
     exact_version: &str,
     requested_version: Option<&str>,
     rendered_source: &str,
     template: &str,
-    sha256: &Option<String>,
     digest: Option<String>,
 ) -> (String, LockedEntry) {
-    let platform_key = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+    let platform_key = crate::config::host_platform_key();
+    // Evaluate every platform's own template (per_os / per_os_arch / custom builder) rather than
+    // substituting into the host's template, so non-host URLs are real.
     let mut sources: HashMap<String, String> = HashMap::new();
-    if template.contains("{os}") && template.contains("{arch}") {
-        let oss = ["linux", "darwin", "windows"];
-        let archs = ["amd64", "arm64"];
-        for o in oss.iter() {
-            for a in archs.iter() {
-                let url = template
-                    .replace("{version}", exact_version)
-                    .replace("{os}", o)
-                    .replace("{arch}", a);
-                sources.insert(format!("{o}-{a}"), url);
-            }
+    for o in ["linux", "darwin", "windows"] {
+        for a in ["amd64", "arm64"] {
+            sources.insert(format!("{o}-{a}"), tool.source_for(exact_version, o, a));
         }
     }
+    // Platform independent sources (e.g. a single tarball) need no matrix.
+    let first = sources.values().next().cloned();
+    if sources.values().all(|u| Some(u) == first.as_ref()) {
+        sources.clear();
+    }
     (
-        name.to_string(),
+        tool.name.clone(),
         LockedEntry {
             version: exact_version.to_string(),
             requested_version: requested_version.map(|s| s.to_string()),
@@ -156,9 +553,40 @@ pub fn to_locked_entry(
             } else {
                 Some(sources)
             },
-            sha256: sha256.clone(),
+            sha256: tool.host_sha256().map(str::to_string),
+            sha256_by_platform: match &tool.sha256 {
+                Some(crate::config::Checksum::PerPlatform(map)) => Some(map.clone()),
+                _ => None,
+            },
             digest,
+            size: crate::cache::stored_size(rendered_source),
+            tofu_sha256: None,
+            served_from: crate::installer::served_from(&tool.name),
         },
     )
 }
 // old schema kept only for upgrade parsing
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "# project tools\njq = \"1.7.1\"\n\n[settings]\n# keep the lock honest\nlock_hash = \"\"   # rewritten by tlk\n\n[[settings.webhooks]]\nurl = \"https://example.com\"\n";
+
+    #[test]
+    fn only_the_lock_hash_value_is_rewritten() {
+        let range = lock_hash_value(CONFIG).unwrap();
+        let mut updated = CONFIG.to_string();
+        updated.replace_range(range, "\"abc\"");
+        assert_eq!(
+            updated,
+            CONFIG.replace("lock_hash = \"\"", "lock_hash = \"abc\"")
+        );
+        assert_eq!(without_lock_hash(&updated), CONFIG);
+    }
+
+    #[test]
+    fn lock_hash_outside_settings_is_not_touched() {
+        assert_eq!(lock_hash_value("[tools.lock_hash]\nlock_hash = \"x\"\n"), None);
+    }
+}
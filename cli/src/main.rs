@@ -1,26 +1,104 @@
+mod cache;
+mod checksum;
 mod cli;
 mod command_handlers;
 mod config;
+mod fixture;
+mod git;
+mod http;
 mod installer;
 mod known_tools;
 mod lock;
+mod naming;
+mod notify;
 mod ops;
 mod platform;
+mod probe;
+mod progress;
+mod receipt;
+mod report;
+mod template;
+mod throttle;
+mod tmp;
+mod tofu;
 mod unknown_tools;
+mod unpack;
 mod versioning;
 
 use anyhow::Result;
 use clap::Parser;
 
+use crate::cache::CachePolicy;
 use crate::cli::Cli;
 use crate::config::TlkConfig;
+use crate::progress::ProgressMode;
 
 // CLI definitions moved to cli.rs
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let offline = cli.offline
+        || std::env::var("TLK_OFFLINE").is_ok_and(|v| !v.is_empty() && v != "0" && v != "false");
+    if offline {
+        http::set_offline();
+    }
+    let fixture_dir = cli.fixture_dir.clone().or_else(|| {
+        std::env::var_os("TLK_FIXTURE_DIR")
+            .filter(|v| !v.is_empty())
+            .map(Into::into)
+    });
+    if let Some(dir) = &fixture_dir {
+        fixture::set_dir(dir.clone());
+    }
+    cache::set_policy(if fixture_dir.is_some() {
+        CachePolicy::NoCache
+    } else if cli.cache_only || offline {
+        CachePolicy::CacheOnly
+    } else if cli.no_cache {
+        CachePolicy::NoCache
+    } else if cli.refresh {
+        CachePolicy::Refresh
+    } else {
+        CachePolicy::Default
+    });
+    progress::set_mode(if cli.progress == "json" { ProgressMode::Json } else { ProgressMode::Bars });
     let path = cli.config.clone().unwrap_or_else(|| "tlk.toml".to_string());
+    let profile = cli
+        .profile
+        .clone()
+        .or_else(|| std::env::var("TLK_PROFILE").ok())
+        .filter(|p| !p.is_empty());
+    if let Some(profile) = profile {
+        config::set_profile(profile);
+    }
     let cfg = TlkConfig::load(&path);
+    http::set_policy(
+        cfg.as_ref().and_then(|c| c.settings.tls.clone()).unwrap_or_default(),
+        cli.insecure,
+        cfg.as_ref().and_then(|c| c.settings.http_attempts),
+        cfg.as_ref().map(|c| c.network.clone()).unwrap_or_default(),
+    );
+    versioning::set_github_token_env(cfg.as_ref().and_then(|c| c.settings.github_token_env.clone()));
+    let limit_rate = cli
+        .limit_rate
+        .clone()
+        .or_else(|| cfg.as_ref().and_then(|c| c.settings.limit_rate.clone()));
+    throttle::set_global(limit_rate);
+    if let Some(cfg) = &cfg {
+        installer::set_mirrors(&cfg.mirrors);
+    }
+    installer::set_jobs(
+        cli.jobs
+            .map(usize::from)
+            .or_else(|| cfg.as_ref().and_then(|c| c.settings.jobs)),
+    );
+    lock::set_lock_timeout(cfg.as_ref().and_then(|c| c.settings.lock_timeout));
+    if let Some(max) = cfg.as_ref().and_then(|c| c.cache.max_size.clone()) {
+        cache::set_max_size(max);
+    }
+    if let Some(settings) = cfg.as_ref().map(|c| &c.cache) {
+        cache::set_archive(settings.archive, settings.archive_to.clone());
+    }
     command_handlers::dispatch::dispatch(cli.command, cfg.as_ref(), &path)?;
     Ok(())
 }
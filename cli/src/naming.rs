@@ -0,0 +1,67 @@
+use serde::Deserialize;
+
+/// Platform naming conventions found in upstream release asset names.
+/// tlk's canonical names (what {os}/{arch} expand to by default) are go-style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum NamingScheme {
+    /// linux / darwin / windows, amd64 / arm64 (Go, HashiCorp, Kubernetes)
+    #[serde(rename = "go-style")]
+    Go,
+    /// linux / darwin / windows, x86_64 / aarch64 (target triples, uname -m)
+    #[serde(rename = "gnu-style")]
+    Gnu,
+    /// linux / darwin / win, x64 / arm64 (nodejs.org dist)
+    #[serde(rename = "node-style")]
+    Node,
+    /// linux / macos / windows, amd64 / arm64
+    #[serde(rename = "macos-style")]
+    Macos,
+}
+
+impl NamingScheme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "go-style" => Some(Self::Go),
+            "gnu-style" => Some(Self::Gnu),
+            "node-style" => Some(Self::Node),
+            "macos-style" => Some(Self::Macos),
+            _ => None,
+        }
+    }
+
+    /// Map a canonical (go-style) OS name into this scheme.
+    pub fn os<'a>(&self, os: &'a str) -> &'a str {
+        match (self, os) {
+            (Self::Node, "windows") => "win",
+            (Self::Macos, "darwin") => "macos",
+            _ => os,
+        }
+    }
+
+    /// Map a canonical (go-style) arch name into this scheme.
+    pub fn arch<'a>(&self, arch: &'a str) -> &'a str {
+        match (self, arch) {
+            (Self::Gnu, "amd64") => "x86_64",
+            (Self::Gnu, "arm64") => "aarch64",
+            (Self::Node, "amd64") => "x64",
+            _ => arch,
+        }
+    }
+}
+
+/// Canonical OS name for a Rust `std::env::consts::OS` value.
+pub fn canonical_os(os: &str) -> &str {
+    match os {
+        "macos" => "darwin",
+        other => other,
+    }
+}
+
+/// Canonical arch name for a Rust `std::env::consts::ARCH` value (or a gnu-style alias).
+pub fn canonical_arch(arch: &str) -> &str {
+    match arch {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => other,
+    }
+}
@@ -0,0 +1,153 @@
+use crate::config::{TlkConfig, Webhook};
+use crate::lock::LockFile;
+use serde_json::json;
+use std::time::Duration;
+
+/// Significant events reported to configured webhooks.
+pub enum Event {
+    /// Tools whose locked version was added, changed or removed by a lock write.
+    LockUpdated(Vec<String>),
+    /// Lock verification problems.
+    VerifyFailed(Vec<String>),
+}
+
+impl Event {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::LockUpdated(_) => "lock-updated",
+            Event::VerifyFailed(_) => "verify-failed",
+        }
+    }
+
+    fn details(&self) -> &[String] {
+        match self {
+            Event::LockUpdated(d) | Event::VerifyFailed(d) => d,
+        }
+    }
+
+    fn summary(&self) -> String {
+        match self {
+            Event::LockUpdated(d) => format!("tlk.lock updated ({} tool(s))", d.len()),
+            Event::VerifyFailed(d) => {
+                format!("tlk lock verification failed ({} problem(s))", d.len())
+            }
+        }
+    }
+}
+
+/// Describe version changes between the previous lock (if any) and the one about to be written.
+pub fn lock_changes(old: Option<&LockFile>, new: &LockFile) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (name, entry) in &new.tools {
+        match old.and_then(|o| o.tools.get(name)) {
+            None => changes.push(format!("{name}: added {}", entry.version)),
+            Some(prev) if prev.version != entry.version => {
+                changes.push(format!("{name}: {} -> {}", prev.version, entry.version))
+            }
+            _ => {}
+        }
+    }
+    if let Some(old) = old {
+        for (name, entry) in &old.tools {
+            if !new.tools.contains_key(name) {
+                changes.push(format!("{name}: removed {}", entry.version));
+            }
+        }
+    }
+    changes.sort();
+    changes
+}
+
+/// Fire every webhook subscribed to `event`. Delivery is best effort: failures are printed as
+/// warnings and never fail the command that triggered them.
+pub fn emit(cfg: &TlkConfig, event: Event) {
+    if event.details().is_empty() {
+        return;
+    }
+    let hooks: Vec<&Webhook> = cfg
+        .settings
+        .webhooks
+        .iter()
+        .filter(|h| h.wants(event.name()))
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+    let built =
+        crate::http::builder().and_then(|b| Ok(b.timeout(Duration::from_secs(10)).build()?));
+    let client = match built {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Warning: webhook client: {e}");
+            return;
+        }
+    };
+    let project = project_name();
+    for hook in hooks {
+        let Some(url) = hook.resolve_url() else {
+            continue;
+        };
+        let body = payload(hook, &event, &project);
+        let res = crate::http::send(
+            client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .body(body),
+        )
+        .and_then(|r| Ok(r.error_for_status()?));
+        if let Err(e) = res {
+            // The URL is the webhook's secret (Slack and friends): keep it out of the message.
+            let reason = match e.downcast::<reqwest::Error>() {
+                Ok(e) => format!("{:#}", anyhow::Error::from(e.without_url())),
+                Err(e) => format!("{e:#}").replace(&url, "<webhook url>"),
+            };
+            eprintln!("Warning: webhook for {} failed: {reason}", event.name());
+        }
+    }
+}
+
+fn payload(hook: &Webhook, event: &Event, project: &str) -> String {
+    let summary = event.summary();
+    let details = event.details().join("\n");
+    if let Some(template) = &hook.template {
+        // Values are JSON-escaped (without quotes) so templates can place them inside strings.
+        let esc = |s: &str| {
+            let quoted = serde_json::to_string(s).unwrap_or_default();
+            quoted[1..quoted.len() - 1].to_string()
+        };
+        return template
+            .replace("{event}", &esc(event.name()))
+            .replace("{project}", &esc(project))
+            .replace("{summary}", &esc(&summary))
+            .replace("{details}", &esc(&details));
+    }
+    match hook.format.as_deref() {
+        Some("slack") => json!({ "text": format!("*{project}*: {summary}\n```{details}```") }),
+        _ => json!({
+            "event": event.name(),
+            "project": project,
+            "summary": summary,
+            "details": event.details(),
+        }),
+    }
+    .to_string()
+}
+
+// CI-provided repository slug when available, otherwise the project directory name.
+fn project_name() -> String {
+    for var in [
+        "GITHUB_REPOSITORY",
+        "CI_PROJECT_PATH",
+        "BUILD_REPOSITORY_NAME",
+    ] {
+        if let Ok(v) = std::env::var(var) {
+            if !v.is_empty() {
+                return v;
+            }
+        }
+    }
+    std::env::current_dir()
+        .ok()
+        .and_then(|d| d.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "tlk".into())
+}
@@ -1,64 +1,115 @@
-use crate::config::{TlkConfig, Tool};
+use crate::config::{Checksum, TlkConfig, Tool};
 use crate::known_tools::{placeholder_arch, placeholder_os};
-use crate::{installer, lock};
+use crate::{installer, lock, notify};
 use anyhow::Result;
 
-pub fn write_single_lock(tool: &Tool) -> Result<()> {
-    use std::collections::HashMap;
-    let path = "tlk.lock";
-    let mut map = if let Some(existing) = lock::LockFile::load(path)? {
-        existing.tools
-    } else {
-        HashMap::new()
-    };
-    let digest = installer::compute_installed_digest(tool).ok();
-    let tpl = tool.effective_source_template(placeholder_os(), placeholder_arch());
-    let (exact, requested) = normalize_version(&tool.version);
-    let rendered = installer::render_source(tool).replace(&tool.version, &exact);
-    let (name, entry) = lock::to_locked_entry(
-        &tool.name,
-        &exact,
-        requested.as_deref(),
-        &rendered,
-        &tpl,
-        &tool.sha256,
-        digest,
-    );
-    map.insert(name, entry);
-    let lf = lock::LockFile::new(map);
-    lf.save(path)?;
-    println!("Updated lock with {} {}", tool.name, exact);
+pub fn write_single_lock(cfg: Option<&TlkConfig>, tool: &Tool) -> Result<()> {
+    write_locks(cfg, std::slice::from_ref(tool))
+}
+
+/// Record installed tools in tlk.lock with a single merged write, and notify `cfg`'s webhooks of
+/// the versions that changed.
+pub fn write_locks(cfg: Option<&TlkConfig>, tools: &[Tool]) -> Result<()> {
+    if tools.is_empty() {
+        return Ok(());
+    }
+    let mut entries = Vec::new();
+    for tool in tools {
+        let digest = installer::compute_installed_digest(tool).ok();
+        let tpl = tool.effective_source_template(placeholder_os(), placeholder_arch());
+        let (exact, requested) = normalize_version(&tool.version);
+        let rendered = installer::render_source(tool).replace(&tool.version, &exact);
+        let (name, entry) =
+            lock::to_locked_entry(tool, &exact, requested.as_deref(), &rendered, &tpl, digest);
+        entries.push((tool, name, entry));
+    }
+    let mut changed = Vec::new();
+    let mut previous = None;
+    let lf = lock::LockFile::update("tlk.lock", |map| {
+        previous = Some(lock::LockFile::new(map.clone()));
+        for (tool, name, mut entry) in entries {
+            println!("Updated lock with {} {}", name, entry.version);
+            entry.carry_served_from(map.get(&name));
+            entry.tofu_sha256 = crate::tofu::pins(tool, &entry.version, map.get(&name));
+            if map.get(&name).map(|e| &e.version) != Some(&entry.version) {
+                changed.push(name.clone());
+            }
+            map.insert(name, entry);
+        }
+    })?;
+    crate::cache::archive(changed.iter().map(String::as_str));
+    if let Some(cfg) = cfg {
+        let changes = notify::lock_changes(previous.as_ref(), &lf);
+        notify::emit(cfg, notify::Event::LockUpdated(changes));
+    }
     Ok(())
 }
 
-pub fn install_locked(lock_path: &str, cfg: Option<&TlkConfig>) -> Result<()> {
+pub fn install_locked(
+    lock_path: &str,
+    cfg: Option<&TlkConfig>,
+    opts: &installer::InstallOptions,
+    only: Option<&[String]>,
+) -> Result<()> {
     let Some(cfg) = cfg else {
         return Err(anyhow::anyhow!("No config provided for locked install"));
     };
     let Some(lock) = lock::LockFile::load(lock_path)? else {
         anyhow::bail!("no lock file found at {lock_path}");
     };
+    lock::check_lock_hash(lock_path)?;
     for (name, lt) in lock.tools.iter() {
-        let mut tool = if let Some(t) = cfg.tools.iter().find(|t| &t.name == name) {
-            let mut cloned = t.clone();
-            cloned.version = lt.version.clone();
-            cloned
-        } else {
-            crate::known_tools::build_known_tool(name, &lt.version)?
-        };
-        let platform_key = format!("{}-{}", placeholder_os(), placeholder_arch());
-        if let Some(srcs) = &lt.sources {
-            if let Some(url) = srcs.get(&platform_key) {
-                tool.source = url.clone();
-            } else {
-                tool.source = lt.source.clone();
-            }
+        if only.is_some_and(|names| !names.contains(name)) {
+            continue;
+        }
+        if cfg.skipped.iter().any(|t| &t.name == name) {
+            println!("Skipping {name} (when condition not met)");
+            continue;
+        }
+        let tool = locked_tool(cfg, name, lt)?;
+        installer::install_single(&tool, opts)?;
+    }
+    Ok(())
+}
+
+/// `name` as pinned by its lock entry: the locked version, this platform's locked source and the
+/// locked checksums, with config settings (install dir, permissions, …) kept.
+pub fn locked_tool(cfg: &TlkConfig, name: &str, lt: &lock::LockedEntry) -> Result<Tool> {
+    let mut tool = if let Some(t) = cfg.tools.iter().find(|t| t.name == name) {
+        let mut cloned = t.clone();
+        cloned.version = lt.version.clone();
+        cloned
+    } else {
+        let mut built = crate::known_tools::build_known_tool(name, &lt.version)?;
+        cfg.settings.apply(&mut built);
+        built
+    };
+    // The locked URL is authoritative: drop anything that would re-derive the source.
+    tool.source_fn = None;
+    tool.variants = &[];
+    tool.per_os = None;
+    tool.per_os_arch = None;
+    let platform_key = format!("{}-{}", placeholder_os(), placeholder_arch());
+    if let Some(srcs) = &lt.sources {
+        if let Some(url) = srcs.get(&platform_key) {
+            tool.source = url.clone();
         } else {
             tool.source = lt.source.clone();
         }
-        installer::install_single(&tool)?;
+    } else {
+        tool.source = lt.source.clone();
     }
-    Ok(())
+    // Locked checksums win over config: the artifact must hash to the locked sha256 and,
+    // when the lock was written on this platform, the binary must match the locked digest.
+    if let Some(map) = &lt.sha256_by_platform {
+        tool.sha256 = Some(Checksum::PerPlatform(map.clone()));
+    } else if let Some(sum) = &lt.sha256 {
+        tool.sha256 = Some(Checksum::Single(sum.clone()));
+    }
+    if lt.platform.as_deref() == Some(crate::config::host_platform_key().as_str()) {
+        tool.expected_digest = lt.digest.clone();
+    }
+    Ok(tool)
 }
 
 fn normalize_version(spec: &str) -> (String, Option<String>) {
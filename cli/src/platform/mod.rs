@@ -8,10 +8,77 @@ use std::path::{Path, PathBuf};
 pub trait PlatformOps: Sync + Send {
     fn home_dir(&self) -> Option<PathBuf>;
     fn global_bin_dir(&self) -> Option<PathBuf>;
+    /// Shared machine-wide location used by `tlk install --system`.
+    fn system_bin_dir(&self) -> Option<PathBuf>;
     fn final_binary_name(&self, base: &str) -> String;
     fn candidate_archive_entry_names(&self, base: &str) -> Vec<String>;
     fn adjust_direct_url(&self, url: &str) -> String;
-    fn make_executable(&self, path: &Path) -> Result<()>;
+    /// Form of an absolute path that is safe to hand to filesystem APIs regardless of length.
+    fn long_path(&self, path: &Path) -> PathBuf;
+    /// Inverse of `long_path`, for paths shown to users or compared with paths from elsewhere.
+    fn display_path(&self, path: &Path) -> PathBuf;
+    fn make_executable(&self, path: &Path, mode: u32) -> Result<()>;
+    fn set_dir_mode(&self, path: &Path, mode: u32) -> Result<()>;
+    fn set_group(&self, path: &Path, group: &str) -> Result<()>;
+    /// Write a launcher at `shim` (extension added where the platform needs one) that runs
+    /// `target` with `args` before the caller's arguments, with `target`'s directory first on
+    /// PATH. Returns the file written.
+    fn write_shim(&self, shim: &Path, target: &Path, args: &[&str]) -> Result<PathBuf>;
+}
+
+/// Windows extended-length (`\\?\`) form of an absolute path such as `C:\a\b` or
+/// `\\server\share\a`. The prefix switches off Windows' own path normalization, so `.` and `..`
+/// are resolved here (never above the drive or share) and `/` becomes `\`. None for relative
+/// paths; paths that already carry the prefix are returned as they are.
+#[cfg(any(windows, test))]
+pub fn extended_length(path: &str) -> Option<String> {
+    if path.starts_with(r"\\?\") {
+        return Some(path.to_string());
+    }
+    let s = path.replace('/', "\\");
+    let (mut out, rest) = match s.strip_prefix(r"\\") {
+        Some(unc) => {
+            let mut parts = unc.splitn(3, '\\');
+            let server = parts.next().filter(|p| !p.is_empty())?;
+            let share = parts.next().filter(|p| !p.is_empty())?;
+            (format!(r"\\?\UNC\{server}\{share}"), parts.next().unwrap_or(""))
+        }
+        None => {
+            let b = s.as_bytes();
+            if b.len() < 3 || !b[0].is_ascii_alphabetic() || b[1] != b':' || b[2] != b'\\' {
+                return None;
+            }
+            (format!(r"\\?\{}", &s[..2]), &s[3..])
+        }
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split('\\') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    if parts.is_empty() {
+        out.push('\\');
+    }
+    for part in parts {
+        out.push('\\');
+        out.push_str(part);
+    }
+    Some(out)
+}
+
+/// `path` without the extended-length prefix `extended_length` adds (`\\?\UNC\server\share`
+/// back to `\\server\share`); other paths are returned as they are.
+#[cfg(any(windows, test))]
+pub fn plain_path(path: &str) -> String {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        return format!(r"\\{unc}");
+    }
+    path.strip_prefix(r"\\?\").unwrap_or(path).to_string()
 }
 
 #[cfg(unix)]
@@ -23,3 +90,61 @@ pub use unix::UNIX_PLATFORM as ConcretePlatform;
 mod windows;
 #[cfg(windows)]
 pub use windows::WINDOWS_PLATFORM as ConcretePlatform;
+
+#[cfg(test)]
+mod tests {
+    use super::{extended_length, plain_path};
+
+    #[test]
+    fn drive_paths_get_the_prefix() {
+        assert_eq!(
+            extended_length(r"C:\Users\me\.tlk\bin").as_deref(),
+            Some(r"\\?\C:\Users\me\.tlk\bin")
+        );
+        assert_eq!(extended_length("C:/a/b").as_deref(), Some(r"\\?\C:\a\b"));
+        assert_eq!(extended_length(r"C:\").as_deref(), Some(r"\\?\C:\"));
+    }
+
+    #[test]
+    fn dots_are_resolved_before_prefixing() {
+        assert_eq!(
+            extended_length(r"C:\proj\.tlk\..\tools\.\bin\").as_deref(),
+            Some(r"\\?\C:\proj\tools\bin")
+        );
+        assert_eq!(extended_length(r"C:\a\..\..\b").as_deref(), Some(r"\\?\C:\b"));
+        assert_eq!(extended_length(r"C:\a\..").as_deref(), Some(r"\\?\C:\"));
+    }
+
+    #[test]
+    fn unc_paths_use_the_unc_form() {
+        assert_eq!(
+            extended_length(r"\\server\share\tools\..\bin").as_deref(),
+            Some(r"\\?\UNC\server\share\bin")
+        );
+        assert_eq!(
+            extended_length(r"\\server\share\..\..\x").as_deref(),
+            Some(r"\\?\UNC\server\share\x")
+        );
+        assert_eq!(extended_length(r"\\server"), None);
+    }
+
+    #[test]
+    fn relative_and_prefixed_paths() {
+        assert_eq!(extended_length(r"tools\bin"), None);
+        assert_eq!(extended_length(r"\tools\bin"), None);
+        assert_eq!(extended_length("C:tools"), None);
+        assert_eq!(
+            extended_length(r"\\?\C:\a\..\b").as_deref(),
+            Some(r"\\?\C:\a\..\b")
+        );
+    }
+
+    #[test]
+    fn plain_path_reverses_the_prefix() {
+        assert_eq!(plain_path(r"\\?\C:\a\b"), r"C:\a\b");
+        assert_eq!(plain_path(r"\\?\UNC\server\share\x"), r"\\server\share\x");
+        assert_eq!(plain_path(r"C:\a"), r"C:\a");
+        let long = r"C:\proj\tools\bin";
+        assert_eq!(plain_path(&extended_length(long).unwrap()), long);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::platform::PlatformOps;
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use std::path::{Path, PathBuf};
 
 pub static UNIX_PLATFORM: Unix = Unix;
@@ -13,17 +13,16 @@ impl PlatformOps for Unix {
     fn global_bin_dir(&self) -> Option<PathBuf> {
         self.home_dir().map(|h| h.join(".tlk").join("bin"))
     }
+    fn system_bin_dir(&self) -> Option<PathBuf> {
+        Some(PathBuf::from("/opt/tlk/bin"))
+    }
     fn final_binary_name(&self, base: &str) -> String {
         base.to_string()
     }
     fn candidate_archive_entry_names(&self, base: &str) -> Vec<String> {
         // Include base plus common nested patterns (e.g., linux-amd64/helm, darwin-arm64/gh)
-        let os = std::env::consts::OS;
-        let arch = match std::env::consts::ARCH {
-            "x86_64" => "amd64",
-            "aarch64" => "arm64",
-            other => other,
-        };
+        let os = crate::known_tools::detect_os();
+        let arch = crate::known_tools::detect_arch();
         vec![
             base.to_string(),
             format!("{os}-{arch}/{base}"),
@@ -34,11 +33,92 @@ impl PlatformOps for Unix {
     fn adjust_direct_url(&self, url: &str) -> String {
         url.to_string()
     }
-    fn make_executable(&self, path: &Path) -> Result<()> {
+    fn long_path(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+    fn display_path(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+    fn make_executable(&self, path: &Path, mode: u32) -> Result<()> {
         use std::os::unix::fs::PermissionsExt;
         let mut perms = std::fs::metadata(path)?.permissions();
-        perms.set_mode(0o755);
+        perms.set_mode(mode);
         std::fs::set_permissions(path, perms)?;
         Ok(())
     }
+    fn set_dir_mode(&self, path: &Path, mode: u32) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("setting mode {mode:o} on {path:?}"))
+    }
+    fn set_group(&self, path: &Path, group: &str) -> Result<()> {
+        let gid = resolve_gid(group)?;
+        std::os::unix::fs::chown(path, None, Some(gid))
+            .with_context(|| format!("changing group of {path:?} to {group}"))
+    }
+    fn write_shim(&self, shim: &Path, target: &Path, args: &[&str]) -> Result<PathBuf> {
+        let dir = target.parent().unwrap_or(Path::new("."));
+        let args: String = args.iter().map(|a| format!(" \"{a}\"")).collect();
+        let script = format!(
+            "#!/bin/sh\nexport PATH=\"{}:$PATH\"\nexec \"{}\"{args} \"$@\"\n",
+            dir.display(),
+            target.display()
+        );
+        std::fs::write(shim, script).with_context(|| format!("writing shim {shim:?}"))?;
+        self.make_executable(shim, 0o755)?;
+        Ok(shim.to_path_buf())
+    }
+}
+
+// Accept a numeric gid or look the name up through NSS (getgrnam_r), so groups from LDAP / sssd
+// resolve like they do for chgrp, not just those in /etc/group.
+fn resolve_gid(group: &str) -> Result<u32> {
+    if let Ok(gid) = group.parse::<u32>() {
+        return Ok(gid);
+    }
+    let name =
+        std::ffi::CString::new(group).map_err(|_| anyhow!("invalid group name '{group}'"))?;
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut entry: libc::group = unsafe { std::mem::zeroed() };
+        let mut found: *mut libc::group = std::ptr::null_mut();
+        // SAFETY: every pointer refers to live, correctly sized storage owned by this frame.
+        let rc = unsafe {
+            libc::getgrnam_r(
+                name.as_ptr(),
+                &mut entry,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut found,
+            )
+        };
+        match rc {
+            0 if found.is_null() => return Err(anyhow!("unknown group '{group}'")),
+            0 => return Ok(entry.gr_gid),
+            // Large groups (many members) need a bigger buffer.
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 4, 0),
+            errno => {
+                return Err(std::io::Error::from_raw_os_error(errno))
+                    .with_context(|| format!("looking up group '{group}'"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_gid;
+
+    #[test]
+    fn groups_resolve_by_number_and_name() {
+        assert_eq!(resolve_gid("1234").unwrap(), 1234);
+        assert_eq!(
+            resolve_gid("root")
+                .or_else(|_| resolve_gid("wheel"))
+                .unwrap(),
+            0
+        );
+        let err = resolve_gid("tlk-no-such-group").unwrap_err();
+        assert!(err.to_string().contains("unknown group"), "{err}");
+    }
 }
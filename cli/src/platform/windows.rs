@@ -1,5 +1,5 @@
 use crate::platform::PlatformOps;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
 pub static WINDOWS_PLATFORM: Windows = Windows;
@@ -8,9 +8,26 @@ pub struct Windows;
 
 impl PlatformOps for Windows {
     fn home_dir(&self) -> Option<PathBuf> { std::env::var_os("USERPROFILE").map(PathBuf::from) }
+    fn display_path(&self, path: &Path) -> PathBuf { PathBuf::from(super::plain_path(&path.to_string_lossy())) }
     fn global_bin_dir(&self) -> Option<PathBuf> { self.home_dir().map(|h| h.join(".tlk").join("bin")) }
+    fn system_bin_dir(&self) -> Option<PathBuf> { std::env::var_os("ProgramData").map(|p| PathBuf::from(p).join("tlk").join("bin")) }
     fn final_binary_name(&self, base: &str) -> String { if base.ends_with(".exe") { base.to_string() } else { format!("{base}.exe") } }
     fn candidate_archive_entry_names(&self, base: &str) -> Vec<String> { if base.ends_with(".exe") { vec![base.to_string()] } else { vec![base.to_string(), format!("{base}.exe")] } }
-    fn adjust_direct_url(&self, url: &str) -> String { if url.ends_with(".exe") || url.ends_with(".zip") || url.ends_with(".tar.gz") { url.to_string() } else { format!("{url}.exe") } }
-    fn make_executable(&self, _path: &Path) -> Result<()> { Ok(()) }
+    fn adjust_direct_url(&self, url: &str) -> String { if url.ends_with(".exe") || url.ends_with(".zip") || url.ends_with(".gz") || url.ends_with(".tar") { url.to_string() } else { format!("{url}.exe") } }
+    // Paths beyond MAX_PATH (260) need the extended-length prefix; UNC roots use the \\?\UNC\ form.
+    fn long_path(&self, path: &Path) -> PathBuf { super::extended_length(&path.to_string_lossy()).map_or_else(|| path.to_path_buf(), PathBuf::from) }
+    fn make_executable(&self, _path: &Path, _mode: u32) -> Result<()> { Ok(()) }
+    // NTFS ACLs are inherited from the install directory; unix style modes/groups do not apply.
+    fn set_dir_mode(&self, _path: &Path, _mode: u32) -> Result<()> { Ok(()) }
+    fn set_group(&self, _path: &Path, _group: &str) -> Result<()> { Ok(()) }
+    // cmd.exe does not understand the extended-length prefix, so the script gets plain paths.
+    fn write_shim(&self, shim: &Path, target: &Path, args: &[&str]) -> Result<PathBuf> {
+        let plain = |p: &Path| super::plain_path(&p.to_string_lossy());
+        let dir = target.parent().map(plain).unwrap_or_default();
+        let path = shim.with_extension("cmd");
+        let args: String = args.iter().map(|a| format!(" \"{a}\"")).collect();
+        let script = format!("@echo off\r\nset \"PATH={dir};%PATH%\"\r\n\"{}\"{args} %*\r\n", plain(target));
+        std::fs::write(&path, script).with_context(|| format!("writing shim {path:?}"))?;
+        Ok(path)
+    }
 }
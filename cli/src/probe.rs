@@ -0,0 +1,145 @@
+use crate::config::ProbeSettings;
+use crate::tmp::TempDir;
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use semver::Version;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Version probing runs downloaded binaries (`<bin> --version`). Every probe gets a timeout,
+// closed stdin and a scratch working directory; with `sandbox = true` it additionally runs
+// with a scrubbed environment and, where a helper is available and works, without network access
+// (`unshare --net` on Linux, `sandbox-exec` on macOS). A sandboxed probe that fails or prints
+// nothing reports "unprobed": the binary controls that outcome, so it never earns a second run
+// outside the sandbox.
+pub fn probe_version(path: &Path, settings: &ProbeSettings) -> Result<String> {
+    let scratch = TempDir::new()?;
+    let scratch = scratch.path();
+    let stdout = if settings.sandbox() {
+        match run_probe(sandboxed_command(path, scratch), scratch, settings) {
+            Ok(out) if !out.trim().is_empty() => out,
+            _ => return Ok("unprobed".into()),
+        }
+    } else {
+        let mut c = Command::new(path);
+        c.arg("--version");
+        run_probe(c, scratch, settings)?
+    };
+    for tok in stdout.split_whitespace() {
+        if let Ok(v) = Version::parse(tok.trim_start_matches('v')) {
+            return Ok(v.to_string());
+        }
+    }
+    Ok("unknown".into())
+}
+
+fn run_probe(mut cmd: Command, scratch: &Path, settings: &ProbeSettings) -> Result<String> {
+    cmd.current_dir(scratch)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    run_with_timeout(cmd, settings.timeout())
+}
+
+fn sandboxed_command(path: &Path, scratch: &Path) -> Command {
+    let mut cmd = match &*NETWORK_HELPER {
+        Some(helper) => helper.wrap(path),
+        None => Command::new(path),
+    };
+    cmd.arg("--version");
+    // Only what a --version probe plausibly needs; HOME points at the scratch dir.
+    cmd.env_clear()
+        .env("HOME", scratch)
+        .env("TMPDIR", scratch)
+        .env("LANG", "C");
+    if let Some(root) = std::env::var_os("SystemRoot") {
+        cmd.env("SystemRoot", root);
+    }
+    cmd
+}
+
+enum NetworkHelper {
+    Unshare(std::path::PathBuf),
+    SandboxExec(std::path::PathBuf),
+}
+
+impl NetworkHelper {
+    fn wrap(&self, program: &Path) -> Command {
+        let mut c = match self {
+            Self::Unshare(unshare) => {
+                let mut c = Command::new(unshare);
+                c.args(["--user", "--map-root-user", "--net", "--"]);
+                c
+            }
+            Self::SandboxExec(exec) => {
+                let mut c = Command::new(exec);
+                c.args(["-p", "(version 1)(allow default)(deny network*)"]);
+                c
+            }
+        };
+        c.arg(program);
+        c
+    }
+}
+
+// Found and checked once per run: unshare is commonly installed but unusable (unprivileged user
+// namespaces disabled, or inside a container), which would otherwise turn every sandboxed probe
+// into "unprobed".
+static NETWORK_HELPER: Lazy<Option<NetworkHelper>> = Lazy::new(|| {
+    let helper = if cfg!(target_os = "linux") {
+        which::which("unshare").ok().map(NetworkHelper::Unshare)
+    } else if cfg!(target_os = "macos") {
+        which::which("sandbox-exec")
+            .ok()
+            .map(NetworkHelper::SandboxExec)
+    } else {
+        None
+    }?;
+    let works = helper
+        .wrap(Path::new("true"))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    if !works {
+        eprintln!(
+            "Warning: cannot isolate version probes from the network on this machine; \
+             sandboxed probes run with a scrubbed environment only"
+        );
+        return None;
+    }
+    Some(helper)
+});
+
+fn run_with_timeout(mut cmd: Command, timeout: Duration) -> Result<String> {
+    use std::io::Read;
+    let mut child = cmd.spawn()?;
+    // Drain stdout on a thread so a chatty binary cannot block on a full pipe.
+    let reader = child.stdout.take().map(|mut out| {
+        std::thread::spawn(move || {
+            let mut buf = String::new();
+            let _ = out.read_to_string(&mut buf);
+            buf
+        })
+    });
+    let start = Instant::now();
+    loop {
+        if child.try_wait()?.is_some() {
+            break;
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!(
+                "version probe timed out after {}s",
+                timeout.as_secs()
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(25));
+    }
+    Ok(reader
+        .map(|r| r.join().unwrap_or_default())
+        .unwrap_or_default())
+}
@@ -0,0 +1,333 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Install progress goes through a ProgressReporter so the installer carries no UI of its own:
+// the CLI plugs in indicatif bars or, with `--progress json`, newline-delimited JSON events on
+// stderr; embedders supply their own implementation. One reporter serves a whole install run and
+// is shared by the worker threads, so every callback names the tool it is about.
+//
+// JSON events, one object per line:
+//   {"ts":"…","tool":"helm","event":"download_progress","bytes":1048576,"total":16777216,"percent":6}
+// Events: install_started, skipped, download_started, download_progress, download_finished,
+// extract, verify, installed, failed.
+
+/// Intermediate steps of one tool's install.
+pub enum Stage<'a> {
+    Download {
+        url: &'a str,
+        total: Option<u64>,
+    },
+    Downloaded {
+        bytes: u64,
+        sha256: &'a str,
+        cached: bool,
+    },
+    Extract {
+        format: &'a str,
+    },
+    Verify {
+        expected: &'a str,
+    },
+}
+
+/// How a successful install ended.
+pub enum Outcome<'a> {
+    Installed,
+    /// A matching version was already present
+    Skipped {
+        installed: &'a str,
+    },
+}
+
+pub trait ProgressReporter: Send + Sync {
+    fn on_start(&self, tool: &str, version: &str);
+    fn on_stage(&self, _tool: &str, _stage: Stage<'_>) {}
+    /// Bytes downloaded so far for `tool`, out of `total` when the server sent a length.
+    fn on_bytes(&self, tool: &str, done: u64, total: Option<u64>);
+    fn on_finish(&self, tool: &str, version: &str, outcome: Outcome<'_>);
+    fn on_error(&self, tool: &str, version: &str, err: &anyhow::Error);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressMode {
+    #[default]
+    Bars,
+    Json,
+}
+
+static MODE: OnceCell<ProgressMode> = OnceCell::new();
+
+pub fn set_mode(mode: ProgressMode) {
+    let _ = MODE.set(mode);
+}
+
+pub fn mode() -> ProgressMode {
+    MODE.get().copied().unwrap_or_default()
+}
+
+/// Reporter for the CLI's `--progress` mode; bars are laid out for `tools` in order. With
+/// `--report` the events also go to the run report.
+pub fn for_mode(tools: &[(String, String)]) -> Arc<dyn ProgressReporter> {
+    let reporter: Arc<dyn ProgressReporter> = match mode() {
+        ProgressMode::Bars => Arc::new(IndicatifReporter::new(tools)),
+        ProgressMode::Json => Arc::new(JsonReporter::default()),
+    };
+    match crate::report::recorder() {
+        Some(recorder) => Arc::new(Tee(reporter, recorder)),
+        None => reporter,
+    }
+}
+
+/// Forwards every event to both reporters.
+pub struct Tee(pub Arc<dyn ProgressReporter>, pub Arc<dyn ProgressReporter>);
+
+impl ProgressReporter for Tee {
+    fn on_start(&self, tool: &str, version: &str) {
+        self.0.on_start(tool, version);
+        self.1.on_start(tool, version);
+    }
+
+    fn on_stage(&self, tool: &str, stage: Stage<'_>) {
+        let copy = match &stage {
+            Stage::Download { url, total } => Stage::Download { url, total: *total },
+            Stage::Downloaded {
+                bytes,
+                sha256,
+                cached,
+            } => Stage::Downloaded {
+                bytes: *bytes,
+                sha256,
+                cached: *cached,
+            },
+            Stage::Extract { format } => Stage::Extract { format },
+            Stage::Verify { expected } => Stage::Verify { expected },
+        };
+        self.0.on_stage(tool, stage);
+        self.1.on_stage(tool, copy);
+    }
+
+    fn on_bytes(&self, tool: &str, done: u64, total: Option<u64>) {
+        self.0.on_bytes(tool, done, total);
+        self.1.on_bytes(tool, done, total);
+    }
+
+    fn on_finish(&self, tool: &str, version: &str, outcome: Outcome<'_>) {
+        let copy = match &outcome {
+            Outcome::Installed => Outcome::Installed,
+            Outcome::Skipped { installed } => Outcome::Skipped { installed },
+        };
+        self.0.on_finish(tool, version, outcome);
+        self.1.on_finish(tool, version, copy);
+    }
+
+    fn on_error(&self, tool: &str, version: &str, err: &anyhow::Error) {
+        self.0.on_error(tool, version, err);
+        self.1.on_error(tool, version, err);
+    }
+}
+
+/// One spinner line per tool, switching to a byte bar while downloading.
+pub struct IndicatifReporter {
+    bars: HashMap<String, ProgressBar>,
+}
+
+impl IndicatifReporter {
+    pub fn new(tools: &[(String, String)]) -> Self {
+        let multi = MultiProgress::new();
+        let style = ProgressStyle::with_template("{spinner} {msg}").unwrap();
+        let bars = tools
+            .iter()
+            .map(|(name, version)| {
+                let pb = multi.add(ProgressBar::new_spinner());
+                pb.set_style(style.clone());
+                pb.set_message(format!("Starting {name} {version}"));
+                pb.enable_steady_tick(Duration::from_millis(120));
+                (name.clone(), pb)
+            })
+            .collect();
+        IndicatifReporter { bars }
+    }
+
+    fn bar(&self, tool: &str) -> Option<&ProgressBar> {
+        self.bars.get(tool)
+    }
+}
+
+impl ProgressReporter for IndicatifReporter {
+    fn on_start(&self, tool: &str, version: &str) {
+        if let Some(pb) = self.bar(tool) {
+            pb.set_message(format!("Installing {tool} {version}"));
+        }
+    }
+
+    fn on_stage(&self, tool: &str, stage: Stage<'_>) {
+        let Some(pb) = self.bar(tool) else {
+            return;
+        };
+        match stage {
+            Stage::Download { total, .. } => {
+                if let Some(total) = total {
+                    pb.set_length(total);
+                    pb.set_style(
+                        ProgressStyle::with_template(
+                            "{spinner} {msg} {bytes}/{total_bytes} ({bytes_per_sec})",
+                        )
+                        .unwrap(),
+                    );
+                }
+                pb.set_message(format!("GET {tool}"));
+            }
+            Stage::Downloaded { .. } => {
+                pb.set_style(ProgressStyle::with_template("{spinner} {msg}").unwrap());
+            }
+            Stage::Extract { .. } => pb.set_message(format!("Extract {tool}")),
+            Stage::Verify { .. } => pb.set_message(format!("Verify {tool}")),
+        }
+    }
+
+    fn on_bytes(&self, tool: &str, done: u64, _total: Option<u64>) {
+        if let Some(pb) = self.bar(tool) {
+            pb.set_position(done);
+        }
+    }
+
+    fn on_finish(&self, tool: &str, version: &str, outcome: Outcome<'_>) {
+        if let Some(pb) = self.bar(tool) {
+            pb.finish_with_message(match outcome {
+                Outcome::Installed => format!("Installed {tool} {version}"),
+                Outcome::Skipped { installed } => format!("{tool} already at {installed} (skip)"),
+            });
+        }
+    }
+
+    fn on_error(&self, tool: &str, version: &str, err: &anyhow::Error) {
+        if let Some(pb) = self.bar(tool) {
+            pb.finish_with_message(format!("FAILED {tool} {version}: {err}"));
+        }
+    }
+}
+
+/// `--progress json`: download_progress is throttled to whole percents when the size is known,
+/// otherwise to one event per MiB.
+#[derive(Default)]
+pub struct JsonReporter {
+    last_step: Mutex<HashMap<String, u64>>,
+}
+
+impl JsonReporter {
+    fn emit(&self, tool: &str, event: &str, fields: Value) {
+        let mut line = json!({
+            "ts": crate::fixture::now().to_rfc3339(),
+            "tool": tool,
+            "event": event,
+        });
+        if let (Some(obj), Value::Object(extra)) = (line.as_object_mut(), fields) {
+            obj.extend(extra);
+        }
+        // Whole lines only: stderr's lock spans the write.
+        let mut err = std::io::stderr().lock();
+        let _ = writeln!(err, "{line}");
+    }
+}
+
+impl ProgressReporter for JsonReporter {
+    fn on_start(&self, tool: &str, version: &str) {
+        self.emit(tool, "install_started", json!({ "version": version }));
+    }
+
+    fn on_stage(&self, tool: &str, stage: Stage<'_>) {
+        match stage {
+            Stage::Download { url, total } => self.emit(
+                tool,
+                "download_started",
+                json!({ "url": url, "total": total }),
+            ),
+            Stage::Downloaded {
+                bytes,
+                sha256,
+                cached,
+            } => self.emit(
+                tool,
+                "download_finished",
+                json!({ "bytes": bytes, "sha256": sha256, "cached": cached }),
+            ),
+            Stage::Extract { format } => self.emit(tool, "extract", json!({ "format": format })),
+            Stage::Verify { expected } => {
+                self.emit(tool, "verify", json!({ "expected": expected }))
+            }
+        }
+    }
+
+    fn on_bytes(&self, tool: &str, done: u64, total: Option<u64>) {
+        let percent = total.filter(|t| *t > 0).map(|t| done * 100 / t);
+        let step = percent.unwrap_or(done >> 20);
+        {
+            let mut last = self.last_step.lock().unwrap();
+            let prev = last.entry(tool.to_string()).or_insert(0);
+            if step <= *prev {
+                return;
+            }
+            *prev = step;
+        }
+        self.emit(
+            tool,
+            "download_progress",
+            json!({ "bytes": done, "total": total, "percent": percent }),
+        );
+    }
+
+    fn on_finish(&self, tool: &str, version: &str, outcome: Outcome<'_>) {
+        match outcome {
+            Outcome::Installed => self.emit(tool, "installed", json!({ "version": version })),
+            Outcome::Skipped { installed } => {
+                self.emit(tool, "skipped", json!({ "installed": installed }))
+            }
+        }
+    }
+
+    fn on_error(&self, tool: &str, _version: &str, err: &anyhow::Error) {
+        self.emit(tool, "failed", json!({ "error": format!("{err:#}") }));
+    }
+}
+
+/// Reader adapter forwarding download progress to a reporter as bytes flow through it.
+pub struct ProgressReader<'a, R> {
+    inner: R,
+    tool: &'a str,
+    reporter: &'a dyn ProgressReporter,
+    total: Option<u64>,
+    read: u64,
+}
+
+impl<'a, R> ProgressReader<'a, R> {
+    pub fn new(
+        inner: R,
+        tool: &'a str,
+        reporter: &'a dyn ProgressReporter,
+        total: Option<u64>,
+    ) -> Self {
+        ProgressReader {
+            inner,
+            tool,
+            reporter,
+            total,
+            read: 0,
+        }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.read += n as u64;
+            self.reporter.on_bytes(self.tool, self.read, self.total);
+        }
+        Ok(n)
+    }
+}
@@ -0,0 +1,252 @@
+use crate::config::Tool;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Install receipts: every install writes .tlk/receipts/<tool>.json recording where the artifact
+// came from and everything it put on disk (binary, unpacked toolchain, command shims) plus the
+// environment the tool exports through `tlk env`. Uninstall removes exactly what the receipt
+// lists, and `tlk diagnose --kind receipts` reports receipts whose files were deleted by hand.
+
+/// What an install wrote, filled in by the installer.
+#[derive(Debug, Default)]
+pub struct Written {
+    /// URL the artifact was downloaded from (none for corepack shims)
+    pub source: Option<String>,
+    pub sha256: Option<String>,
+    pub files: Vec<PathBuf>,
+    pub dirs: Vec<PathBuf>,
+    pub shims: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Receipt {
+    pub tool: String,
+    pub version: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    pub installed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// Directories removed as a whole on uninstall (unpacked toolchains)
+    #[serde(default)]
+    pub dirs: Vec<PathBuf>,
+    #[serde(default)]
+    pub shims: Vec<PathBuf>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Receipt {
+    /// Recorded paths that no longer exist.
+    pub fn missing(&self) -> Vec<&Path> {
+        self.files
+            .iter()
+            .chain(&self.dirs)
+            .chain(&self.shims)
+            .map(PathBuf::as_path)
+            .filter(|p| !p.exists())
+            .collect()
+    }
+}
+
+fn receipts_dir() -> Option<PathBuf> {
+    let root = crate::installer::project_root().or_else(|| std::env::current_dir().ok())?;
+    Some(root.join(".tlk").join("receipts"))
+}
+
+fn path_for(name: &str) -> Option<PathBuf> {
+    receipts_dir().map(|d| d.join(format!("{name}.json")))
+}
+
+/// Record the install of `tool`. A receipt that cannot be written is only a warning: the tool is
+/// installed, and uninstall falls back to the paths it derives from the config.
+pub fn record(tool: &Tool, written: Written) {
+    let (version, _) = crate::installer::normalize_version(&tool.version);
+    let receipt = Receipt {
+        tool: tool.name.clone(),
+        version,
+        source: written.source,
+        sha256: written.sha256,
+        installed_at: crate::fixture::now(),
+        files: written.files,
+        dirs: written.dirs,
+        shims: written.shims,
+        env: tool
+            .env
+            .iter()
+            .flatten()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+    };
+    if let Err(e) = save(&receipt) {
+        eprintln!(
+            "Warning: could not write the install receipt for {}: {e:#}",
+            tool.name
+        );
+    }
+}
+
+fn save(receipt: &Receipt) -> Result<()> {
+    let path = path_for(&receipt.tool).context("no project directory")?;
+    fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    let tmp = path.with_extension(format!("json.tmp{}", std::process::id()));
+    fs::write(&tmp, serde_json::to_string_pretty(receipt)? + "\n")?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// `tlk rename`: move the receipt of `old` to `new`, pointing its binary entry at `new_bin`.
+/// Returns false when `old` has no receipt.
+pub fn rename(old: &str, new: &str, old_bin: &Path, new_bin: &Path) -> Result<bool> {
+    let Some(mut receipt) = load(old)? else {
+        return Ok(false);
+    };
+    if path_for(new).is_some_and(|p| p.exists()) {
+        anyhow::bail!("an install receipt for '{new}' already exists");
+    }
+    receipt.tool = new.to_string();
+    for file in receipt.files.iter_mut().filter(|f| f.as_path() == old_bin) {
+        *file = new_bin.to_path_buf();
+    }
+    save(&receipt)?;
+    if let Some(path) = path_for(old) {
+        fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+    }
+    Ok(true)
+}
+
+pub fn load(name: &str) -> Result<Option<Receipt>> {
+    let Some(path) = path_for(name).filter(|p| p.is_file()) else {
+        return Ok(None);
+    };
+    let data = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+    let receipt =
+        serde_json::from_slice(&data).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(Some(receipt))
+}
+
+pub fn all() -> Result<Vec<Receipt>> {
+    let Some(dir) = receipts_dir() else {
+        return Ok(Vec::new());
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|e| {
+            e.file_name()
+                .to_str()?
+                .strip_suffix(".json")
+                .map(str::to_string)
+        })
+        .collect();
+    names.sort();
+    let mut out = Vec::new();
+    for name in names {
+        out.extend(load(&name)?);
+    }
+    Ok(out)
+}
+
+// `path` with its parent resolved (the entry itself may be a symlink, which is removed rather than
+// followed), when that is strictly inside one of `roots`. None when there is nothing to remove.
+fn contained(path: &Path, roots: &[PathBuf]) -> Result<Option<PathBuf>> {
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+        anyhow::bail!("refusing to remove {}", path.display());
+    };
+    let Ok(parent) = parent.canonicalize() else {
+        return Ok(None);
+    };
+    let resolved = parent.join(file_name);
+    if resolved.symlink_metadata().is_err() {
+        return Ok(None);
+    }
+    if !roots
+        .iter()
+        .any(|r| resolved.starts_with(r) && resolved != *r)
+    {
+        anyhow::bail!(
+            "refusing to remove {}: it is outside the tool's install directories",
+            path.display()
+        );
+    }
+    Ok(Some(resolved))
+}
+
+/// Remove everything the receipt for `name` lists, then the receipt. Returns false when there is
+/// no receipt. Every listed path must lie inside the tool's install directories; a receipt naming
+/// anything else (edited by hand, or from another checkout) removes nothing.
+pub fn revert(name: &str) -> Result<bool> {
+    let Some(receipt) = load(name)? else {
+        return Ok(false);
+    };
+    let roots = crate::installer::install_roots(name);
+    let check = |paths: &[PathBuf]| -> Result<Vec<PathBuf>> {
+        let mut out = Vec::new();
+        for path in paths {
+            out.extend(contained(path, &roots).map_err(|e| {
+                anyhow::anyhow!(
+                    "{e} (listed in .tlk/receipts/{name}.json; delete the receipt to remove the \
+                     paths tlk.toml implies instead)"
+                )
+            })?);
+        }
+        Ok(out)
+    };
+    let files = check(&[receipt.files.as_slice(), receipt.shims.as_slice()].concat())?;
+    let dirs = check(&receipt.dirs)?;
+    for path in &files {
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("removing {}", path.display())),
+        }
+    }
+    for dir in &dirs {
+        if dir.exists() {
+            fs::remove_dir_all(dir).with_context(|| format!("removing {}", dir.display()))?;
+        }
+    }
+    if let Some(path) = path_for(name) {
+        fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+    }
+    Ok(true)
+}
+
+/// `tlk diagnose --kind receipts`: installed tools whose recorded files are gone.
+pub fn diagnose() -> Result<()> {
+    let receipts = all()?;
+    let mut broken = 0;
+    for receipt in &receipts {
+        let missing = receipt.missing();
+        if missing.is_empty() {
+            continue;
+        }
+        broken += 1;
+        println!(
+            "{} {}: {} recorded path(s) missing (run 'tlk install --reinstall {}'):",
+            receipt.tool,
+            receipt.version,
+            missing.len(),
+            receipt.tool
+        );
+        for path in missing {
+            println!("  {}", path.display());
+        }
+    }
+    if broken == 0 {
+        println!(
+            "All {} install receipt(s) match the files on disk",
+            receipts.len()
+        );
+        return Ok(());
+    }
+    anyhow::bail!("{broken} tool(s) have files missing since they were installed");
+}
@@ -0,0 +1,199 @@
+use crate::progress::{Outcome, ProgressReporter, Stage};
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+// `--report FILE` on install / update: a machine-readable record of the run for build telemetry,
+// independent of the stdout / --progress formatting. The recorder sees the same events as the
+// progress reporter (progress::for_mode tees them here) and is written out once the command ends,
+// whether it succeeded or not.
+
+static RECORDER: OnceCell<Arc<Recorder>> = OnceCell::new();
+
+/// Start recording this run; every reporter handed out afterwards also feeds the report.
+pub fn enable() {
+    let _ = RECORDER.set(Arc::new(Recorder {
+        started: crate::fixture::now(),
+        clock: Instant::now(),
+        tools: Mutex::new(Vec::new()),
+    }));
+}
+
+pub fn recorder() -> Option<Arc<Recorder>> {
+    RECORDER.get().cloned()
+}
+
+pub struct Recorder {
+    started: chrono::DateTime<chrono::Utc>,
+    clock: Instant,
+    tools: Mutex<Vec<ToolRecord>>,
+}
+
+#[derive(Default)]
+struct ToolRecord {
+    name: String,
+    version: String,
+    action: &'static str,
+    started: Option<Instant>,
+    duration_ms: u128,
+    url: Option<String>,
+    bytes_downloaded: u64,
+    cache_hit: bool,
+    sha256: Option<String>,
+    installed: Option<String>,
+    error: Option<String>,
+}
+
+impl Recorder {
+    fn with(&self, tool: &str, f: impl FnOnce(&mut ToolRecord)) {
+        let mut tools = self.tools.lock().unwrap();
+        // A tool can run more than once per command (update installs, then re-locks); the latest
+        // attempt is the one that counts.
+        let idx = match tools.iter().rposition(|t| t.name == tool) {
+            Some(idx) => idx,
+            None => {
+                tools.push(ToolRecord {
+                    name: tool.to_string(),
+                    ..Default::default()
+                });
+                tools.len() - 1
+            }
+        };
+        f(&mut tools[idx]);
+    }
+
+    fn finish(&self, tool: &str, action: &'static str) {
+        self.with(tool, |t| {
+            t.action = action;
+            t.duration_ms = t.started.map_or(0, |s| s.elapsed().as_millis());
+        });
+    }
+}
+
+impl ProgressReporter for Recorder {
+    fn on_start(&self, tool: &str, version: &str) {
+        let mut tools = self.tools.lock().unwrap();
+        tools.retain(|t| t.name != tool);
+        tools.push(ToolRecord {
+            name: tool.to_string(),
+            version: version.to_string(),
+            action: "started",
+            started: Some(Instant::now()),
+            ..Default::default()
+        });
+    }
+
+    fn on_stage(&self, tool: &str, stage: Stage<'_>) {
+        match stage {
+            Stage::Download { url, .. } => self.with(tool, |t| t.url = Some(url.to_string())),
+            Stage::Downloaded {
+                bytes,
+                sha256,
+                cached,
+            } => self.with(tool, |t| {
+                t.cache_hit = cached;
+                if !cached {
+                    t.bytes_downloaded += bytes;
+                }
+                t.sha256 = Some(sha256.to_string());
+            }),
+            Stage::Extract { .. } | Stage::Verify { .. } => {}
+        }
+    }
+
+    fn on_bytes(&self, _tool: &str, _done: u64, _total: Option<u64>) {}
+
+    fn on_finish(&self, tool: &str, _version: &str, outcome: Outcome<'_>) {
+        match outcome {
+            Outcome::Installed => self.finish(tool, "installed"),
+            Outcome::Skipped { installed } => {
+                self.with(tool, |t| t.installed = Some(installed.to_string()));
+                self.finish(tool, "skipped");
+            }
+        }
+    }
+
+    fn on_error(&self, tool: &str, _version: &str, err: &anyhow::Error) {
+        self.with(tool, |t| t.error = Some(format!("{err:#}")));
+        self.finish(tool, "failed");
+    }
+}
+
+/// Write the report for `command` to `path`; `outcome` is the command's own result.
+pub fn write(path: &str, command: &str, lock_path: &str, outcome: &Result<()>) -> Result<()> {
+    let Some(report) = render(command, lock_path, outcome) else {
+        return Ok(());
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&report)? + "\n")
+        .with_context(|| format!("writing report {path}"))
+}
+
+/// ~/.tlk/last-report.json: the report of the latest install / update, kept for `tlk bugreport`.
+pub fn last_report_path() -> Option<std::path::PathBuf> {
+    crate::platform::platform()
+        .home_dir()
+        .map(|h| h.join(".tlk").join("last-report.json"))
+}
+
+/// Keep this run's report as the last one; best effort, like the install stamp.
+pub fn keep_last(command: &str, lock_path: &str, outcome: &Result<()>) {
+    let (Some(path), Some(report)) = (last_report_path(), render(command, lock_path, outcome))
+    else {
+        return;
+    };
+    if let (Some(dir), Ok(text)) = (path.parent(), serde_json::to_string_pretty(&report)) {
+        let _ = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, text + "\n"));
+    }
+}
+
+fn render(command: &str, lock_path: &str, outcome: &Result<()>) -> Option<serde_json::Value> {
+    let rec = recorder()?;
+    let tools = rec.tools.lock().unwrap();
+    let count = |action: &str| tools.iter().filter(|t| t.action == action).count();
+    let items: Vec<_> = tools
+        .iter()
+        .map(|t| {
+            json!({
+                "name": t.name,
+                "version": t.version,
+                "action": t.action,
+                "duration_ms": t.duration_ms,
+                "url": t.url,
+                "bytes_downloaded": t.bytes_downloaded,
+                "cache_hit": t.cache_hit,
+                "sha256": t.sha256,
+                "installed": t.installed,
+                "error": t.error,
+            })
+        })
+        .collect();
+    let lock_sha256 = std::fs::read(lock_path)
+        .ok()
+        .map(|data| hex::encode(Sha256::digest(data)));
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    totals.insert(
+        "bytes_downloaded",
+        tools.iter().map(|t| t.bytes_downloaded).sum(),
+    );
+    totals.insert(
+        "cache_hits",
+        tools.iter().filter(|t| t.cache_hit).count() as u64,
+    );
+    for action in ["installed", "skipped", "failed"] {
+        totals.insert(action, count(action) as u64);
+    }
+    Some(json!({
+        "command": command,
+        "started": rec.started.to_rfc3339(),
+        "duration_ms": rec.clock.elapsed().as_millis(),
+        "success": outcome.is_ok(),
+        "error": outcome.as_ref().err().map(|e| format!("{e:#}")),
+        "tools": items,
+        "totals": totals,
+        "lock": { "path": lock_path, "sha256": lock_sha256 },
+    }))
+}
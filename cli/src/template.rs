@@ -0,0 +1,128 @@
+use crate::naming::NamingScheme;
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Per-tool named mapping tables usable as template filters, e.g. `[tools.foo.maps.x64] amd64 = "x64"`
+/// lets a source say `{arch|x64}`.
+pub type TemplateMaps = HashMap<String, HashMap<String, String>>;
+
+// {version}, {os}, {arch} optionally followed by one or more |filter segments.
+static PLACEHOLDER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{(version|os|arch)((?:\|[A-Za-z0-9_-]+)*)\}").unwrap());
+
+/// Expand placeholders in a source template. Filters apply left to right: tool-declared
+/// maps first, then naming schemes (`{arch|gnu-style}`) and the built-ins `upper`, `lower`
+/// and `title`. Values missing from a map pass through unchanged; unknown filters are an error.
+pub fn render(
+    template: &str,
+    version: &str,
+    os: &str,
+    arch: &str,
+    maps: Option<&TemplateMaps>,
+) -> Result<String> {
+    let mut err = None;
+    let out = PLACEHOLDER.replace_all(template, |caps: &regex::Captures| {
+        let mut value = match &caps[1] {
+            "version" => version.to_string(),
+            "os" => os.to_string(),
+            _ => arch.to_string(),
+        };
+        for filter in caps[2].split('|').filter(|f| !f.is_empty()) {
+            match apply_filter(filter, &value, maps) {
+                Ok(v) => value = v,
+                Err(e) => {
+                    err.get_or_insert(e);
+                }
+            }
+        }
+        value
+    });
+    match err {
+        Some(e) => Err(e),
+        None => Ok(out.into_owned()),
+    }
+}
+
+/// Check a template's filters without rendering it for a particular platform.
+pub fn check(template: &str, maps: Option<&TemplateMaps>) -> Result<()> {
+    render(template, "0.0.0", "linux", "amd64", maps).map(|_| ())
+}
+
+fn apply_filter(filter: &str, value: &str, maps: Option<&TemplateMaps>) -> Result<String> {
+    if let Some(table) = maps.and_then(|m| m.get(filter)) {
+        return Ok(table
+            .get(value)
+            .cloned()
+            .unwrap_or_else(|| value.to_string()));
+    }
+    if let Some(scheme) = NamingScheme::from_name(filter) {
+        return Ok(scheme.os(scheme.arch(value)).to_string());
+    }
+    Ok(match filter {
+        "upper" => value.to_uppercase(),
+        "lower" => value.to_lowercase(),
+        "title" => {
+            let mut chars = value.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+        _ => bail!(
+            "unknown template filter '{filter}' (use a [maps] table name, a naming scheme, upper, lower or title)"
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maps() -> TemplateMaps {
+        let x64 = HashMap::from([("amd64".to_string(), "x64".to_string())]);
+        HashMap::from([("x64".to_string(), x64)])
+    }
+
+    #[test]
+    fn plain_placeholders() {
+        assert_eq!(
+            render("t-{version}-{os}-{arch}.tgz", "1.2.3", "linux", "amd64", None).unwrap(),
+            "t-1.2.3-linux-amd64.tgz"
+        );
+    }
+
+    #[test]
+    fn filters_chain_left_to_right() {
+        assert_eq!(
+            render("{os|title}_{arch|gnu-style|upper}", "1", "darwin", "amd64", None).unwrap(),
+            "Darwin_X86_64"
+        );
+        assert_eq!(
+            render("{os|node-style}-{os|macos-style}", "1", "windows", "arm64", None).unwrap(),
+            "win-windows"
+        );
+    }
+
+    #[test]
+    fn maps_apply_before_built_ins_and_pass_misses_through() {
+        let maps = maps();
+        assert_eq!(
+            render("{arch|x64|upper}", "1", "linux", "amd64", Some(&maps)).unwrap(),
+            "X64"
+        );
+        assert_eq!(
+            render("{arch|x64}", "1", "linux", "arm64", Some(&maps)).unwrap(),
+            "arm64"
+        );
+    }
+
+    #[test]
+    fn unknown_filters_are_errors() {
+        let err = render("{arch|amd}", "1", "linux", "amd64", None).unwrap_err();
+        assert!(err.to_string().contains("'amd'"), "{err}");
+        assert!(check("{arch|x64}", None).is_err());
+        assert!(check("{arch|x64}", Some(&maps())).is_ok());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::cache;
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Download bandwidth limits. `--limit-rate` / `[settings] limit_rate` caps every download of the
+// run together, so parallel installs share one budget instead of each getting the full rate; a
+// tool's own `limit_rate` caps its download on top of that. Limits are token buckets holding at
+// most one second of budget, drained as the response body is read.
+
+// Reads are split so a bucket is drained in small steps and the rate stays smooth.
+const CHUNK: usize = 16 * 1024;
+
+// The run-wide rate as given, parsed on the first download so a bad value only fails commands
+// that download anything.
+static GLOBAL_SPEC: OnceCell<String> = OnceCell::new();
+static GLOBAL: OnceCell<Option<Arc<Bucket>>> = OnceCell::new();
+
+pub fn set_global(rate: Option<String>) {
+    if let Some(rate) = rate {
+        let _ = GLOBAL_SPEC.set(rate);
+    }
+}
+
+fn global() -> Result<Option<Arc<Bucket>>> {
+    GLOBAL
+        .get_or_try_init(|| {
+            let Some(spec) = GLOBAL_SPEC.get() else {
+                return Ok(None);
+            };
+            let rate = parse_rate(spec)?;
+            Ok((rate > 0).then(|| Arc::new(Bucket::new(rate))))
+        })
+        .cloned()
+}
+
+/// Parse rates like "2M", "500K" or "1.5MB/s" into bytes per second.
+pub fn parse_rate(s: &str) -> Result<u64> {
+    let t = s.trim();
+    let t = t.strip_suffix("/s").unwrap_or(t);
+    cache::parse_size(t).with_context(|| format!("invalid rate '{s}' (e.g. 2M or 500K)"))
+}
+
+pub struct Bucket {
+    rate: f64,
+    // (available bytes, last refill); goes negative while readers sleep off their debt
+    state: Mutex<(f64, Instant)>,
+}
+
+impl Bucket {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let rate = bytes_per_sec as f64;
+        Self {
+            rate,
+            state: Mutex::new((rate, Instant::now())),
+        }
+    }
+
+    fn take(&self, n: usize) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let (available, last) = &mut *state;
+            let now = Instant::now();
+            let refill = now.duration_since(*last).as_secs_f64() * self.rate;
+            *available = (*available + refill).min(self.rate) - n as f64;
+            *last = now;
+            if *available < 0.0 {
+                Duration::from_secs_f64(-*available / self.rate)
+            } else {
+                Duration::ZERO
+            }
+        };
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+/// `inner` limited by the global rate and `tool_rate` (bytes per second), when set.
+pub fn reader<R: Read>(inner: R, tool_rate: Option<u64>) -> Result<Throttled<R>> {
+    let buckets = global()?
+        .into_iter()
+        .chain(
+            tool_rate
+                .filter(|r| *r > 0)
+                .map(|r| Arc::new(Bucket::new(r))),
+        )
+        .collect();
+    Ok(Throttled { inner, buckets })
+}
+
+pub struct Throttled<R> {
+    inner: R,
+    buckets: Vec<Arc<Bucket>>,
+}
+
+impl<R: Read> Read for Throttled<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.buckets.is_empty() {
+            return self.inner.read(buf);
+        }
+        let len = buf.len().min(CHUNK);
+        let n = self.inner.read(&mut buf[..len])?;
+        for bucket in &self.buckets {
+            bucket.take(n);
+        }
+        Ok(n)
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Scratch space for downloads / extractions: .tlk/tmp/<id> directories in the project root (the
+// directory holding tlk.toml, else the working directory), recorded in .tlk/tmp/journal (one
+// "<id> <pid> <unix secs>" line each). Dirs are removed when the owning TempDir drops; dirs left
+// behind by a crashed run (owner process gone, or older than STALE_AFTER where liveness cannot be
+// checked) are swept when the next install / update / uninstall takes the project lock, and by
+// `tlk clean --tmp`. Journal rewrites hold
+// JOURNAL_LOCK against other threads and an exclusive lock on .tlk/tmp/journal.lck against other
+// tlk processes, so concurrent runs never drop each other's entries.
+
+const JOURNAL: &str = "journal";
+const JOURNAL_LCK: &str = "journal.lck";
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+static JOURNAL_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn tmp_root() -> PathBuf {
+    let root = crate::installer::project_root()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+        .join(".tlk")
+        .join("tmp");
+    crate::platform::platform().long_path(&root)
+}
+
+// Held while the journal is read and rewritten.
+struct JournalGuard {
+    _thread: MutexGuard<'static, ()>,
+    _file: fs::File,
+}
+
+fn lock_journal(root: &Path) -> Result<JournalGuard> {
+    let thread = JOURNAL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    fs::create_dir_all(root).with_context(|| format!("creating {root:?}"))?;
+    let path = root.join(JOURNAL_LCK);
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("opening {path:?}"))?;
+    file.lock().with_context(|| format!("locking {path:?}"))?;
+    Ok(JournalGuard {
+        _thread: thread,
+        _file: file,
+    })
+}
+
+pub struct TempDir {
+    id: String,
+    root: PathBuf,
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Result<Self> {
+        static SEQ: AtomicUsize = AtomicUsize::new(0);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let id = format!(
+            "{:x}-{:x}-{}",
+            std::process::id(),
+            now.as_nanos(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        );
+        let root = tmp_root();
+        let path = root.join(&id);
+        // Journal before creating so a concurrent sweep never sees it as an orphan.
+        {
+            let _guard = lock_journal(&root)?;
+            let mut entries = read_journal(&root);
+            entries.push(JournalEntry {
+                id: id.clone(),
+                pid: std::process::id(),
+                started: now.as_secs(),
+            });
+            write_journal(&root, &entries)?;
+        }
+        fs::create_dir_all(&path).with_context(|| format!("creating {path:?}"))?;
+        Ok(TempDir { id, root, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+        let Ok(_guard) = lock_journal(&self.root) else {
+            return;
+        };
+        let mut entries = read_journal(&self.root);
+        entries.retain(|e| e.id != self.id);
+        let _ = write_journal(&self.root, &entries);
+    }
+}
+
+/// Move a finished file into place; falls back to copy when `dest` is on another filesystem.
+pub fn persist(src: &Path, dest: &Path) -> Result<()> {
+    if fs::rename(src, dest).is_ok() {
+        return Ok(());
+    }
+    fs::copy(src, dest).with_context(|| format!("copying {src:?} to {dest:?}"))?;
+    let _ = fs::remove_file(src);
+    Ok(())
+}
+
+/// Remove temp dirs left by crashed runs. With `all`, also removes dirs whose owner cannot be
+/// shown to be dead (still sparing those of running processes when that can be checked).
+/// Returns the number of dirs removed.
+pub fn sweep(all: bool) -> Result<usize> {
+    let root = tmp_root();
+    if !root.exists() {
+        return Ok(0);
+    }
+    let _guard = lock_journal(&root)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut keep = Vec::new();
+    let mut stale = Vec::new();
+    for e in read_journal(&root) {
+        let owned_by_us = e.pid == std::process::id();
+        let dead = match pid_alive(e.pid) {
+            Some(alive) => !alive,
+            None => all || now.saturating_sub(e.started) > STALE_AFTER.as_secs(),
+        };
+        if !owned_by_us && dead {
+            stale.push(e.id);
+        } else {
+            keep.push(e);
+        }
+    }
+    let mut removed = 0;
+    for entry in fs::read_dir(&root)?.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == JOURNAL || keep.iter().any(|e| e.id == name) {
+            continue;
+        }
+        // Journaled dirs of dead owners, and old orphans the journal never recorded.
+        let orphan_expired = entry.path().is_dir()
+            && (all
+                || entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|m| m.elapsed().ok())
+                    .is_some_and(|age| age > STALE_AFTER));
+        if stale.contains(&name) || orphan_expired {
+            fs::remove_dir_all(entry.path())
+                .with_context(|| format!("removing {:?}", entry.path()))?;
+            removed += 1;
+        }
+    }
+    write_journal(&root, &keep)?;
+    Ok(removed)
+}
+
+struct JournalEntry {
+    id: String,
+    pid: u32,
+    started: u64,
+}
+
+fn read_journal(root: &Path) -> Vec<JournalEntry> {
+    let raw = fs::read_to_string(root.join(JOURNAL)).unwrap_or_default();
+    raw.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            Some(JournalEntry {
+                id: parts.next()?.to_string(),
+                pid: parts.next()?.parse().ok()?,
+                started: parts.next()?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn write_journal(root: &Path, entries: &[JournalEntry]) -> Result<()> {
+    let body: String = entries
+        .iter()
+        .map(|e| format!("{} {} {}\n", e.id, e.pid, e.started))
+        .collect();
+    fs::write(root.join(JOURNAL), body).with_context(|| "writing tmp journal")
+}
+
+// Liveness is only knowable where /proc exists; elsewhere callers fall back to age.
+fn pid_alive(pid: u32) -> Option<bool> {
+    let proc_root = Path::new("/proc");
+    if !proc_root.join("self").exists() {
+        return None;
+    }
+    Some(proc_root.join(pid.to_string()).exists())
+}
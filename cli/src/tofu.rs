@@ -0,0 +1,90 @@
+use crate::config::host_platform_key;
+use crate::config::Tool;
+use crate::installer::normalize_version;
+use crate::lock::{LockFile, LockedEntry};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Trust-on-first-use checksums for tools that publish none (`[settings] tofu = true`, or
+// `tofu = true` on a tool). The first download's sha256 is pinned in tlk.lock under
+// `tofu_sha256`, keyed by platform (`host_platform_key()`, like `sources` and per-platform
+// `sha256` tables) because each platform fetches its own artifact. Later
+// downloads of the same version must match the pin for their platform on any machine; a new
+// version starts unpinned. `tlk lock bless <tool>` re-pins after an intentional upstream change.
+
+const LOCK_PATH: &str = "tlk.lock";
+
+// Pins as recorded when this process started (a run only ever adds to them).
+static PINNED: Lazy<HashMap<String, LockedEntry>> = Lazy::new(|| {
+    LockFile::load(LOCK_PATH)
+        .ok()
+        .flatten()
+        .map(|l| l.tools)
+        .unwrap_or_default()
+});
+
+// sha256 of artifacts downloaded this run: tool -> (exact version, sha256).
+static SEEN: Lazy<Mutex<HashMap<String, (String, String)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn enabled(tool: &Tool) -> bool {
+    tool.host_sha256().is_none() && tool.tofu == Some(true)
+}
+
+/// Check a downloaded artifact's sha256 against the pin for this platform, remembering it for
+/// the next lock write.
+pub fn check(tool: &Tool, sha256: &str) -> Result<()> {
+    if !enabled(tool) {
+        return Ok(());
+    }
+    let (exact, _) = normalize_version(&tool.version);
+    if let Some(pin) = pin(tool) {
+        if !pin.eq_ignore_ascii_case(sha256) {
+            anyhow::bail!(
+                "{} {exact}: download sha256 {sha256} does not match the TOFU pin {pin} in \
+                 {LOCK_PATH}; if upstream changed the artifact on purpose run 'tlk lock bless {}'",
+                tool.name,
+                tool.name
+            );
+        }
+    }
+    SEEN.lock()
+        .unwrap()
+        .insert(tool.name.clone(), (exact, sha256.to_string()));
+    Ok(())
+}
+
+/// The pin recorded for `tool`'s version on this platform when the run started.
+pub fn pin(tool: &Tool) -> Option<String> {
+    let (exact, _) = normalize_version(&tool.version);
+    PINNED
+        .get(&tool.name)
+        .filter(|e| e.version == exact)
+        .and_then(|e| e.tofu_sha256.as_ref()?.get(&host_platform_key()))
+        .cloned()
+}
+
+/// Pins to record for `tool` at `exact`: those of the previous entry (same version only) plus
+/// this platform's download from the current run.
+pub fn pins(
+    tool: &Tool,
+    exact: &str,
+    previous: Option<&LockedEntry>,
+) -> Option<HashMap<String, String>> {
+    if !enabled(tool) {
+        return None;
+    }
+    let mut pins = previous
+        .filter(|e| e.version == exact)
+        .and_then(|e| e.tofu_sha256.clone())
+        .unwrap_or_default();
+    if let Some((version, sha)) = SEEN.lock().unwrap().get(&tool.name) {
+        if version == exact {
+            pins.entry(host_platform_key())
+                .or_insert_with(|| sha.clone());
+        }
+    }
+    (!pins.is_empty()).then_some(pins)
+}
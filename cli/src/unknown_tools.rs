@@ -1,6 +1,5 @@
 use crate::config::Tool;
 use anyhow::{Context, Result};
-use crate::known_tools::{detect_arch, detect_os};
 
 // Unknown tools are user-declared entries. Supported syntaxes:
 // 1) NEW preferred: [tools.foo] version = "1.2.3" source = "..."
@@ -47,8 +46,8 @@ pub fn parse_unknown(root: &toml::Value) -> Result<Vec<Tool>> {
 pub fn augment_binary_fields(tools: &mut [Tool]) {
     for t in tools.iter_mut() {
         if t.binary.is_none() {
-            if t.name == "helm" { t.binary = Some(format!("{os}-{arch}/helm", os=detect_os(), arch=detect_arch())); }
-            else if t.name == "gh" { t.binary = Some(format!("gh_{v}_{os}_{arch}/bin/gh", v=t.version, os=detect_os(), arch=detect_arch())); }
+            if t.name == "helm" { t.binary = Some("*-*/helm".into()); }
+            else if t.name == "gh" { t.binary = Some("**/bin/gh".into()); }
         }
     }
 }
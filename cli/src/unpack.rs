@@ -0,0 +1,552 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+// Unpacking a toolchain archive next to an earlier toolchain of the same tool (delta updates).
+// A regular file whose size, mode and bytes match the file at the same path in `previous` is
+// hard-linked to it instead of written again, so a minor version bump of a large toolchain (node,
+// go) only writes the files that changed. The archive is still read whole: an entry is compared
+// against the old file as it streams, and only written out once the two differ. Paths below the
+// archive's top-level directory are matched first, since that directory is usually named after
+// the version and is dropped when the toolchain is moved into place.
+
+const CHUNK: usize = 64 * 1024;
+
+/// What an unpack reused from the previous toolchain.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Reused {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+pub fn tar<R: Read>(
+    archive: &mut tar::Archive<R>,
+    dest: &Path,
+    previous: Option<&Path>,
+) -> Result<Reused> {
+    fs::create_dir_all(dest)?;
+    let mut reused = Reused::default();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let size = entry.header().size()?;
+        let mode = entry.header().mode().ok();
+        let old = match (previous, entry.header().entry_type().is_file()) {
+            (Some(previous), true) => earlier_copy(previous, &path, size, mode),
+            _ => None,
+        };
+        let target = relative(&path).map(|rel| dest.join(rel));
+        match (old, target) {
+            (Some(old), Some(target)) if stays_inside(dest, &target) => {
+                if write_file(&mut entry, &target, &old, mode)? {
+                    reused.files += 1;
+                    reused.bytes += size;
+                }
+            }
+            // Directories, links, files new in this version, and paths that lead out of `dest`
+            // through a symlink unpacked earlier: tar's own checks apply to those.
+            _ => {
+                entry
+                    .unpack_in(dest)
+                    .with_context(|| format!("unpacking {path:?}"))?;
+            }
+        }
+    }
+    Ok(reused)
+}
+
+/// Unpack the zip in `file`, spreading entries over up to `threads` threads (each reads the file
+/// through its own cursor). Directories are created first, so the workers only write files.
+pub fn zip(file: &File, dest: &Path, previous: Option<&Path>, threads: usize) -> Result<Reused> {
+    let mut archive = zip::ZipArchive::new(At::new(file)?)?;
+    fs::create_dir_all(dest)?;
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        let path = entry
+            .enclosed_name()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| anyhow::anyhow!("invalid path {:?} in zip", entry.name()))?;
+        let target = dest.join(&path);
+        if entry.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            files.push((i, path));
+        }
+    }
+    let threads = threads.clamp(1, files.len().max(1));
+    let work = |shard: usize| -> Result<Reused> {
+        let mut archive = zip::ZipArchive::new(At::new(file)?)?;
+        let mut reused = Reused::default();
+        for (i, path) in files.iter().skip(shard).step_by(threads) {
+            let mut entry = archive.by_index(*i)?;
+            let target = dest.join(path);
+            let mode = entry.unix_mode();
+            let old = previous
+                .and_then(|p| earlier_copy(p, path, entry.size(), mode))
+                .filter(|_| stays_inside(dest, &target));
+            let linked = match old {
+                Some(old) => write_file(&mut entry, &target, &old, mode)?,
+                None => {
+                    io::copy(&mut entry, &mut File::create(&target)?)
+                        .with_context(|| format!("unpacking {path:?}"))?;
+                    set_mode(&target, mode)?;
+                    false
+                }
+            };
+            if linked {
+                reused.files += 1;
+                reused.bytes += entry.size();
+            }
+        }
+        Ok(reused)
+    };
+    let results: Vec<Result<Reused>> = if threads == 1 {
+        vec![work(0)]
+    } else {
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|shard| scope.spawn(move || work(shard)))
+                .collect();
+            workers
+                .into_iter()
+                .map(|w| {
+                    w.join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("unzip worker panicked")))
+                })
+                .collect()
+        })
+    };
+    let mut total = Reused::default();
+    for reused in results {
+        let reused = reused?;
+        total.files += reused.files;
+        total.bytes += reused.bytes;
+    }
+    Ok(total)
+}
+
+/// Threads used for unpacking zips: one per core, at most 8 (past that the disk is the limit).
+pub fn zip_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get().min(8))
+}
+
+// A reader over a shared file with its own position, using positioned reads so several can be
+// used at once (`File::try_clone` handles share one cursor).
+struct At<'a> {
+    file: &'a File,
+    pos: u64,
+    len: u64,
+}
+
+impl<'a> At<'a> {
+    fn new(file: &'a File) -> io::Result<Self> {
+        Ok(At {
+            file,
+            pos: 0,
+            len: file.metadata()?.len(),
+        })
+    }
+}
+
+impl Read for At<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        #[cfg(unix)]
+        let n = std::os::unix::fs::FileExt::read_at(self.file, buf, self.pos)?;
+        #[cfg(windows)]
+        let n = std::os::windows::fs::FileExt::seek_read(self.file, buf, self.pos)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for At<'_> {
+    fn seek(&mut self, to: io::SeekFrom) -> io::Result<u64> {
+        let pos = match to {
+            io::SeekFrom::Start(n) => Some(n),
+            io::SeekFrom::End(n) => self.len.checked_add_signed(n),
+            io::SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+        self.pos = pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek before the start of the file",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+// The file at `path` in the previous toolchain, when its size and permissions match.
+fn earlier_copy(previous: &Path, path: &Path, size: u64, mode: Option<u32>) -> Option<PathBuf> {
+    let rel = relative(path)?;
+    let below_top: PathBuf = rel.components().skip(1).collect();
+    [below_top, rel]
+        .into_iter()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(|p| previous.join(p))
+        .find(|old| {
+            fs::symlink_metadata(old)
+                .is_ok_and(|m| m.is_file() && m.len() == size && same_mode(&m, mode))
+                && stays_inside(previous, old)
+        })
+}
+
+// Whether `path` (below `root`) is not a symlink and its directory resolves inside `root`, so
+// writing it cannot follow a symlink out of the tree. Directories that do not exist yet are fine:
+// `relative` only lets plain components through, so they get created below the existing part.
+fn stays_inside(root: &Path, path: &Path) -> bool {
+    if fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()) {
+        return false;
+    }
+    let Ok(root) = root.canonicalize() else {
+        return false;
+    };
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        match d.canonicalize() {
+            Ok(real) => return real.starts_with(&root),
+            // A dangling symlink exists but cannot be resolved.
+            Err(_) if fs::symlink_metadata(d).is_ok() => return false,
+            Err(_) => dir = d.parent(),
+        }
+    }
+    false
+}
+
+// Plain relative form of an archive path, or None for absolute or `..` paths.
+fn relative(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+// Stream `entry` to `target`, hard-linking `old` instead when the bytes turn out identical.
+// Returns whether the file was linked.
+fn write_file(entry: &mut impl Read, target: &Path, old: &Path, mode: Option<u32>) -> Result<bool> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut previous = File::open(old)?;
+    let mut new_buf = vec![0; CHUNK];
+    let mut old_buf = vec![0; CHUNK];
+    let mut same: u64 = 0;
+    loop {
+        let n = read_full(entry, &mut new_buf)?;
+        if n == 0 {
+            break;
+        }
+        let m = read_full(&mut previous, &mut old_buf[..n])?;
+        if m != n || new_buf[..n] != old_buf[..n] {
+            // Differs from here on: the matching prefix is the old file's, the rest the entry's.
+            let mut out = File::create(target)?;
+            io::copy(&mut File::open(old)?.take(same), &mut out)?;
+            io::Write::write_all(&mut out, &new_buf[..n])?;
+            io::copy(entry, &mut out).with_context(|| format!("unpacking {target:?}"))?;
+            drop(out);
+            set_mode(target, mode)?;
+            return Ok(false);
+        }
+        same += n as u64;
+    }
+    if target.exists() {
+        fs::remove_file(target)?;
+    }
+    if fs::hard_link(old, target).is_err() {
+        // Filesystems without hard links (or across devices) get a plain copy.
+        fs::copy(old, target).with_context(|| format!("copying {old:?} to {target:?}"))?;
+    }
+    Ok(true)
+}
+
+fn read_full(r: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match r.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(unix)]
+fn same_mode(meta: &fs::Metadata, mode: Option<u32>) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    mode.is_none_or(|mode| meta.permissions().mode() & 0o777 == mode & 0o777)
+}
+
+#[cfg(not(unix))]
+fn same_mode(_: &fs::Metadata, _: Option<u32>) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_: &Path, _: Option<u32>) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tarball(top: &str, files: &[(&str, &[u8], u32)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data, mode) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(*mode);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("{top}/{name}"), *data)
+                .unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn unpack(bytes: &[u8], dest: &Path, previous: Option<&Path>) -> Reused {
+        tar(&mut tar::Archive::new(bytes), dest, previous).unwrap()
+    }
+
+    #[test]
+    fn unchanged_files_are_linked_from_the_previous_toolchain() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old");
+        let new = dir.path().join("new");
+        let big = vec![7u8; 3 * CHUNK + 5];
+        let mut changed = big.clone();
+        *changed.last_mut().unwrap() = 8;
+        unpack(
+            &tarball(
+                "tool-v1",
+                &[
+                    ("bin/tool", b"#!/bin/sh\necho 1\n", 0o755),
+                    ("lib/same", &big, 0o644),
+                    ("lib/tail", &big, 0o644),
+                    ("lib/mode", b"x", 0o644),
+                ],
+            ),
+            &old,
+            None,
+        );
+        let old = old.join("tool-v1");
+        let reused = unpack(
+            &tarball(
+                "tool-v2",
+                &[
+                    ("bin/tool", b"#!/bin/sh\necho 2\n", 0o755),
+                    ("lib/same", &big, 0o644),
+                    ("lib/tail", &changed, 0o644),
+                    ("lib/mode", b"x", 0o600),
+                    ("lib/added", b"new", 0o644),
+                ],
+            ),
+            &new,
+            Some(&old),
+        );
+        let new = new.join("tool-v2");
+        assert_eq!(
+            reused,
+            Reused {
+                files: 1,
+                bytes: big.len() as u64
+            }
+        );
+        assert_eq!(fs::read(new.join("lib/same")).unwrap(), big);
+        assert_eq!(fs::read(new.join("lib/tail")).unwrap(), changed);
+        assert_eq!(
+            fs::read(new.join("bin/tool")).unwrap(),
+            b"#!/bin/sh\necho 2\n"
+        );
+        assert_eq!(fs::read(new.join("lib/added")).unwrap(), b"new");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let linked = fs::metadata(new.join("lib/same")).unwrap();
+            assert_eq!(
+                linked.ino(),
+                fs::metadata(old.join("lib/same")).unwrap().ino()
+            );
+            let rewritten = fs::metadata(new.join("lib/tail")).unwrap();
+            assert_ne!(
+                rewritten.ino(),
+                fs::metadata(old.join("lib/tail")).unwrap().ino()
+            );
+            assert_eq!(
+                fs::metadata(new.join("lib/mode")).unwrap().mode() & 0o777,
+                0o600
+            );
+        }
+        // The old tree is untouched by the partial match.
+        assert_eq!(fs::read(old.join("lib/tail")).unwrap(), big);
+    }
+
+    fn zipfile(dir: &Path, files: &[(String, Vec<u8>)]) -> File {
+        use std::io::Write;
+        let path = dir.join("archive.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::FileOptions::default().unix_permissions(0o644);
+        for (name, data) in files {
+            writer.start_file(name.as_str(), options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+        File::open(path).unwrap()
+    }
+
+    #[test]
+    fn zip_entries_are_linked_too() {
+        let dir = tempfile::tempdir().unwrap();
+        let previous = dir.path().join("old");
+        fs::create_dir_all(previous.join("include")).unwrap();
+        fs::write(previous.join("include/a.proto"), "syntax = \"proto3\";").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::Permissions::from_mode(0o644);
+            fs::set_permissions(previous.join("include/a.proto"), mode).unwrap();
+        }
+        let archive = zipfile(
+            dir.path(),
+            &[
+                ("include/a.proto".into(), b"syntax = \"proto3\";".to_vec()),
+                ("include/b.proto".into(), b"package b;".to_vec()),
+            ],
+        );
+        let dest = dir.path().join("new");
+        let reused = zip(&archive, &dest, Some(&previous), 2).unwrap();
+        assert_eq!(reused.files, 1);
+        assert_eq!(
+            fs::read(dest.join("include/b.proto")).unwrap(),
+            b"package b;"
+        );
+    }
+
+    fn many_files(count: usize, size: usize) -> Vec<(String, Vec<u8>)> {
+        (0..count)
+            .map(|i| {
+                // xorshift noise, so deflate cannot shrink it away
+                let mut x = 0x9e37_79b9_7f4a_7c15u64 ^ i as u64;
+                let data = (0..size)
+                    .map(|_| {
+                        x ^= x << 13;
+                        x ^= x >> 7;
+                        x ^= x << 17;
+                        x as u8
+                    })
+                    .collect();
+                (format!("pkg/lib/file-{i}.bin"), data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parallel_zip_matches_serial() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = many_files(40, 5000);
+        let archive = zipfile(dir.path(), &files);
+        for threads in [1, 4] {
+            let dest = dir.path().join(format!("out-{threads}"));
+            zip(&archive, &dest, None, threads).unwrap();
+            for (name, data) in &files {
+                assert_eq!(&fs::read(dest.join(name)).unwrap(), data, "{name}");
+            }
+        }
+    }
+
+    // cargo test --release unpack::tests::bench -- --ignored --nocapture
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_zip_unpack() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = many_files(400, 512 * 1024);
+        let archive = zipfile(dir.path(), &files);
+        let size: usize = files.iter().map(|(_, data)| data.len()).sum();
+        for threads in [1, 2, 4, zip_threads()] {
+            let dest = dir.path().join(format!("out-{threads}"));
+            let start = std::time::Instant::now();
+            zip(&archive, &dest, None, threads).unwrap();
+            let fresh = start.elapsed();
+            let start = std::time::Instant::now();
+            zip(&archive, &dir.path().join("delta"), Some(&dest), threads).unwrap();
+            let delta = start.elapsed();
+            let _ = fs::remove_dir_all(dir.path().join("delta"));
+            println!(
+                "{threads} thread(s), {size} bytes unpacked: {:.0} MB/s fresh, {:.0} MB/s against an identical previous tree",
+                size as f64 / 1e6 / fresh.as_secs_f64(),
+                size as f64 / 1e6 / delta.as_secs_f64()
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_are_not_written_through() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("file"), b"secret").unwrap();
+        let previous = dir.path().join("old");
+        unpack(
+            &tarball("tool-v1", &[("dir/file", b"secret", 0o644)]),
+            &previous,
+            None,
+        );
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut link = tar::Header::new_gnu();
+        link.set_entry_type(tar::EntryType::Symlink);
+        link.set_size(0);
+        link.set_mode(0o777);
+        builder
+            .append_link(&mut link, "tool-v2/dir", &outside)
+            .unwrap();
+        let mut file = tar::Header::new_gnu();
+        file.set_size(6);
+        file.set_mode(0o644);
+        file.set_cksum();
+        builder
+            .append_data(&mut file, "tool-v2/dir/file", &b"public"[..])
+            .unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let dest = dir.path().join("new");
+        let _ = tar(
+            &mut tar::Archive::new(&archive[..]),
+            &dest,
+            Some(&previous.join("tool-v1")),
+        );
+        assert_eq!(fs::read(outside.join("file")).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn unsafe_paths_are_not_linked() {
+        assert_eq!(relative(Path::new("../etc/passwd")), None);
+        assert_eq!(relative(Path::new("/etc/passwd")), None);
+        assert_eq!(
+            relative(Path::new("./node/bin/node")),
+            Some(PathBuf::from("node/bin/node"))
+        );
+    }
+}
@@ -1,9 +1,76 @@
+use crate::http;
 use anyhow::Result;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use regex::Regex;
+use reqwest::blocking::RequestBuilder;
 use std::collections::HashMap;
 use std::sync::Mutex;
 
+// GitHub API calls authenticate with a token when one is available: the variable named by
+// `[settings] github_token_env`, then GITHUB_TOKEN, then GH_TOKEN. Authenticated calls get 5000
+// requests per hour instead of 60 and can read private repositories.
+static GITHUB_TOKEN_ENV: OnceCell<String> = OnceCell::new();
+
+pub fn set_github_token_env(name: Option<String>) {
+    if let Some(name) = name {
+        let _ = GITHUB_TOKEN_ENV.set(name);
+    }
+}
+
+fn github_token() -> Option<String> {
+    GITHUB_TOKEN_ENV
+        .get()
+        .map(String::as_str)
+        .into_iter()
+        .chain(["GITHUB_TOKEN", "GH_TOKEN"])
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|token| token.trim().to_string())
+        .find(|token| !token.is_empty())
+}
+
+fn github_request(client: &reqwest::blocking::Client, url: &str) -> RequestBuilder {
+    let req = client.get(url).header("User-Agent", "tlk");
+    match github_token() {
+        Some(token) => req.bearer_auth(token),
+        None => req,
+    }
+}
+
+/// API download URL of a release asset given its github.com/<owner>/<repo>/releases/download/
+/// <tag>/<asset> URL, for private repositories whose browser URLs 404 even with a token. None
+/// without a token or for other URLs.
+pub fn private_release_asset(url: &str) -> Result<Option<String>> {
+    if github_token().is_none() {
+        return Ok(None);
+    }
+    let Some(rest) = url.strip_prefix("https://github.com/") else {
+        return Ok(None);
+    };
+    let parts: Vec<&str> = rest.splitn(6, '/').collect();
+    let [owner, repo, "releases", "download", tag, asset] = parts[..] else {
+        return Ok(None);
+    };
+    let what = format!("reading the {owner}/{repo} release {tag}");
+    let path = format!("repos/{owner}/{repo}/releases/tags/{tag}");
+    let Some(release) = github_api(&path, &what, true)? else {
+        return Ok(None);
+    };
+    Ok(release
+        .get("assets")
+        .and_then(|a| a.as_array())
+        .into_iter()
+        .flatten()
+        .find(|a| a.get("name").and_then(|n| n.as_str()) == Some(asset))
+        .and_then(|a| a.get("url"))
+        .and_then(|u| u.as_str())
+        .map(str::to_string))
+}
+
+/// GET a release asset through the API (see [`private_release_asset`]).
+pub fn github_asset_request(client: &reqwest::blocking::Client, api_url: &str) -> RequestBuilder {
+    github_request(client, api_url).header("Accept", "application/octet-stream")
+}
+
 static VERSION_CACHE: Lazy<Mutex<HashMap<String, Vec<semver::Version>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
@@ -21,7 +88,147 @@ pub fn fetch_all_versions(name: &str) -> Result<Vec<semver::Version>> {
             return Ok(list.clone());
         }
     }
-    let raw: Vec<String> = match name {
+    let fixture = crate::fixture::dir().map(|d| d.to_string_lossy().into_owned());
+    let raw = match fixture.or_else(registry) {
+        Some(base) => registry_list(&base, name)?,
+        None => crate::cache::versions(name, || fetch_remote_list(name))?,
+    };
+    let mut parsed: Vec<semver::Version> = raw
+        .into_iter()
+        .filter_map(|s| semver::Version::parse(&s).ok())
+        .collect();
+    parsed.sort_by(|a, b| b.cmp(a));
+    let mut cache = VERSION_CACHE.lock().unwrap();
+    cache.insert(name.to_string(), parsed.clone());
+    Ok(parsed)
+}
+
+// TLK_REGISTRY points at a snapshot written by `tlk registry mirror` (a directory or an http(s)
+// base URL serving it). Version lists then come only from the snapshot, bypassing upstream and
+// the disk cache, so "latest" and ranges resolve within the blessed set on offline networks.
+pub fn registry() -> Option<String> {
+    std::env::var("TLK_REGISTRY").ok().filter(|v| !v.is_empty())
+}
+
+fn registry_list(base: &str, name: &str) -> Result<Vec<String>> {
+    let base = base.trim_end_matches('/');
+    let location = format!("{base}/versions/{name}.json");
+    let data = if base.starts_with("http://") || base.starts_with("https://") {
+        let resp = http::send(http::client()?.get(&location))?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("{name} is not in the registry snapshot at {base}");
+        }
+        resp.error_for_status()?.bytes()?.to_vec()
+    } else {
+        match std::fs::read(&location) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("{name} is not in the registry snapshot at {base}")
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    serde_json::from_slice(&data)
+        .map_err(|e| anyhow::anyhow!("invalid registry version list {location}: {e}"))
+}
+
+/// Whether `spec` names a release channel ("lts", "lts/*", "lts/<codename>", "current") rather
+/// than a version or range.
+pub fn is_channel(spec: &str) -> bool {
+    let s = spec.trim().to_ascii_lowercase();
+    s == "lts" || s == "current" || s.starts_with("lts/")
+}
+
+// Node release lines come from the nodejs/Release schedule: a line is LTS from its `lts` date until
+// its `end` date, and "current" is the newest release overall. `lts/<codename>` (e.g. lts/iron)
+// pins one line and is resolved even after it reached end of life, with a warning.
+pub fn resolve_channel(name: &str, spec: &str) -> Result<Option<String>> {
+    if !is_channel(spec) {
+        return Ok(None);
+    }
+    if name != "node" {
+        anyhow::bail!("release channel '{spec}' is only supported for node");
+    }
+    let channel = spec.trim().to_ascii_lowercase();
+    let all = fetch_all_versions(name)?;
+    if channel == "current" {
+        return Ok(all.first().map(|v| v.to_string()));
+    }
+    let today = crate::fixture::now().format("%Y-%m-%d").to_string();
+    let schedule = node_schedule()?;
+    let major = match channel.strip_prefix("lts/").filter(|c| *c != "*") {
+        Some(codename) => {
+            let line = schedule
+                .iter()
+                .find(|l| l.codename.eq_ignore_ascii_case(codename))
+                .ok_or_else(|| anyhow::anyhow!("unknown node LTS codename '{codename}'"))?;
+            if line.end.as_str() <= today.as_str() {
+                eprintln!(
+                    "Warning: node {} (lts/{codename}) reached end of life on {}",
+                    line.major, line.end
+                );
+            }
+            line.major
+        }
+        None => schedule
+            .iter()
+            .filter(|l| !l.lts.is_empty() && l.lts <= today && today < l.end)
+            .map(|l| l.major)
+            .max()
+            .ok_or_else(|| anyhow::anyhow!("no node release line is in LTS as of {today}"))?,
+    };
+    all.iter()
+        .find(|v| v.major == major && v.pre.is_empty())
+        .map(|v| Some(v.to_string()))
+        .ok_or_else(|| anyhow::anyhow!("no node {major}.x release found for {spec}"))
+}
+
+struct NodeLine {
+    major: u64,
+    lts: String,
+    end: String,
+    codename: String,
+}
+
+fn node_schedule() -> Result<Vec<NodeLine>> {
+    static SCHEDULE: Lazy<Mutex<Option<serde_json::Value>>> = Lazy::new(|| Mutex::new(None));
+    let mut cached = SCHEDULE.lock().unwrap();
+    if cached.is_none() {
+        let url = "https://raw.githubusercontent.com/nodejs/Release/main/schedule.json";
+        let resp = http::send(http::client()?.get(url).header("User-Agent", "tlk"))?;
+        if !resp.status().is_success() {
+            anyhow::bail!("fetching node release schedule {url}: {}", resp.status());
+        }
+        *cached = Some(resp.json()?);
+    }
+    let field = |line: &serde_json::Value, key: &str| {
+        line.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    // {"v20": {"start": "2023-04-18", "lts": "2023-10-24", "end": "2026-04-30", "codename": "Iron"}, ...}
+    let lines = cached
+        .as_ref()
+        .and_then(|v| v.as_object())
+        .map(|obj| {
+            obj.iter()
+                .filter_map(|(key, line)| {
+                    Some(NodeLine {
+                        major: key.strip_prefix('v')?.parse().ok()?,
+                        lts: field(line, "lts"),
+                        end: field(line, "end"),
+                        codename: field(line, "codename"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(lines)
+}
+
+fn fetch_remote_list(name: &str) -> Result<Vec<String>> {
+    Ok(match name {
         "terraform" => fetch_hashicorp_list("terraform")?,
         "helm" => fetch_github_list("helm", "helm")?,
         "gh" => fetch_github_list("cli", "cli")?,
@@ -36,21 +243,37 @@ pub fn fetch_all_versions(name: &str) -> Result<Vec<semver::Version>> {
         "cosign" => fetch_github_list("sigstore", "cosign")?,
         "age" => fetch_github_list("FiloSottile", "age")?,
         "moon" => fetch_github_list("moonrepo", "moon")?,
+        "firebase" => fetch_github_list("firebase", "firebase-tools")?,
+        "flyctl" => fetch_github_list("superfly", "flyctl")?,
+        "stripe" => fetch_github_list("stripe", "stripe-cli")?,
+        "supabase" => fetch_github_list("supabase", "cli")?,
+        "trivy" => fetch_github_list("aquasecurity", "trivy")?,
+        "grype" => fetch_github_list("anchore", "grype")?,
+        "syft" => fetch_github_list("anchore", "syft")?,
+        "hadolint" => fetch_github_list("hadolint", "hadolint")?,
+        "tflint" => fetch_github_list("terraform-linters", "tflint")?,
+        "terragrunt" => fetch_github_list("gruntwork-io", "terragrunt")?,
+        "checkov" => fetch_github_list("bridgecrewio", "checkov")?,
+        "protoc" => fetch_protoc_list()?,
+        "protoc-gen-go" => fetch_github_list("protocolbuffers", "protobuf-go")?,
+        "protoc-gen-grpc-web" => fetch_github_list("grpc", "grpc-web")?,
         _ => return Err(anyhow::anyhow!("version listing unsupported for {name}")),
-    };
-    let mut parsed: Vec<semver::Version> = raw
-        .into_iter()
-        .filter_map(|s| semver::Version::parse(&s).ok())
-        .collect();
-    parsed.sort_by(|a, b| b.cmp(a));
-    let mut cache = VERSION_CACHE.lock().unwrap();
-    cache.insert(name.to_string(), parsed.clone());
-    Ok(parsed)
+    })
 }
 
 fn fetch_hashicorp_list(tool: &str) -> Result<Vec<String>> {
     let url = format!("https://releases.hashicorp.com/{tool}/");
-    let body = reqwest::blocking::get(&url)?.text()?;
+    let body = match send_revalidated(http::client()?.get(&url), &url)? {
+        Fetched::Unchanged(body) => body,
+        Fetched::Fresh(resp) => {
+            let etag = etag_of(&resp);
+            let body = resp.error_for_status()?.text()?;
+            if let Some(etag) = etag {
+                crate::cache::store_validated(&url, &etag, &body);
+            }
+            body
+        }
+    };
     let re = Regex::new(&format!(r"/{tool}/([0-9]+\.[0-9]+\.[0-9]+)/"))?;
     let mut versions = Vec::new();
     for cap in re.captures_iter(&body) {
@@ -62,24 +285,18 @@ fn fetch_hashicorp_list(tool: &str) -> Result<Vec<String>> {
 }
 
 fn fetch_github_list(owner: &str, repo: &str) -> Result<Vec<String>> {
-    let url = format!("https://api.github.com/repos/{owner}/{repo}/releases?per_page=100");
-    let client = reqwest::blocking::Client::new();
-    let resp = client.get(url).header("User-Agent", "tlk").send()?;
-    let arr: serde_json::Value = resp.json()?;
     let mut out = Vec::new();
-    if let Some(items) = arr.as_array() {
-        for it in items {
-            if it
-                .get("prerelease")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false)
-            {
-                continue;
-            }
-            if let Some(tag) = it.get("tag_name").and_then(|v| v.as_str()) {
-                let norm = tag.trim_start_matches('v').to_string();
-                out.push(norm);
-            }
+    for it in github_releases(owner, repo)? {
+        if it
+            .get("prerelease")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        if let Some(tag) = it.get("tag_name").and_then(|v| v.as_str()) {
+            let norm = tag.trim_start_matches('v').to_string();
+            out.push(norm);
         }
     }
     out.sort();
@@ -87,27 +304,177 @@ fn fetch_github_list(owner: &str, repo: &str) -> Result<Vec<String>> {
     Ok(out)
 }
 
+// Release list of a GitHub repo.
+fn github_releases(owner: &str, repo: &str) -> Result<Vec<serde_json::Value>> {
+    let path = format!("repos/{owner}/{repo}/releases?per_page=100");
+    let what = format!("listing {owner}/{repo} releases");
+    match github_api(&path, &what, false)? {
+        Some(serde_json::Value::Array(items)) => Ok(items),
+        _ => Err(anyhow::anyhow!(
+            "GitHub API error {what}: unexpected response"
+        )),
+    }
+}
+
+/// SPDX id of a GitHub repo's license as GitHub detects it; None when the repo has no license
+/// file or GitHub cannot classify it ("NOASSERTION").
+pub fn github_license(owner: &str, repo: &str) -> Result<Option<String>> {
+    let what = format!("reading the {owner}/{repo} license");
+    let body = github_api(&format!("repos/{owner}/{repo}/license"), &what, true)?;
+    Ok(body
+        .as_ref()
+        .and_then(|b| b.pointer("/license/spdx_id"))
+        .and_then(|v| v.as_str())
+        .filter(|id| *id != "NOASSERTION")
+        .map(str::to_string))
+}
+
+enum Fetched {
+    Fresh(reqwest::blocking::Response),
+    /// 304 Not Modified: the body remembered with the ETag is still current.
+    Unchanged(String),
+}
+
+// Send `req` with If-None-Match when a response of `url` was remembered with its ETag. A 304
+// costs no body transfer (and no GitHub rate-limit quota), so a stale version list is refreshed
+// cheaply when upstream has not published anything since.
+fn send_revalidated(req: RequestBuilder, url: &str) -> Result<Fetched> {
+    let cached = crate::cache::validated(url);
+    let req = match &cached {
+        Some(c) => req.header("If-None-Match", &c.etag),
+        None => req,
+    };
+    let resp = http::send(req)?;
+    match cached {
+        Some(c) if resp.status() == reqwest::StatusCode::NOT_MODIFIED => {
+            Ok(Fetched::Unchanged(c.body))
+        }
+        _ => Ok(Fetched::Fresh(resp)),
+    }
+}
+
+fn etag_of(resp: &reqwest::blocking::Response) -> Option<String> {
+    resp.headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+// GET api.github.com/<path>. Rate-limit, auth and not-found responses come back as a JSON object
+// ({"message": ..., "documentation_url": ...}); turn those into an error carrying GitHub's
+// message and the quota headers instead of an empty result. `missing_ok` maps 404 to None.
+fn github_api(path: &str, what: &str, missing_ok: bool) -> Result<Option<serde_json::Value>> {
+    let url = format!("https://api.github.com/{path}");
+    let client = http::client()?;
+    let resp = match send_revalidated(github_request(&client, &url), &url)? {
+        Fetched::Unchanged(body) => return Ok(Some(serde_json::from_str(&body)?)),
+        Fetched::Fresh(resp) => resp,
+    };
+    let status = resp.status();
+    let header = |name: &str| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let remaining = header("x-ratelimit-remaining");
+    let limit = header("x-ratelimit-limit");
+    let reset = header("x-ratelimit-reset")
+        .and_then(|v| v.parse::<i64>().ok())
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0));
+    if missing_ok && status == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let etag = etag_of(&resp);
+    let text = resp.text()?;
+    let body: serde_json::Value = serde_json::from_str(&text).map_err(|e| {
+        anyhow::anyhow!("GitHub API {url} returned {status} with an unreadable body: {e}")
+    })?;
+    if status.is_success() {
+        if let Some(etag) = etag {
+            crate::cache::store_validated(&url, &etag, &text);
+        }
+        return Ok(Some(body));
+    }
+    let message = body
+        .get("message")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unexpected response");
+    let mut err = format!("GitHub API error {what} ({status}): {message}");
+    if let (Some(remaining), Some(limit)) = (&remaining, &limit) {
+        err.push_str(&format!(
+            "\n  rate limit: {remaining}/{limit} requests remaining"
+        ));
+        if let Some(reset) = reset {
+            let local: chrono::DateTime<chrono::Local> = reset.into();
+            err.push_str(&format!(
+                ", resets at {}",
+                local.format("%Y-%m-%d %H:%M:%S")
+            ));
+        }
+    }
+    if let Some(doc) = body.get("documentation_url").and_then(|v| v.as_str()) {
+        err.push_str(&format!("\n  see {doc}"));
+    }
+    let rate_limited = remaining.as_deref() == Some("0")
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || message.to_ascii_lowercase().contains("rate limit");
+    let hint = if rate_limited && github_token().is_none() {
+        Some(
+            "set GITHUB_TOKEN or GH_TOKEN to raise the limit, wait for the reset, pin an exact \
+             version in tlk.toml (no lookup needed), or resolve against a snapshot from `tlk \
+             registry mirror` via TLK_REGISTRY",
+        )
+    } else if rate_limited {
+        Some(
+            "wait for the reset, pin an exact version in tlk.toml (no lookup needed), or resolve \
+             against a snapshot from `tlk registry mirror` via TLK_REGISTRY",
+        )
+    } else if status == reqwest::StatusCode::UNAUTHORIZED && github_token().is_some() {
+        Some("the GitHub token was rejected; check that it is valid and not expired")
+    } else if status == reqwest::StatusCode::UNAUTHORIZED {
+        Some("the request was rejected as unauthenticated; check proxy or credential settings")
+    } else if status == reqwest::StatusCode::NOT_FOUND {
+        Some("the repository may have moved or been renamed")
+    } else {
+        None
+    };
+    if let Some(hint) = hint {
+        err.push_str(&format!("\n  hint: {hint}"));
+    }
+    Err(anyhow::anyhow!(err))
+}
+
+// protoc releases are tagged v25.1 (two parts since 21.0, v3.20.3 before); two-part tags are
+// padded to semver here and the recipe drops the ".0" again when rendering the URL.
+fn fetch_protoc_list() -> Result<Vec<String>> {
+    let mut out: Vec<String> = fetch_github_list("protocolbuffers", "protobuf")?
+        .into_iter()
+        .map(|v| match v.split('.').count() {
+            2 => format!("{v}.0"),
+            _ => v,
+        })
+        .collect();
+    out.sort();
+    out.dedup();
+    Ok(out)
+}
+
 // jq tags look like jq-1.7.1 (sometimes without standard semver prefix handling)
 fn fetch_jq_list() -> Result<Vec<String>> {
-    let url = "https://api.github.com/repos/jqlang/jq/releases?per_page=100";
-    let client = reqwest::blocking::Client::new();
-    let resp = client.get(url).header("User-Agent", "tlk").send()?;
-    let arr: serde_json::Value = resp.json()?;
     let mut out = Vec::new();
-    if let Some(items) = arr.as_array() {
-        for it in items {
-            if it
-                .get("prerelease")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false)
-            {
-                continue;
-            }
-            if let Some(tag) = it.get("tag_name").and_then(|v| v.as_str()) {
-                // tag form: jq-1.7.1
-                if let Some(stripped) = tag.strip_prefix("jq-") {
-                    out.push(stripped.to_string());
-                }
+    for it in github_releases("jqlang", "jq")? {
+        if it
+            .get("prerelease")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        if let Some(tag) = it.get("tag_name").and_then(|v| v.as_str()) {
+            // tag form: jq-1.7.1
+            if let Some(stripped) = tag.strip_prefix("jq-") {
+                out.push(stripped.to_string());
             }
         }
     }
@@ -0,0 +1,133 @@
+// install -> receipt -> uninstall, run against a fixture directory so nothing touches the network.
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+const CONFIG: &str = r#"[tools.foo]
+version = "1.0.0"
+kind = "direct"
+source = "http://fixture.test/foo-{version}"
+"#;
+
+fn tlk(project: &Path, fixtures: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_tlk"))
+        .args(args)
+        .current_dir(project)
+        .env("HOME", project)
+        .env("TLK_CACHE_DIR", project.join("cache"))
+        .env("TLK_FIXTURE_DIR", fixtures)
+        .env("TLK_NO_AUTO_PATH", "1")
+        .output()
+        .expect("running tlk")
+}
+
+fn setup() -> (tempfile::TempDir, tempfile::TempDir) {
+    let project = tempfile::tempdir().unwrap();
+    let fixtures = tempfile::tempdir().unwrap();
+    let download = fixtures.path().join("downloads/fixture.test/foo-1.0.0");
+    fs::create_dir_all(download.parent().unwrap()).unwrap();
+    fs::write(&download, "#!/bin/sh\necho foo 1.0.0\n").unwrap();
+    fs::set_permissions(&download, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(project.path().join("tlk.toml"), CONFIG).unwrap();
+    (project, fixtures)
+}
+
+#[test]
+fn uninstall_removes_what_the_receipt_lists() {
+    let (project, fixtures) = setup();
+    let out = tlk(project.path(), fixtures.path(), &["install"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let binary = project.path().join(".tlk/bin/foo");
+    let receipt = project.path().join(".tlk/receipts/foo.json");
+    assert!(binary.is_file());
+    let recorded: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&receipt).unwrap()).unwrap();
+    assert_eq!(recorded["version"], "1.0.0");
+    assert_eq!(recorded["source"], "http://fixture.test/foo-1.0.0");
+    let files = recorded["files"].as_array().unwrap();
+    assert_eq!(files.len(), 1);
+    assert!(Path::new(files[0].as_str().unwrap()).ends_with(".tlk/bin/foo"));
+
+    let out = tlk(project.path(), fixtures.path(), &["uninstall", "foo"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(!binary.exists());
+    assert!(!receipt.exists());
+    assert!(!fs::read_to_string(project.path().join("tlk.toml"))
+        .unwrap()
+        .contains("[tools.foo]"));
+}
+
+#[test]
+fn uninstall_refuses_receipt_paths_outside_the_install_dirs() {
+    let (project, fixtures) = setup();
+    let out = tlk(project.path(), fixtures.path(), &["install"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let outside = project.path().join("keep");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("data"), "precious").unwrap();
+    let receipt = project.path().join(".tlk/receipts/foo.json");
+    let mut recorded: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&receipt).unwrap()).unwrap();
+    recorded["dirs"] = serde_json::json!([project.path().join(".tlk/bin/../../keep")]);
+    fs::write(&receipt, recorded.to_string()).unwrap();
+
+    let out = tlk(project.path(), fixtures.path(), &["uninstall", "foo"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(!out.status.success());
+    assert!(
+        stderr.contains("outside the tool's install directories"),
+        "{stderr}"
+    );
+    assert!(outside.join("data").is_file());
+    assert!(project.path().join(".tlk/bin/foo").is_file());
+}
+
+#[test]
+fn rename_moves_the_receipt() {
+    let (project, fixtures) = setup();
+    let out = tlk(project.path(), fixtures.path(), &["install"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+
+    let out = tlk(project.path(), fixtures.path(), &["rename", "foo", "bar"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let receipts = project.path().join(".tlk/receipts");
+    assert!(!receipts.join("foo.json").exists());
+    let recorded: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(receipts.join("bar.json")).unwrap()).unwrap();
+    assert_eq!(recorded["tool"], "bar");
+    let files = recorded["files"].as_array().unwrap();
+    assert!(Path::new(files[0].as_str().unwrap()).ends_with(".tlk/bin/bar"));
+
+    let out = tlk(project.path(), fixtures.path(), &["uninstall", "bar"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(!project.path().join(".tlk/bin/bar").exists());
+}